@@ -102,6 +102,12 @@ fn sum_primes() -> i64 {
     run_expect_value!(source, Int)
 }
 
+#[cfg(feature = "wasmer")]
+fn wasm_pipeline(source: &str) -> String {
+    let (bytes, typ) = wasmize(source, Env::default()).unwrap();
+    run_wasm(&bytes, typ).unwrap()
+}
+
 fn criterion_taylor_series(c: &mut Criterion) {
     let mut group = c.benchmark_group("taylor_series");
     group.sample_size(50);
@@ -133,5 +139,30 @@ fn criterion_primes(c: &mut Criterion) {
     group.finish();
 }
 
+// compares a numeric pipeline using function literals, which are inlined, to one using named functions
+#[cfg(feature = "wasmer")]
+fn criterion_wasm_inline(c: &mut Criterion) {
+    let inlined = "sum(|x: Int| { x * 3 + 1 } -> filter(|x: Int| { x / 2 * 2 = x }, 1 to 30000))";
+    let called = "
+    f := |x: Int| { x * 3 + 1 }
+    g := |x: Int| { x / 2 * 2 = x }
+    sum(f -> filter(g, 1 to 30000))
+    ";
+    let mut group = c.benchmark_group("wasm_inline");
+    group.sample_size(20);
+    group.bench_function(
+        "inlined",
+        move |b| b.iter(|| wasm_pipeline(inlined))
+    );
+    group.bench_function(
+        "called",
+        move |b| b.iter(|| wasm_pipeline(called))
+    );
+    group.finish();
+}
+
+#[cfg(not(feature = "wasmer"))]
 criterion_group!(benches, criterion_taylor_series, criterion_fibonacci, criterion_primes);
+#[cfg(feature = "wasmer")]
+criterion_group!(benches, criterion_taylor_series, criterion_fibonacci, criterion_primes, criterion_wasm_inline);
 criterion_main!(benches);
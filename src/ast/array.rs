@@ -1,10 +1,13 @@
 use crate::chunk::OpCode;
+use crate::values::Value;
 
 use super::*;
 
 #[derive(Debug)]
 enum ArrayElems {
     Empty(TypeAnnotation),
+    // empty array whose type is given by a type ascription, as in `([]): Arr(Int)`
+    Untyped,
     Elements(Vec<Box<dyn Expression>>),
 }
 
@@ -21,27 +24,56 @@ impl Array {
     pub fn new_empty(typ: TypeAnnotation) -> Result<Self, String> {
         Ok(Self { elements: ArrayElems::Empty(typ), parent: None })
     }
+    pub fn new_untyped() -> Self {
+        Self { elements: ArrayElems::Untyped, parent: None }
+    }
+
+    // the length of an array that can be known without evaluating it,
+    // i.e., one made only of literals (or nested arrays of literals), so skipping evaluation can't skip side effects
+    pub fn static_len(&self) -> Option<usize> {
+        match &self.elements {
+            ArrayElems::Empty(_) | ArrayElems::Untyped => Some(0),
+            ArrayElems::Elements(elems) => {
+                let is_static = elems.iter().all(|e| {
+                    e.downcast_ref::<Literal>().is_some()
+                        || e.downcast_ref::<Array>().and_then(|a| a.static_len()).is_some()
+                });
+                is_static.then_some(elems.len())
+            }
+        }
+    }
+
+    fn ascribed_type(&self) -> Result<Type, CompileError> {
+        let ascription = self.parent
+            .and_then(|p| unsafe { &*p }.downcast_ref::<TypeAscription>());
+        match ascription.map(|a| a.ascribed_type()) {
+            Some(Ok(t @ Type::Arr(_))) => Ok(t),
+            Some(Err(e)) => Err(e),
+            _ => Err(type_error("Empty arrays must be annoted with a type".to_string())),
+        }
+    }
 }
 
 impl Expression for Array {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         match &self.elements {
             ArrayElems::Empty(t) => Ok(Type::Arr(Box::new(t.get_type()?))),
+            ArrayElems::Untyped => self.ascribed_type(),
             ArrayElems::Elements(elems) => {
                 let first_type = elems[0].get_type()?;
                 for elem in elems.iter() {
                     let elem_type = elem.get_type()?;
                     if elem_type != first_type {
-                        return Err(format!(
+                        return Err(type_error(format!(
                             "Array elements have different types: {:?} and {:?}", first_type, elem_type
-                        ));
+                        )));
                     }
                 };
                 Ok(Type::Arr(Box::new(first_type)))
             }
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         match &mut self.elements {
@@ -53,6 +85,7 @@ impl Expression for Array {
             ArrayElems::Empty(t) => {
                 t.set_parent(Some(self_ptr))?;
             },
+            ArrayElems::Untyped => (),
         }
         Ok(())
     }
@@ -60,7 +93,7 @@ impl Expression for Array {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let len = match &self.elements {
             ArrayElems::Elements(elems) => {
                 for elem in elems.iter() {
@@ -68,7 +101,7 @@ impl Expression for Array {
                 }
                 elems.len() as u16
             },
-            ArrayElems::Empty(_) => 0,
+            ArrayElems::Empty(_) | ArrayElems::Untyped => 0,
         };
         let typ = match self.get_type()? {
             Type::Arr(t) => t,
@@ -90,7 +123,7 @@ impl Expression for Array {
                 }
                 elems.len() as u16
             },
-            ArrayElems::Empty(_) => 0,
+            ArrayElems::Empty(_) | ArrayElems::Untyped => 0,
         };
         let typ = match self.get_type()? {
             Type::Arr(t) => t,
@@ -101,6 +134,66 @@ impl Expression for Array {
     }
 }
 
+#[derive(Debug)]
+pub struct ArrayRepeat {
+    value: Box<dyn Expression>,
+    count: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ArrayRepeat {
+    pub fn new(value: Box<dyn Expression>, count: Box<dyn Expression>) -> Self {
+        Self { value, count, parent: None }
+    }
+
+    // the length must be known at compile time, so only Int literals are accepted
+    fn len(&self) -> Result<u16, CompileError> {
+        let count = match self.count.downcast_ref::<Literal>().and_then(|lit| lit.int_value()) {
+            Some(count) => count,
+            None => return Err(
+                type_error("Length of repeated array must be a constant Int".to_string())
+            ),
+        };
+        u16::try_from(count).map_err(|_| other_error(format!(
+            "Length of repeated array must be between 0 and {}; got {}", u16::MAX, count
+        )))
+    }
+}
+
+impl Expression for ArrayRepeat {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        self.len()?;
+        Ok(Type::Arr(Box::new(self.value.get_type()?)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.value.set_parent(Some(self_ptr))?;
+        self.count.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let len = self.len()?;
+        self.value.compile(compiler)?;
+        if self.value.get_type()?.is_heap() {
+            compiler.write_array_heap_repeat(len)
+        }
+        else {
+            compiler.write_array_repeat(len)
+        }
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let len = self.len()?;
+        self.value.wasmize(wasmizer)?;
+        wasmizer.write_array_repeat(len, &self.value.get_type()?)?;
+        Ok(0)
+    }
+}
+
 
 #[derive(Debug)]
 pub struct Len {
@@ -112,18 +205,27 @@ impl Len {
     pub fn new(expr: Box<dyn Expression>) -> Self {
         Self { expr, parent: None }
     }
+
+    // the length of a literal array, which can be folded into a constant
+    fn static_len(&self) -> Option<usize> {
+        let expr = match self.expr.downcast_ref::<TypeAscription>() {
+            Some(ascription) => ascription.expr(),
+            None => self.expr.as_ref(),
+        };
+        expr.downcast_ref::<Array>().and_then(|a| a.static_len())
+    }
 }
 
 impl Expression for Len {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         match self.expr.get_type()? {
             Type::Arr(_) | Type::Iter(_) | Type::Str => Ok(Type::Int),
-            x => Err(format!(
+            x => Err(type_error(format!(
                 "Len expression must be an array, iterator, or string; got a {:?}", x
-            )),
+            ))),
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.expr.set_parent(Some(self_ptr))
@@ -132,8 +234,11 @@ impl Expression for Len {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         self.get_type()?;  // just to check that type is valid
+        if let Some(len) = self.static_len() {
+            return compiler.write_constant(Value::from_i64(len as i64));
+        }
         self.expr.compile(compiler)?;
         compiler.write_opcode(OpCode::Len);
         Ok(())
@@ -141,6 +246,10 @@ impl Expression for Len {
 
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
         let expr_type = self.expr.get_type()?;
+        if let Some(len) = self.static_len() {
+            wasmizer.write_const(&len.to_string(), &Type::Int)?;
+            return Ok(0);
+        }
         self.expr.wasmize(wasmizer)?;
         wasmizer.write_len(&expr_type)?;
         Ok(0)
@@ -12,29 +12,37 @@ impl Assignment {
         Self { name, value, parent: None }
     }
 
-    fn handle_recursive_def(&self) -> Result<Type, String> {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_function_def(&self) -> bool {
+        self.value.is::<Function>()
+    }
+
+    fn handle_recursive_def(&self) -> Result<Type, CompileError> {
         // recursive definition, not allowed except for annotated functions
         return match self.value.downcast_ref::<Function>() {
             Some(f) => match f.explicit_type()? {
                 Some(t) => Ok(t),
-                None => Err(format!(
+                None => Err(type_error(format!(
                     "Variable {} is defined recursively. This is allowed for functions, but the function must have an explicit return type annotation",
                     self.name
-                ))
+                )))
             },
-            None => Err(format!(
+            None => Err(type_error(format!(
                 "Variable {} is defined recursively, which is not allowed for non-function types or functions with no arguments",
                 self.name
-            )),
+            ))),
         }
     } 
 }
 
 impl Expression for Assignment {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         self.value.get_type()
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.value.set_parent(Some(self_ptr))
@@ -42,16 +50,16 @@ impl Expression for Assignment {
     fn get_parent(&self) -> Option<*const dyn Expression> {
         self.parent
     }
-    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         let name_truncated = truncate_template_types(name);
         let no_template = name_truncated == name;
         if no_template {
             if &self.name == name {
                 if self.value.as_ref() as *const _ as *const () == upto as *const () {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Variable {} is defined recursively, which is not allowed for non-function types or functions with no arguments",
                         self.name
-                    ));
+                    )));
                 }
                 else {
                     return Ok(Some(self.value.get_type()?));
@@ -82,7 +90,7 @@ impl Expression for Assignment {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let typ = self.value.get_type()?;
         let name = match &typ {
             Type::Func(paramtypes, _) => if paramtypes.is_empty() {
@@ -19,10 +19,41 @@ impl Binary {
             parent: None,
         })
     }
+
+    // the type of `arr + iter` or `iter + arr`, which concatenates into an array after collecting the iterator
+    fn mixed_concat_type(&self) -> Result<Option<Type>, CompileError> {
+        if self.op != TokenType::Plus {
+            return Ok(None);
+        }
+        match (self.left.get_type()?, self.right.get_type()?) {
+            (Type::Arr(l), Type::Iter(r)) | (Type::Iter(l), Type::Arr(r)) if l == r => Ok(Some(Type::Arr(l))),
+            _ => Ok(None),
+        }
+    }
+
+    // compiles an equality test or concatenation where one or both operands are iterators,
+    // which are collected into arrays of the given type
+    fn compile_collected(&self, compiler: &mut Compiler, arr_type: Type) -> Result<(), CompileError> {
+        for operand in [&self.left, &self.right] {
+            operand.compile(compiler)?;
+            if matches!(operand.get_type()?, Type::Iter(_)) {
+                compiler.write_opcode(OpCode::Collect);
+            }
+        }
+        let is_float = arr_type == Type::Arr(Box::new(Type::Float));
+        compiler.write_opcode(match (self.op, is_float) {
+            (TokenType::Plus, _) => OpCode::Concat,
+            (TokenType::Eq, true) => OpCode::FloatArrEqual,
+            (TokenType::NEq, true) => OpCode::FloatArrNotEqual,
+            (TokenType::Eq, false) => OpCode::HeapEqual,
+            _ => OpCode::HeapNotEqual,
+        });
+        Ok(())
+    }
 }
 
 impl Expression for Binary {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         match self.op {
             TokenType::Eq
             | TokenType::NEq
@@ -31,10 +62,13 @@ impl Expression for Binary {
             | TokenType::GT
             | TokenType::LT => Ok(Type::Bool),
             TokenType::To => Ok(Type::Iter(Box::new(Type::Int))),
-            _ => self.left.get_type(),
+            _ => match self.mixed_concat_type()? {
+                Some(t) => Ok(t),
+                None => self.left.get_type(),
+            },
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.left.set_parent(Some(self_ptr))?;
@@ -45,15 +79,28 @@ impl Expression for Binary {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let left_type = self.left.get_type()?;
         let right_type = self.right.get_type()?;
 
+        // iterators are compared to each other or to arrays by collecting them into arrays first,
+        // so comparing with an infinite iterator won't terminate (unless the VM has a collect limit)
+        if matches!(self.op, TokenType::Eq | TokenType::NEq) {
+            if let (Type::Arr(l) | Type::Iter(l), Type::Arr(r) | Type::Iter(r)) = (&left_type, &right_type) {
+                if l == r && (matches!(left_type, Type::Iter(_)) || matches!(right_type, Type::Iter(_))) {
+                    return self.compile_collected(compiler, Type::Arr(l.clone()));
+                }
+            }
+        }
+        if let Some(arr_type) = self.mixed_concat_type()? {
+            return self.compile_collected(compiler, arr_type);
+        }
+
         if left_type != right_type {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Operands for operator {:?} must be of the same type; got {:?} and {:?}",
                 self.op, left_type, right_type
-            ));
+            )));
         }
         self.left.compile(compiler)?;
         self.right.compile(compiler)?;
@@ -71,11 +118,12 @@ impl Expression for Binary {
                     TokenType::Minus => OpCode::IntSubtract,
                     TokenType::Star => OpCode::IntMultiply,
                     TokenType::Slash => OpCode::IntDivide,
+                    TokenType::Percent => OpCode::IntMod,
                     TokenType::To => OpCode::To,
-                    x => return Err(format!(
+                    x => return Err(type_error(format!(
                         "Operator {:?} not supported for type {:?}",
                         x, left_type
-                    )),
+                    ))),
                 })
             },
             Type::Float => {
@@ -90,10 +138,10 @@ impl Expression for Binary {
                     TokenType::Minus => OpCode::FloatSubtract,
                     TokenType::Star => OpCode::FloatMultiply,
                     TokenType::Slash => OpCode::FloatDivide,
-                    x => return Err(format!(
+                    x => return Err(type_error(format!(
                         "Operator {:?} not supported for type {:?}",
                         x, left_type
-                    ))
+                    )))
                 })
             },
             Type::Bool => {
@@ -102,10 +150,11 @@ impl Expression for Binary {
                     TokenType::NEq => OpCode::BoolNotEqual,
                     TokenType::And => OpCode::And,
                     TokenType::Or => OpCode::Or,
-                    x => return Err(format!(
+                    TokenType::Xor => OpCode::BoolNotEqual,
+                    x => return Err(type_error(format!(
                         "Operator {:?} not supported for type {:?}",
                         x, left_type
-                    ))
+                    )))
                 })
             },
             Type::Str => {
@@ -113,26 +162,30 @@ impl Expression for Binary {
                     TokenType::Eq => OpCode::HeapEqual,
                     TokenType::NEq => OpCode::HeapNotEqual,
                     TokenType::Plus => OpCode::Concat,
-                    x => return Err(format!(
+                    x => return Err(type_error(format!(
                         "Operator {:?} not supported for type {:?}",
                         x, left_type
-                    ))
+                    )))
                 })
             },
             Type::Arr(t) => {
+                let is_float = t.as_ref() == &Type::Float;
                 compiler.write_opcode(match self.op {
+                    // floats are compared by value, not bitwise
+                    TokenType::Eq if is_float => OpCode::FloatArrEqual,
+                    TokenType::NEq if is_float => OpCode::FloatArrNotEqual,
                     TokenType::Eq => OpCode::HeapEqual,
                     TokenType::NEq => OpCode::HeapNotEqual,
                     TokenType::Plus => OpCode::Concat,
-                    x => return Err(format!(
+                    x => return Err(type_error(format!(
                         "Operator {:?} not supported for type {:?}",
                         x, t
-                    ))
+                    )))
                 })
             },
-            x => return Err(format!(
+            x => return Err(type_error(format!(
                 "Type {:?} not yet supported for binary operation", x
-            ))
+            )))
         };
         Ok(())
     }
@@ -161,11 +214,16 @@ impl Expression for Binary {
             TokenType::Minus => wasmizer.write_sub(&left_type),
             TokenType::Star => wasmizer.write_mul(&left_type),
             TokenType::Slash => wasmizer.write_div(&left_type),
+            TokenType::Percent => wasmizer.write_mod(&left_type),
             TokenType::And => wasmizer.write_and(&left_type),
             TokenType::Or => wasmizer.write_or(&left_type),
+            TokenType::Xor => wasmizer.write_xor(&left_type),
             TokenType::To => wasmizer.write_range(&left_type),
             _ => return Err(format!("Operator {:?} not supported", self.op)),
         }?;
         Ok(0)
     }
+    fn is_inlinable(&self, param: &str) -> bool {
+        self.op != TokenType::To && self.left.is_inlinable(param) && self.right.is_inlinable(param)
+    }
 }
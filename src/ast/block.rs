@@ -16,6 +16,10 @@ impl Block {
         Ok(Self { expressions, parent: None })
     }
 
+    pub fn expressions(&self) -> &[Box<dyn Expression>] {
+        &self.expressions
+    }
+
     // get the number of functions defined within this block
     pub fn count_function_chidren(&self) -> usize {
         let mut count = 0;
@@ -33,10 +37,10 @@ impl Block {
 }
 
 impl Expression for Block {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         self.expressions.last().unwrap().get_type()
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         for e in self.expressions.iter_mut() {
@@ -47,7 +51,7 @@ impl Expression for Block {
     fn get_parent(&self) -> Option<*const dyn Expression> {
         self.parent
     }
-    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         for e in self.expressions.iter() {
             if e.as_ref() as *const _ as *const () == upto as *const () {
                 break;
@@ -59,7 +63,7 @@ impl Expression for Block {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         compiler.begin_scope();
         for e in self.expressions.iter().take(self.expressions.len() - 1) {
             e.compile(compiler)?;
@@ -87,4 +91,10 @@ impl Expression for Block {
         wasmizer.end_scope()?;
         Ok(0)
     }
+    fn is_inlinable(&self, param: &str) -> bool {
+        self.expressions.len() == 1 && self.expressions[0].is_inlinable(param)
+    }
+    fn has_tail_call_to(&self, name: &str) -> bool {
+        self.expressions.last().is_some_and(|e| e.has_tail_call_to(name))
+    }
 }
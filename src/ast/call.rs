@@ -14,36 +14,36 @@ impl Call {
         Ok(Self { callee, args, parent: None })
     }
 
-    fn argtypes(&self) -> Result<Vec<Type>, String> {
+    fn argtypes(&self) -> Result<Vec<Type>, CompileError> {
         self.args.iter().map(|e| e.get_type()).collect()
     }
 
-    fn validate(&self) -> Result<Type, String> {
+    fn validate(&self) -> Result<Type, CompileError> {
         let callee_type = self.callee.get_type()?;
         let paramtypes = match callee_type.clone() {
             Type::Func(argtypes, _) => argtypes,
             Type::Arr(_) => vec![Type::Int],
             Type::TypeDef(argtypes, _) => argtypes,
-            _ => return Err(format!(
+            _ => return Err(type_error(format!(
                 "Cannot call an expression of type {:?}", callee_type
-            )),
+            ))),
         };
         if paramtypes.len() != self.args.len() {
-            return Err(format!("Wrong number of arguments; expected {} but got {}", paramtypes.len(), self.args.len()));
+            return Err(type_error(format!("Wrong number of arguments; expected {} but got {}", paramtypes.len(), self.args.len())));
         }
         let argtypes = self.args.iter().map(|e| e.get_type()).collect::<Result<Vec<_>, _>>()?;
         if paramtypes.iter().zip(argtypes.iter()).any(|(a, b)| a != b) {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Argument types do not match; expected {:?} but got {:?}",
                 paramtypes, argtypes
-            ));
+            )));
         }
         Ok(callee_type)
     }
 }
 
 impl Expression for Call {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         match self.callee.get_type() {
             Ok(Type::Func(_, return_type)) => {
                 Ok(*return_type)
@@ -51,16 +51,14 @@ impl Expression for Call {
             Ok(Type::Arr(typ)) => Ok(*typ),
             Ok(Type::TypeDef(_, typ)) => Ok(*typ),
             Ok(ctype) => {
-                Err(format!(
+                Err(type_error(format!(
                     "Tried to call an expression of type {:?}, which is not callable", ctype
-                ))
+                )))
             },
-            Err(e) => Err(format!(
-                "Unable to resolve type of call: {}", e
-            ))
+            Err(e) => Err(e.context("Unable to resolve type of call")),
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         for e in self.args.iter_mut() {
@@ -87,7 +85,7 @@ impl Expression for Call {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         self.validate()?;
         for arg in self.args.iter() {
             arg.compile(compiler)?;
@@ -123,4 +121,7 @@ impl Expression for Call {
         }
         Ok(0)
     }
+    fn has_tail_call_to(&self, name: &str) -> bool {
+        self.callee.downcast_ref::<Variable>().is_some_and(|v| v.get_name() == name)
+    }
 }
@@ -17,7 +17,7 @@ impl Function {
         Self { name, params, block, rtype, parent: None }
     }
 
-    fn param_types(&self) -> Result<Vec<Type>, String> {
+    fn param_types(&self) -> Result<Vec<Type>, CompileError> {
         let mut param_types = Vec::new();
         for p in self.params.iter() {
             param_types.push(p.get_type()?);
@@ -25,7 +25,7 @@ impl Function {
         Ok(param_types)
     }
 
-    pub fn explicit_type(&self) -> Result<Option<Type>, String> {
+    pub fn explicit_type(&self) -> Result<Option<Type>, CompileError> {
         let return_type = match &self.rtype {
             None => return Ok(None),
             Some(rtype) => rtype.get_type()?,
@@ -34,10 +34,28 @@ impl Function {
         Ok(Some(Type::Func(param_types, Box::new(return_type))))
     }
 
-    pub fn get_expanded_name(&self) -> Result<String, String> {
+    pub fn get_expanded_name(&self) -> Result<String, CompileError> {
         Ok(format!("{}{:?}", self.name, self.param_types()?))
     }
 
+    // if this function is simple enough to be inlined where it is used in wasm,
+    // get the name of its single parameter and the body that should be inlined
+    pub fn inlinable_body(&self) -> Option<(&str, &dyn Expression)> {
+        if self.params.len() != 1 {
+            return None;
+        }
+        let param = &self.params[0];
+        if !matches!(param.get_type(), Ok(Type::Int | Type::Float | Type::Bool)) {
+            return None;
+        }
+        if self.block.is_inlinable(&param.name) {
+            Some((&param.name, self.block.as_ref()))
+        }
+        else {
+            None
+        }
+    }
+
     // get the number of functions defined within this function
     pub fn count_function_chidren(&self) -> usize {
         let block = self.block.downcast_ref::<Block>().unwrap();
@@ -46,18 +64,18 @@ impl Function {
 }
 
 impl Expression for Function {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let param_types = self.param_types()?;
         let return_type = self.block.get_type()?;
         if let Some(rtype) = &self.rtype {
             let rtype = rtype.get_type()?;
             if rtype != return_type {
-                return Err(format!("Function return type {:?} does not match type {:?} specified in type annotation", return_type, rtype));
+                return Err(type_error(format!("Function return type {:?} does not match type {:?} specified in type annotation", return_type, rtype)));
             }
         }
         Ok(Type::Func(param_types, Box::new(return_type)))
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         if let Some(rtype) = &mut self.rtype {
@@ -72,7 +90,7 @@ impl Expression for Function {
     fn get_parent(&self) -> Option<*const dyn Expression> {
         self.parent
     }
-    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         // vartypes in block should have been already processed, since block is a child of function
         let has_template_types = name.contains('[');
         for p in self.params.iter() {
@@ -97,7 +115,7 @@ impl Expression for Function {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let mut inner_compiler = Compiler::new_from(compiler);
         let (ptypes, rtype) = match self.get_type()? {
             Type::Func(ptypes, rtype) => (ptypes, *rtype),
@@ -114,6 +132,7 @@ impl Expression for Function {
         inner_compiler.function.arity = self.params.len() as u8 - heap_arity;
         inner_compiler.function.heap_arity = heap_arity;
         inner_compiler.function.return_is_heap = rtype.is_heap();
+        inner_compiler.function.is_tail_recursive = self.block.has_tail_call_to(&self.name);
         inner_compiler.function.name = format!("{}{:?}", self.name, self.param_types()?);
         
         for param in self.params.iter() {
@@ -126,9 +145,9 @@ impl Expression for Function {
                 _ => param.name.clone()
             };
             if inner_compiler.create_variable(param_name, &param_type)?.is_some() {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Function parameters should be in local scope"
-                ));
+                )));
             }
         }
 
@@ -1,4 +1,4 @@
-use crate::{chunk::OpCode, values::Value};
+use crate::{chunk::{KeyType, OpCode}, values::Value, wasmizer::structs::Struct};
 
 use super::*;
 
@@ -19,56 +19,55 @@ impl Map {
     }
 
     // Get the types associated with this expression
-    // Returns the inner type of the result, the inner type of the object iterated over, and if that object is an array
-    // Return format is (result_inner_type, input_inner_type, is_array)
-    fn get_type_info(&self) -> Result<(Type, Type, bool), String> {
+    // Returns the inner type of the result and the inner type of the object iterated over
+    // Return format is (result_inner_type, input_inner_type)
+    fn get_type_info(&self) -> Result<(Type, Type), CompileError> {
         let left_type = self.left.get_type()?;
         let right_type = self.right.get_type()?;
 
-        let (input_inner_type, input_is_array) = match &right_type {
-            Type::Iter(arr_type) => (*arr_type.clone(), false),
-            Type::Arr(arr_type) => (*arr_type.clone(), true),
+        let input_inner_type = match &right_type {
+            Type::Iter(arr_type) | Type::Arr(arr_type) => *arr_type.clone(),
             _ => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Operand on right of '->' must be an iterator or array type; got {:?}",
                     right_type
-                ));
+                )));
             }
         };
 
         let result_inner_type = match &left_type {
             Type::Arr(result_type) => {
                 if input_inner_type != Type::Int {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Cannot map from type {:?} using non-integer type {:?}",
                         left_type, input_inner_type
-                    ));
+                    )));
                 }
                 *result_type.clone()
             }
             Type::Func(arg_type, result_type) => {
                 if arg_type.len() != 1 {
-                    return Err(format!("Cannot map with a function that does not have a single argument; got a function with {} arguments", arg_type.len()));
+                    return Err(type_error(format!("Cannot map with a function that does not have a single argument; got a function with {} arguments", arg_type.len())));
                 }
                 if arg_type[0] != input_inner_type {
-                    return Err(format!("Function used for mapping must have an argument of type {:?} to match the array mapped over; got {:?}", input_inner_type, arg_type[0]));
+                    return Err(type_error(format!("Function used for mapping must have an argument of type {:?} to match the array mapped over; got {:?}", input_inner_type, arg_type[0])));
                 }
                 *result_type.clone()
             }
-            typ => return Err(format!("Cannot map with type {:?}", typ)),
+            typ => return Err(type_error(format!("Cannot map with type {:?}", typ))),
         };
 
-        Ok((result_inner_type, input_inner_type, input_is_array))
+        Ok((result_inner_type, input_inner_type))
     }
 }
 
 impl Expression for Map {
-    fn get_type(&self) -> Result<Type, String> {
-        let (result_inner_type, _, _) = self.get_type_info()?;
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (result_inner_type, _) = self.get_type_info()?;
         Ok(Type::Iter(Box::new(result_inner_type)))
     }
 
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.left.set_parent(Some(self_ptr))?;
@@ -78,10 +77,10 @@ impl Expression for Map {
         let rtype = match self.right.get_type()? {
             Type::Iter(t) | Type::Arr(t) => *t,
             _ => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Cannot use '->' with type {:?} on right",
                     self.right.get_type()?
-                ))
+                )))
             }
         };
         if let Some(var) = self.left.downcast_mut::<Variable>() {
@@ -98,7 +97,7 @@ impl Expression for Map {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         self.get_type_info()?; // check that types are all in order
         self.left.compile(compiler)?;
         self.right.compile(compiler)?;
@@ -108,10 +107,16 @@ impl Expression for Map {
     }
 
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
-        let (result_inner_type, input_inner_type, input_is_array) = self.get_type_info()?;
+        let (result_inner_type, input_inner_type) = self.get_type_info()?;
+
+        // small functions can be written directly into the iterator instead of being called on each element
+        if let Some((param, body)) = self.left.downcast_ref::<Function>().and_then(|f| f.inlinable_body()) {
+            wasmizer.write_inline_map(param, body, self.right.as_ref(), &input_inner_type)?;
+            return Ok(0);
+        }
 
         self.left.wasmize(wasmizer)?;
-        self.right.wasmize(wasmizer)?;
+        wasmizer.write_as_iter(self.right.as_ref())?;
 
         match self.left.get_type()? {
             Type::Arr(_) => {
@@ -121,7 +126,7 @@ impl Expression for Map {
                 ));
             }
             Type::Func(..) => {
-                wasmizer.write_map(&result_inner_type, &input_inner_type, input_is_array)?;
+                wasmizer.write_map(&result_inner_type, &input_inner_type)?;
             }
             typ => return Err(format!("Cannot map with type {:?}", typ)),
         }
@@ -151,53 +156,52 @@ impl Reduce {
         }
     }
 
-    // get the types of the result, the type contained in the array or iterator object iterated over, and whether that object is an array (as opposed to an iterator)
-    // returns (result_type, array_type, is_array)
-    fn get_type_info(&self) -> Result<(Type, Type, bool), String> {
+    // get the types of the result and the type contained in the array or iterator object iterated over
+    // returns (result_type, array_type)
+    fn get_type_info(&self) -> Result<(Type, Type), CompileError> {
         let (func_arg_types, func_ret_type) = match self.function.get_type()? {
             Type::Func(arg, ret) => (arg, *ret),
-            x => return Err(format!("Reduce function must be a function; got a {:?}", x)),
+            x => return Err(type_error(format!("Reduce function must be a function; got a {:?}", x))),
         };
         if func_arg_types.len() != 2 {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Reduce function must take two arguments; got {:?}",
                 func_arg_types
-            ));
+            )));
         };
         let acc_type = func_arg_types[0].clone();
         let x_type = func_arg_types[1].clone();
         let iter_over_type = self.iter_over.get_type()?;
-        let iter_over_is_array = matches!(iter_over_type, Type::Arr(_));
         let iter_over_inner_type = match iter_over_type {
             Type::Arr(x) | Type::Iter(x) => *x,
             x => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Second argument of reduce must be an array or iterator; got a {:?}",
                     x
-                ))
+                )))
             }
         };
         if iter_over_inner_type != x_type {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Second argument of reduce function and array must have the same type; got {:?} and {:?}", x_type, iter_over_inner_type
-            ));
+            )));
         }
         let init_type = self.init.get_type()?;
         if func_ret_type != init_type || func_ret_type != acc_type {
-            return Err(format!(
+            return Err(type_error(format!(
                 "First argument of reduce funtion, reduce function return value, and initial value must all have the same type; got {:?}, {:?}, and {:?}", acc_type, func_ret_type, init_type
-            ));
+            )));
         }
-        Ok((acc_type, x_type, iter_over_is_array))
+        Ok((acc_type, x_type))
     }
 }
 
 impl Expression for Reduce {
-    fn get_type(&self) -> Result<Type, String> {
-        let (acc_type, _, _) = self.get_type_info()?;
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (acc_type, _) = self.get_type_info()?;
         Ok(acc_type)
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.function.set_parent(Some(self_ptr))?;
@@ -209,10 +213,10 @@ impl Expression for Reduce {
         let iter_over_type = match self.iter_over.get_type()? {
             Type::Arr(t) | Type::Iter(t) => *t,
             _ => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Cannot use '->' with type {:?} on right",
                     self.iter_over.get_type()?
-                ))
+                )))
             }
         };
         if let Some(var) = self.function.downcast_mut::<Variable>() {
@@ -224,7 +228,7 @@ impl Expression for Reduce {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let _ = self.get_type()?; // check that types are all in order
         self.init.compile(compiler)?;
         self.iter_over.compile(compiler)?;
@@ -234,15 +238,213 @@ impl Expression for Reduce {
     }
 
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
-        let (acc_type, x_type, use_array_iter) = self.get_type_info()?;
+        let (acc_type, x_type) = self.get_type_info()?;
         self.function.wasmize(wasmizer)?;
         self.init.wasmize(wasmizer)?;
-        self.iter_over.wasmize(wasmizer)?;
-        wasmizer.write_reduce(&acc_type, &x_type, use_array_iter)?;
+        wasmizer.write_as_iter(self.iter_over.as_ref())?;
+        wasmizer.write_reduce(&acc_type, &x_type)?;
         return Ok(0);
     }
 }
 
+// like reduce, but gives an iterator over each successive accumulator value, not including the initial value
+#[derive(Debug)]
+pub struct Scan {
+    reduce: Reduce,
+    parent: Option<*const dyn Expression>,
+}
+
+impl Scan {
+    pub fn new(
+        function: Box<dyn Expression>,
+        iter_over: Box<dyn Expression>,
+        init: Box<dyn Expression>,
+    ) -> Self {
+        Self {
+            reduce: Reduce::new(function, iter_over, init),
+            parent: None,
+        }
+    }
+}
+
+impl Expression for Scan {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (acc_type, _) = self.reduce.get_type_info()?;
+        Ok(Type::Iter(Box::new(acc_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.reduce.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (acc_type, _) = self.reduce.get_type_info()?;
+        self.reduce.init.compile(compiler)?;
+        self.reduce.iter_over.compile(compiler)?;
+        self.reduce.function.compile(compiler)?;
+        compiler.write_opcode(
+            if acc_type.is_heap() { OpCode::ScanHeap } else { OpCode::Scan }
+        );
+        Ok(())
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let (acc_type, x_type) = self.reduce.get_type_info()?;
+        self.reduce.function.wasmize(wasmizer)?;
+        self.reduce.init.wasmize(wasmizer)?;
+        wasmizer.write_as_iter(self.reduce.iter_over.as_ref())?;
+        wasmizer.write_scan(&acc_type, &x_type)?;
+        Ok(0)
+    }
+}
+
+// like reduce, but also counts the elements folded over, giving a `Counted { result, count }` object
+// a program can declare `Counted := type { result: Acc, count: Int }` to write functions that take the result
+#[derive(Debug)]
+pub struct ReduceCount {
+    reduce: Reduce,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ReduceCount {
+    pub fn new(
+        function: Box<dyn Expression>,
+        iter_over: Box<dyn Expression>,
+        init: Box<dyn Expression>,
+    ) -> Self {
+        Self {
+            reduce: Reduce::new(function, iter_over, init),
+            parent: None,
+        }
+    }
+}
+
+impl Expression for ReduceCount {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (acc_type, _) = self.reduce.get_type_info()?;
+        Ok(Type::Object(
+            "Counted".to_string(),
+            vec![("result".to_string(), acc_type), ("count".to_string(), Type::Int)]
+        ))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.reduce.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (acc_type, _) = self.reduce.get_type_info()?;
+        self.reduce.init.compile(compiler)?;
+        self.reduce.iter_over.compile(compiler)?;
+        self.reduce.function.compile(compiler)?;
+        compiler.write_opcode(
+            if acc_type.is_heap() { OpCode::ReduceCountHeap } else { OpCode::ReduceCount }
+        );
+        Ok(())
+    }
+}
+
+// like reduce, but the function returns a Maybe, and the fold stops at the first null,
+// giving the last non-null accumulator
+#[derive(Debug)]
+pub struct ReduceWhile {
+    function: Box<dyn Expression>,
+    iter_over: Box<dyn Expression>,
+    init: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ReduceWhile {
+    pub fn new(
+        function: Box<dyn Expression>,
+        iter_over: Box<dyn Expression>,
+        init: Box<dyn Expression>,
+    ) -> Self {
+        Self {
+            function,
+            iter_over,
+            init,
+            parent: None,
+        }
+    }
+}
+
+impl Expression for ReduceWhile {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("reduce_while function must be a function; got a {:?}", x))),
+        };
+        if func_arg_types.len() != 2 {
+            return Err(type_error(format!(
+                "reduce_while function must take two arguments; got {:?}",
+                func_arg_types
+            )));
+        };
+        let iter_over_inner_type = match self.iter_over.get_type()? {
+            Type::Arr(x) | Type::Iter(x) => *x,
+            x => {
+                return Err(type_error(format!(
+                    "Third argument of reduce_while must be an array or iterator; got a {:?}",
+                    x
+                )))
+            }
+        };
+        if iter_over_inner_type != func_arg_types[1] {
+            return Err(type_error(format!(
+                "Second argument of reduce_while function and array must have the same type; got {:?} and {:?}", func_arg_types[1], iter_over_inner_type
+            )));
+        }
+        let acc_type = self.init.get_type()?;
+        if func_arg_types[0] != acc_type || func_ret_type != Type::Maybe(Box::new(acc_type.clone())) {
+            return Err(type_error(format!(
+                "reduce_while function must take the type of the initial value, {:?}, and return a Maybe of it; got a function of type {:?} -> {:?}",
+                acc_type, func_arg_types, func_ret_type
+            )));
+        }
+        Ok(acc_type)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.function.set_parent(Some(self_ptr))?;
+        self.iter_over.set_parent(Some(self_ptr))?;
+        self.init.set_parent(Some(self_ptr))?;
+
+        let inittype = self.init.get_type()?;
+        let iter_over_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!("Cannot use reduce_while over type {:?}", x))),
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![inittype, iter_over_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let acc_type = self.get_type()?;
+        self.init.compile(compiler)?;
+        self.iter_over.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_opcode(
+            if acc_type.is_heap() { OpCode::ReduceWhileHeap } else { OpCode::ReduceWhile }
+        );
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct Filter {
     pub function: Box<dyn Expression>,
@@ -259,53 +461,50 @@ impl Filter {
         }
     }
 
-    // Gets the type of contained in the result iterator, as well as if the input iterator is an array
-    // returns (result_type, is_array)
-    fn get_type_info(&self) -> Result<(Type, bool), String> {
+    // Gets the type of contained in the result iterator
+    fn get_type_info(&self) -> Result<Type, CompileError> {
         let (func_arg_type, func_ret_type) = match self.function.get_type()? {
             Type::Func(arg, ret) => (arg, *ret),
-            x => return Err(format!("Filter function must be a function; got a {:?}", x)),
+            x => return Err(type_error(format!("Filter function must be a function; got a {:?}", x))),
         };
         if func_arg_type.len() != 1 {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Filter function must take one argument; got {:?}",
                 func_arg_type
-            ));
+            )));
         }
         let func_arg_type = func_arg_type[0].clone();
         if func_ret_type != Type::Bool {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Filter function must return a bool; got {:?}",
                 func_ret_type
-            ));
+            )));
         }
-        let iter_over_type = self.iter_over.get_type()?;
-        let is_array = matches!(iter_over_type, Type::Arr(_));
-        let inner_type = match iter_over_type {
+        let inner_type = match self.iter_over.get_type()? {
             Type::Arr(x) | Type::Iter(x) => *x,
             x => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Second filter argument must be an array or iterator; got a {:?}",
                     x
-                ))
+                )))
             }
         };
         if inner_type != func_arg_type {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Filter function argument and array must have the same type; got {:?} and {:?}",
                 func_arg_type, inner_type
-            ));
+            )));
         }
-        Ok((inner_type, is_array))
+        Ok(inner_type)
     }
 }
 
 impl Expression for Filter {
-    fn get_type(&self) -> Result<Type, String> {
-        let (inner_type, _) = self.get_type_info()?;
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let inner_type = self.get_type_info()?;
         Ok(Type::Iter(Box::new(inner_type)))
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.function.set_parent(Some(self_ptr))?;
@@ -315,10 +514,10 @@ impl Expression for Filter {
         let arrtype = match self.iter_over.get_type()? {
             Type::Arr(t) | Type::Iter(t) => *t,
             _ => {
-                return Err(format!(
+                return Err(type_error(format!(
                     "Cannot use '->' with type {:?} on right",
                     self.iter_over.get_type()?
-                ))
+                )))
             }
         };
         if let Some(var) = self.function.downcast_mut::<Variable>() {
@@ -330,7 +529,7 @@ impl Expression for Filter {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let _ = self.get_type()?; // check that types are all in order
         self.function.compile(compiler)?;
         self.iter_over.compile(compiler)?;
@@ -339,94 +538,154 @@ impl Expression for Filter {
     }
 
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
-        let (typ, use_array_iter) = self.get_type_info()?;
+        let typ = self.get_type_info()?;
+        if let Some((param, body)) = self.function.downcast_ref::<Function>().and_then(|f| f.inlinable_body()) {
+            wasmizer.write_inline_filter(param, body, self.iter_over.as_ref(), &typ)?;
+            return Ok(0);
+        }
         self.function.wasmize(wasmizer)?;
-        self.iter_over.wasmize(wasmizer)?;
-        wasmizer.write_filter(&typ, use_array_iter)?;
+        wasmizer.write_as_iter(self.iter_over.as_ref())?;
+        wasmizer.write_filter(&typ)?;
         return Ok(0);
     }
 }
 
+// counts the elements for which a predicate is true
+// this is checked and compiled to wasm like a filter, but the filtered elements are only counted, not collected
 #[derive(Debug)]
-pub struct ZipMap {
-    function: Box<dyn Expression>,
-    exprs: Vec<Box<dyn Expression>>,
+pub struct CountWhere {
+    filter: Filter,
     parent: Option<*const dyn Expression>,
 }
 
-impl ZipMap {
-    pub fn new(fn_expr: Box<dyn Expression>, exprs: Vec<Box<dyn Expression>>) -> Self {
+impl CountWhere {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
         Self {
-            function: fn_expr,
-            exprs,
+            filter: Filter::new(function, iter_over),
             parent: None,
         }
     }
+}
 
-    // get the inner type of the result, the inner types of each of the objects iterated over, and whether each of the objects iterated over are arrays.
-    // returns (result_type, iter_over_types, iter_overs_are_arrays)
-    fn get_type_info(&self) -> Result<(Type, Vec<Type>, Vec<bool>), String> {
-        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
-            Type::Func(arg, ret) | Type::TypeDef(arg, ret) => (arg, *ret),
-            x => {
-                return Err(format!(
-                    "ZipMap function must be a function or type definition; got a {:?}",
-                    x
-                ))
-            }
-        };
-        let mut iter_over_types = Vec::new();
-        let mut iter_overs_are_arrays = Vec::new();
-        for expr in self.exprs.iter() {
-            let typ = expr.get_type()?;
-            iter_overs_are_arrays.push(matches!(typ, Type::Arr(_)));
-            match typ {
-                Type::Arr(t) | Type::Iter(t) => iter_over_types.push(*t),
-                x => {
-                    return Err(format!(
-                        "ZipMap expression must be an array or iterator; got a {:?}",
-                        x
-                    ))
-                }
-            }
-        }
-        if func_arg_types != iter_over_types {
-            return Err(format!(
-                "ZipMap function argument and arrays must have matching types; got {:?} and {:?}",
-                func_arg_types, iter_over_types
-            ));
+impl Expression for CountWhere {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        self.filter.get_type()?;
+        Ok(Type::Int)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.filter.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let _ = self.get_type()?; // check that types are all in order
+        self.filter.function.compile(compiler)?;
+        self.filter.iter_over.compile(compiler)?;
+        compiler.write_opcode(OpCode::CountWhere);
+        Ok(())
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let filtered_type = self.filter.get_type()?;
+        self.filter.wasmize(wasmizer)?;
+        wasmizer.write_len(&filtered_type)?;
+        Ok(0)
+    }
+}
+
+// the index of the first element for which a predicate is true, or null if there is none
+// elements after the first match are not visited, so this also works on infinite iterators that have a match
+#[derive(Debug)]
+pub struct FindIndex {
+    filter: Filter,
+    parent: Option<*const dyn Expression>,
+}
+
+impl FindIndex {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
+        Self {
+            filter: Filter::new(function, iter_over),
+            parent: None,
         }
+    }
+}
+
+impl Expression for FindIndex {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        self.filter.get_type()?;
+        Ok(Type::Maybe(Box::new(Type::Int)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.filter.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
 
-        Ok((func_ret_type, iter_over_types, iter_overs_are_arrays))
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let _ = self.get_type()?; // check that types are all in order
+        self.filter.function.compile(compiler)?;
+        self.filter.iter_over.compile(compiler)?;
+        compiler.write_opcode(OpCode::FindIndex);
+        Ok(())
     }
 }
 
-impl Expression for ZipMap {
-    fn get_type(&self) -> Result<Type, String> {
-        let (func_ret_type, _, _) = self.get_type_info()?;
-        Ok(Type::Iter(Box::new(func_ret_type)))
+// calls a function on each element for its side effects, yielding the elements unchanged
+// the function can return any type, since its results are discarded
+#[derive(Debug)]
+pub struct Inspect {
+    function: Box<dyn Expression>,
+    iter_over: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl Inspect {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
+        Self {
+            function,
+            iter_over,
+            parent: None,
+        }
+    }
+}
+
+impl Expression for Inspect {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "Second argument of inspect must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        let func_arg_types = match self.function.get_type()? {
+            Type::Func(arg, _) => arg,
+            x => return Err(type_error(format!("Inspect function must be a function; got a {:?}", x))),
+        };
+        if func_arg_types != [elem_type.clone()] {
+            return Err(type_error(format!(
+                "Inspect function must take one argument of type {:?}; got {:?}",
+                elem_type, func_arg_types
+            )));
+        }
+        Ok(Type::Iter(Box::new(elem_type)))
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.function.set_parent(Some(self_ptr))?;
-        for expr in self.exprs.iter_mut() {
-            expr.set_parent(Some(self_ptr))?;
-        }
+        self.iter_over.set_parent(Some(self_ptr))?;
 
-        // name to do same special handling for function that we do for callee in Call expression
-        let argtypes = self
-            .exprs
-            .iter()
-            .map(|expr| match expr.get_type() {
-                Ok(Type::Arr(t) | Type::Iter(t)) => Ok(*t),
-                t => Err(format!("Cannot use zipmap with type {:?}", t)),
-            })
-            .collect::<Result<Vec<Type>, String>>()?;
-        if let Some(var) = self.function.downcast_mut::<Variable>() {
-            let vartype = var.get_type();
-            if !matches!(vartype, Ok(Type::TypeDef(..))) {
-                var.set_template_types(argtypes)?;
+        // same special handling for function that we do for callee in Call expression
+        if let Type::Arr(t) | Type::Iter(t) = self.iter_over.get_type()? {
+            if let Some(var) = self.function.downcast_mut::<Variable>() {
+                var.set_template_types(vec![*t])?;
             }
         }
         Ok(())
@@ -435,35 +694,850 @@ impl Expression for ZipMap {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
-        self.get_type()?; // check that types are all in order
-        for expr in self.exprs.iter() {
-            expr.compile(compiler)?;
-        }
-        compiler.write_constant(Value {
-            i: self.exprs.len() as i64,
-        })?;
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let _ = self.get_type()?; // check that types are all in order
         self.function.compile(compiler)?;
-        compiler.write_opcode(OpCode::ZipMap);
+        self.iter_over.compile(compiler)?;
+        compiler.write_opcode(OpCode::Inspect);
         Ok(())
     }
+}
 
-    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
-        let (func_ret_type, iter_over_types, iter_overs_are_arrays) = self.get_type_info()?;
-
-        self.function.wasmize(wasmizer)?;
-        for ((expr, is_array), inner_type) in self
+// groups maximal runs of consecutive elements for which a key function returns the same value
+#[derive(Debug)]
+pub struct ChunkBy {
+    function: Box<dyn Expression>,
+    iter_over: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ChunkBy {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
+        Self {
+            function,
+            iter_over,
+            parent: None,
+        }
+    }
+
+    // get the type of the elements iterated over, and the type of the keys they are grouped by
+    fn get_type_info(&self) -> Result<(Type, KeyType), CompileError> {
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "Second argument of chunk_by must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("First argument of chunk_by must be a function; got a {:?}", x))),
+        };
+        if func_arg_types != [elem_type.clone()] {
+            return Err(type_error(format!(
+                "Key function of chunk_by must take a single argument of type {:?}; got {:?}",
+                elem_type, func_arg_types
+            )));
+        }
+        let key_type = match func_ret_type {
+            Type::Int => KeyType::Int,
+            Type::Str => KeyType::Str,
+            Type::Bool => KeyType::Bool,
+            x => return Err(type_error(format!(
+                "Key function of chunk_by must return an Int, Str, or Bool; got {:?}", x
+            ))),
+        };
+        Ok((elem_type, key_type))
+    }
+}
+
+impl Expression for ChunkBy {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (elem_type, _) = self.get_type_info()?;
+        Ok(Type::Iter(Box::new(Type::Arr(Box::new(elem_type)))))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.function.set_parent(Some(self_ptr))?;
+        self.iter_over.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "Second argument of chunk_by must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![elem_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (_, key_type) = self.get_type_info()?;
+        self.function.compile(compiler)?;
+        self.iter_over.compile(compiler)?;
+        compiler.write_by_key(OpCode::ChunkBy, key_type);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FlatMap {
+    function: Box<dyn Expression>,
+    iter_over: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl FlatMap {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
+        Self {
+            function,
+            iter_over,
+            parent: None,
+        }
+    }
+
+    // Gets the type contained in the result iterator and the type contained in the input
+    // returns (result_inner_type, input_inner_type)
+    fn get_type_info(&self) -> Result<(Type, Type), CompileError> {
+        let (func_arg_type, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("flat_map function must be a function; got a {:?}", x))),
+        };
+        if func_arg_type.len() != 1 {
+            return Err(type_error(format!(
+                "flat_map function must take one argument; got {:?}",
+                func_arg_type
+            )));
+        }
+        let result_inner_type = match func_ret_type {
+            Type::Arr(t) => *t,
+            x => return Err(type_error(format!("flat_map function must return an array; got {:?}", x))),
+        };
+        let input_inner_type = match self.iter_over.get_type()? {
+            Type::Arr(x) | Type::Iter(x) => *x,
+            x => {
+                return Err(type_error(format!(
+                    "Second flat_map argument must be an array or iterator; got a {:?}",
+                    x
+                )))
+            }
+        };
+        if input_inner_type != func_arg_type[0] {
+            return Err(type_error(format!(
+                "flat_map function argument and array must have the same type; got {:?} and {:?}",
+                func_arg_type[0], input_inner_type
+            )));
+        }
+        Ok((result_inner_type, input_inner_type))
+    }
+}
+
+impl Expression for FlatMap {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (result_inner_type, _) = self.get_type_info()?;
+        Ok(Type::Iter(Box::new(result_inner_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.function.set_parent(Some(self_ptr))?;
+        self.iter_over.set_parent(Some(self_ptr))?;
+
+        // need to do same special handling for function that we do for callee in Call expression
+        let arrtype = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            _ => {
+                return Err(type_error(format!(
+                    "Cannot use flat_map with type {:?}",
+                    self.iter_over.get_type()?
+                )))
+            }
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![arrtype])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (result_inner_type, _) = self.get_type_info()?;
+        self.function.compile(compiler)?;
+        self.iter_over.compile(compiler)?;
+        compiler.write_opcode(
+            if result_inner_type.is_heap() { OpCode::FlatMapHeap } else { OpCode::FlatMap }
+        );
+        Ok(())
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let (result_inner_type, input_inner_type) = self.get_type_info()?;
+        if result_inner_type.is_heap() {
+            return Err(format!(
+                "flat_map is not yet implemented in WASM mode for arrays of type {:?}", result_inner_type
+            ));
+        }
+        self.function.wasmize(wasmizer)?;
+        wasmizer.write_as_iter(self.iter_over.as_ref())?;
+        wasmizer.write_flat_map(&result_inner_type, &input_inner_type)?;
+        Ok(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct ZipMap {
+    function: Box<dyn Expression>,
+    exprs: Vec<Box<dyn Expression>>,
+    // if true, an Int index is passed to the function before the other arguments
+    with_index: bool,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ZipMap {
+    pub fn new(fn_expr: Box<dyn Expression>, exprs: Vec<Box<dyn Expression>>) -> Self {
+        Self {
+            function: fn_expr,
+            exprs,
+            with_index: false,
+            parent: None,
+        }
+    }
+
+    // used for `with_index(f, x)` and `zipmap_indexed(f, x, y, ...)`, which act like `zipmap(f, 0 to len(x) - 1, x, y, ...)`
+    pub fn with_index(fn_expr: Box<dyn Expression>, exprs: Vec<Box<dyn Expression>>) -> Self {
+        Self {
+            function: fn_expr,
+            exprs,
+            with_index: true,
+            parent: None,
+        }
+    }
+
+    // the argument types the function is called with
+    fn arg_types(&self, iter_over_types: &[Type]) -> Vec<Type> {
+        if self.with_index {
+            [vec![Type::Int], iter_over_types.to_vec()].concat()
+        }
+        else {
+            iter_over_types.to_vec()
+        }
+    }
+
+    // get the inner type of the result and the inner types of each of the objects iterated over
+    // returns (result_type, iter_over_types)
+    fn get_type_info(&self) -> Result<(Type, Vec<Type>), CompileError> {
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) | Type::TypeDef(arg, ret) => (arg, *ret),
+            x => {
+                return Err(type_error(format!(
+                    "ZipMap function must be a function or type definition; got a {:?}",
+                    x
+                )))
+            }
+        };
+        let mut iter_over_types = Vec::new();
+        for expr in self.exprs.iter() {
+            match expr.get_type()? {
+                Type::Arr(t) | Type::Iter(t) => iter_over_types.push(*t),
+                x => {
+                    return Err(type_error(format!(
+                        "ZipMap expression must be an array or iterator; got a {:?}",
+                        x
+                    )))
+                }
+            }
+        }
+        let arg_types = self.arg_types(&iter_over_types);
+        if func_arg_types != arg_types {
+            return Err(type_error(format!(
+                "ZipMap function argument and arrays must have matching types; got {:?} and {:?}",
+                func_arg_types, arg_types
+            )));
+        }
+
+        Ok((func_ret_type, iter_over_types))
+    }
+}
+
+impl Expression for ZipMap {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (func_ret_type, _) = self.get_type_info()?;
+        Ok(Type::Iter(Box::new(func_ret_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.function.set_parent(Some(self_ptr))?;
+        for expr in self.exprs.iter_mut() {
+            expr.set_parent(Some(self_ptr))?;
+        }
+
+        // name to do same special handling for function that we do for callee in Call expression
+        let argtypes = self
             .exprs
             .iter()
-            .zip(iter_overs_are_arrays.into_iter())
-            .zip(iter_over_types.iter())
-        {
-            expr.wasmize(wasmizer)?;
-            if is_array {
-                wasmizer.make_array_iter(inner_type)?;
+            .map(|expr| match expr.get_type() {
+                Ok(Type::Arr(t) | Type::Iter(t)) => Ok(*t),
+                t => Err(type_error(format!("Cannot use zipmap with type {:?}", t))),
+            })
+            .collect::<Result<Vec<Type>, CompileError>>()?;
+        let argtypes = self.arg_types(&argtypes);
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            let vartype = var.get_type();
+            if !matches!(vartype, Ok(Type::TypeDef(..))) {
+                var.set_template_types(argtypes)?;
             }
         }
-        wasmizer.write_zipmap(&func_ret_type, &iter_over_types)?;
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?; // check that types are all in order
+        if self.with_index {
+            compiler.write_opcode(OpCode::IndexIter);
+        }
+        for expr in self.exprs.iter() {
+            expr.compile(compiler)?;
+        }
+        compiler.write_constant(Value {
+            i: (self.exprs.len() + self.with_index as usize) as i64,
+        })?;
+        self.function.compile(compiler)?;
+        compiler.write_opcode(OpCode::ZipMap);
+        Ok(())
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let (func_ret_type, iter_over_types) = self.get_type_info()?;
+
+        self.function.wasmize(wasmizer)?;
+        if self.with_index {
+            wasmizer.write_index_iter()?;
+        }
+        for expr in self.exprs.iter() {
+            wasmizer.write_as_iter(expr.as_ref())?;
+        }
+        wasmizer.write_zipmap(&func_ret_type, &self.arg_types(&iter_over_types))?;
         return Ok(0);
     }
 }
+
+
+#[derive(Debug)]
+pub struct ZipLongest {
+    a: Box<dyn Expression>,
+    b: Box<dyn Expression>,
+    default_a: Box<dyn Expression>,
+    default_b: Box<dyn Expression>,
+    function: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ZipLongest {
+    pub fn new(
+        a: Box<dyn Expression>,
+        b: Box<dyn Expression>,
+        default_a: Box<dyn Expression>,
+        default_b: Box<dyn Expression>,
+        function: Box<dyn Expression>,
+    ) -> Self {
+        Self { a, b, default_a, default_b, function, parent: None }
+    }
+
+    // get the inner types of the two objects iterated over
+    fn iter_over_types(&self) -> Result<(Type, Type), CompileError> {
+        let mut types = Vec::new();
+        for expr in [&self.a, &self.b] {
+            match expr.get_type()? {
+                Type::Arr(t) | Type::Iter(t) => types.push(*t),
+                x => return Err(type_error(format!(
+                    "zip_longest expression must be an array or iterator; got a {:?}", x
+                ))),
+            }
+        }
+        let b = types.pop().unwrap();
+        let a = types.pop().unwrap();
+        Ok((a, b))
+    }
+}
+
+impl Expression for ZipLongest {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (a_type, b_type) = self.iter_over_types()?;
+        let default_types = (self.default_a.get_type()?, self.default_b.get_type()?);
+        if default_types != (a_type.clone(), b_type.clone()) {
+            return Err(type_error(format!(
+                "zip_longest defaults must match the types of the arrays; expected {:?} and {:?} but got {:?} and {:?}",
+                a_type, b_type, default_types.0, default_types.1
+            )));
+        }
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("zip_longest function must be a function; got a {:?}", x))),
+        };
+        if func_arg_types != [a_type.clone(), b_type.clone()] {
+            return Err(type_error(format!(
+                "zip_longest function argument and arrays must have matching types; got {:?} and {:?}",
+                func_arg_types, [a_type, b_type]
+            )));
+        }
+        Ok(Type::Iter(Box::new(func_ret_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.a.set_parent(Some(self_ptr))?;
+        self.b.set_parent(Some(self_ptr))?;
+        self.default_a.set_parent(Some(self_ptr))?;
+        self.default_b.set_parent(Some(self_ptr))?;
+        self.function.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let (a_type, b_type) = self.iter_over_types()?;
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![a_type, b_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?; // check that types are all in order
+        // defaults go first, so the VM can figure out which stack they're on from the array types
+        self.default_a.compile(compiler)?;
+        self.default_b.compile(compiler)?;
+        self.a.compile(compiler)?;
+        self.b.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_opcode(OpCode::ZipLongest);
+        Ok(())
+    }
+}
+
+// zips two arrays or iterators into pair objects without a mapping function, stopping at the shorter one
+#[derive(Debug)]
+pub struct Zip {
+    a: Box<dyn Expression>,
+    b: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl Zip {
+    pub fn new(a: Box<dyn Expression>, b: Box<dyn Expression>) -> Self {
+        Self { a, b, parent: None }
+    }
+}
+
+impl Expression for Zip {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let mut fields = Vec::new();
+        for (name, expr) in [("first", &self.a), ("second", &self.b)] {
+            match expr.get_type()? {
+                Type::Arr(t) | Type::Iter(t) => fields.push((name.to_string(), *t)),
+                x => return Err(type_error(format!(
+                    "zip expression must be an array or iterator; got a {:?}", x
+                ))),
+            }
+        }
+        // a program can declare `Pair := type { first: A, second: B }` to write functions that take the pairs
+        Ok(Type::Iter(Box::new(Type::Object("Pair".to_string(), fields))))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.a.set_parent(Some(self_ptr))?;
+        self.b.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;
+        self.a.compile(compiler)?;
+        self.b.compile(compiler)?;
+        compiler.write_opcode(OpCode::ZipPair);
+        Ok(())
+    }
+
+    // in wasm, this is a zipmap where the mapping function is the pair struct's constructor
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        let fields = match self.get_type()? {
+            Type::Iter(t) => match *t {
+                Type::Object(_, fields) => fields,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        if let Some((_, t)) = fields.iter().find(|(_, t)| t.is_heap()) {
+            return Err(format!("zip is not yet supported for iterators of {:?}", t));
+        }
+        // pairs of different types have different layouts, so each gets its own struct
+        let struct_def = Struct::from_ast_types(fields.clone());
+        let struct_name = Wasmizer::layout_struct_name("Pair", &fields)?;
+        wasmizer.create_struct(struct_name, struct_def, true)?;
+        wasmizer.write_as_iter(self.a.as_ref())?;
+        wasmizer.write_as_iter(self.b.as_ref())?;
+        let iter_over_types = fields.iter().map(|(_, t)| t.clone()).collect::<Vec<_>>();
+        let pair_type = Type::Object("Pair".to_string(), fields);
+        wasmizer.write_zipmap(&pair_type, &iter_over_types)?;
+        Ok(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Cache {
+    expr: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl Cache {
+    pub fn new(expr: Box<dyn Expression>) -> Self {
+        Self { expr, parent: None }
+    }
+}
+
+impl Expression for Cache {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        match self.expr.get_type()? {
+            Type::Iter(t) => Ok(Type::Iter(t)),
+            x => Err(type_error(format!(
+                "Cache expression must be an iterator; got a {:?}", x
+            ))),
+        }
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.expr.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;  // just to check that type is valid
+        self.expr.compile(compiler)?;
+        compiler.write_opcode(OpCode::Cache);
+        Ok(())
+    }
+}
+
+
+// `max_by(x, f)` / `min_by(x, f)`: the first element of x with the largest / smallest key f(elem), or null if x is empty.
+// elements whose key is a NaN Float are skipped, so the result is null if every key is NaN
+#[derive(Debug)]
+pub struct ExtremumBy {
+    iter_over: Box<dyn Expression>,
+    function: Box<dyn Expression>,
+    is_max: bool,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ExtremumBy {
+    pub fn new(iter_over: Box<dyn Expression>, function: Box<dyn Expression>, is_max: bool) -> Self {
+        Self { iter_over, function, is_max, parent: None }
+    }
+
+    fn name(&self) -> &'static str {
+        if self.is_max { "max_by" } else { "min_by" }
+    }
+
+    // get the type of the elements iterated over, and the type of the keys they are compared by
+    fn get_type_info(&self) -> Result<(Type, KeyType), CompileError> {
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "First argument of {} must be an array or iterator; got a {:?}", self.name(), x
+            ))),
+        };
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("Second argument of {} must be a function; got a {:?}", self.name(), x))),
+        };
+        if func_arg_types != [elem_type.clone()] {
+            return Err(type_error(format!(
+                "Key function of {} must take a single argument of type {:?}; got {:?}",
+                self.name(), elem_type, func_arg_types
+            )));
+        }
+        let key_type = match func_ret_type {
+            Type::Int => KeyType::Int,
+            Type::Float => KeyType::Float,
+            Type::Str => KeyType::Str,
+            x => return Err(type_error(format!(
+                "Key function of {} must return an Int, Float, or Str; got {:?}", self.name(), x
+            ))),
+        };
+        Ok((elem_type, key_type))
+    }
+}
+
+impl Expression for ExtremumBy {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (elem_type, _) = self.get_type_info()?;
+        Ok(Type::Maybe(Box::new(elem_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.iter_over.set_parent(Some(self_ptr))?;
+        self.function.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "First argument of {} must be an array or iterator; got a {:?}", self.name(), x
+            ))),
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![elem_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (_, key_type) = self.get_type_info()?;
+        self.iter_over.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_by_key(if self.is_max { OpCode::MaxBy } else { OpCode::MinBy }, key_type);
+        Ok(())
+    }
+}
+
+
+// sums the values a function projects the elements of an array or iterator to, without collecting them first
+#[derive(Debug)]
+pub struct SumBy {
+    function: Box<dyn Expression>,
+    iter_over: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl SumBy {
+    pub fn new(function: Box<dyn Expression>, iter_over: Box<dyn Expression>) -> Self {
+        Self { function, iter_over, parent: None }
+    }
+
+    // get the type of the elements iterated over, and the numeric type they are projected to
+    fn get_type_info(&self) -> Result<(Type, Type), CompileError> {
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "Second argument of sum_by must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("First argument of sum_by must be a function; got a {:?}", x))),
+        };
+        if func_arg_types != [elem_type.clone()] {
+            return Err(type_error(format!(
+                "Function passed to sum_by must take a single argument of type {:?}; got {:?}",
+                elem_type, func_arg_types
+            )));
+        }
+        if !matches!(func_ret_type, Type::Int | Type::Float) {
+            return Err(type_error(format!(
+                "Function passed to sum_by must return an Int or Float; got {:?}", func_ret_type
+            )));
+        }
+        Ok((elem_type, func_ret_type))
+    }
+}
+
+impl Expression for SumBy {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (_, sum_type) = self.get_type_info()?;
+        Ok(sum_type)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.function.set_parent(Some(self_ptr))?;
+        self.iter_over.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "Second argument of sum_by must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![elem_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (_, sum_type) = self.get_type_info()?;
+        self.iter_over.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_by_key(OpCode::SumBy, if sum_type == Type::Int { KeyType::Int } else { KeyType::Float });
+        Ok(())
+    }
+}
+
+
+// `sort_by_key(x, f)`: the elements of x as an array, stably sorted by the keys f(elem), with f called once per element.
+// NaN Float keys are sorted after all other keys
+#[derive(Debug)]
+pub struct SortByKey {
+    iter_over: Box<dyn Expression>,
+    function: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl SortByKey {
+    pub fn new(iter_over: Box<dyn Expression>, function: Box<dyn Expression>) -> Self {
+        Self { iter_over, function, parent: None }
+    }
+
+    // get the type of the elements sorted, and the type of the keys they are sorted by
+    fn get_type_info(&self) -> Result<(Type, KeyType), CompileError> {
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "First argument of sort_by_key must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        let (func_arg_types, func_ret_type) = match self.function.get_type()? {
+            Type::Func(arg, ret) => (arg, *ret),
+            x => return Err(type_error(format!("Second argument of sort_by_key must be a function; got a {:?}", x))),
+        };
+        if func_arg_types != [elem_type.clone()] {
+            return Err(type_error(format!(
+                "Key function of sort_by_key must take a single argument of type {:?}; got {:?}",
+                elem_type, func_arg_types
+            )));
+        }
+        let key_type = match func_ret_type {
+            Type::Int => KeyType::Int,
+            Type::Float => KeyType::Float,
+            Type::Str => KeyType::Str,
+            x => return Err(type_error(format!(
+                "Key function of sort_by_key must return an Int, Float, or Str; got {:?}", x
+            ))),
+        };
+        Ok((elem_type, key_type))
+    }
+}
+
+impl Expression for SortByKey {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let (elem_type, _) = self.get_type_info()?;
+        Ok(Type::Arr(Box::new(elem_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.iter_over.set_parent(Some(self_ptr))?;
+        self.function.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let elem_type = match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => *t,
+            x => return Err(type_error(format!(
+                "First argument of sort_by_key must be an array or iterator; got a {:?}", x
+            ))),
+        };
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![elem_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let (_, key_type) = self.get_type_info()?;
+        self.iter_over.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_by_key(OpCode::SortByKey, key_type);
+        Ok(())
+    }
+}
+
+// `sort_by(x, cmp)`: the elements of x as an array, stably sorted by the comparison function cmp(a, b),
+// which returns a negative Int if a goes before b, a positive Int if a goes after b, and 0 if they are equal
+#[derive(Debug)]
+pub struct SortBy {
+    iter_over: Box<dyn Expression>,
+    function: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl SortBy {
+    pub fn new(iter_over: Box<dyn Expression>, function: Box<dyn Expression>) -> Self {
+        Self { iter_over, function, parent: None }
+    }
+
+    fn elem_type(&self) -> Result<Type, CompileError> {
+        match self.iter_over.get_type()? {
+            Type::Arr(t) | Type::Iter(t) => Ok(*t),
+            x => Err(type_error(format!(
+                "First argument of sort_by must be an array or iterator; got a {:?}", x
+            ))),
+        }
+    }
+}
+
+impl Expression for SortBy {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let elem_type = self.elem_type()?;
+        let cmp_type = Type::Func(vec![elem_type.clone(), elem_type.clone()], Box::new(Type::Int));
+        let func_type = self.function.get_type()?;
+        if func_type != cmp_type {
+            return Err(type_error(format!(
+                "Comparison function of sort_by must have type {:?}; got {:?}", cmp_type, func_type
+            )));
+        }
+        Ok(Type::Arr(Box::new(elem_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.iter_over.set_parent(Some(self_ptr))?;
+        self.function.set_parent(Some(self_ptr))?;
+
+        // same special handling for function that we do for callee in Call expression
+        let elem_type = self.elem_type()?;
+        if let Some(var) = self.function.downcast_mut::<Variable>() {
+            var.set_template_types(vec![elem_type.clone(), elem_type])?;
+        }
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;
+        self.iter_over.compile(compiler)?;
+        self.function.compile(compiler)?;
+        compiler.write_opcode(OpCode::SortBy);
+        Ok(())
+    }
+}
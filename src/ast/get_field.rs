@@ -1,4 +1,6 @@
-use crate::{chunk::OpCode, values::Value};
+use std::rc::Rc;
+
+use crate::{chunk::OpCode, values::{HeapValue, Value}};
 
 use super::*;
 
@@ -16,7 +18,7 @@ impl GetField {
 }
 
 impl Expression for GetField {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let object_type = self.object.get_type()?;
         match &object_type {
             Type::Object(_, fields) => {
@@ -25,16 +27,16 @@ impl Expression for GetField {
                         return Ok(typ.clone());
                     }
                 }
-                Err(format!(
+                Err(type_error(format!(
                     "Field {:?} not found in type {:?}", self.field, object_type
-                ))
+                )))
             },
-            _ => Err(format!(
+            _ => Err(type_error(format!(
                 "Field access on non-object type {:?}", object_type
-            ))
+            )))
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.object.set_parent(Some(self_ptr))
@@ -43,7 +45,7 @@ impl Expression for GetField {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let typ = self.get_type()?;
         compiler.write_constant(Value { b: typ.is_heap() })?;
         compiler.write_string(self.field.clone())?;
@@ -60,3 +62,127 @@ impl Expression for GetField {
         Ok(0)
     }
 }
+
+// `set_field(object, name, value)` creates a copy of object with the field called name set to value
+// since name may not be known until runtime, the field is checked at runtime
+#[derive(Debug)]
+pub struct SetField {
+    object: Box<dyn Expression>,
+    field: Box<dyn Expression>,
+    value: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl SetField {
+    pub fn new(object: Box<dyn Expression>, field: Box<dyn Expression>, value: Box<dyn Expression>) -> Self {
+        Self { object, field, value, parent: None }
+    }
+
+    // get the names of the object's fields that have the same type as the value
+    fn matching_fields(&self) -> Result<Vec<String>, CompileError> {
+        let object_type = self.object.get_type()?;
+        let fields = match &object_type {
+            Type::Object(_, fields) => fields,
+            _ => return Err(type_error(format!(
+                "set_field expected an object, got {:?}", object_type
+            ))),
+        };
+        let field_type = self.field.get_type()?;
+        if field_type != Type::Str {
+            return Err(type_error(format!("set_field expected field name to be a Str, got {:?}", field_type)));
+        }
+        let value_type = self.value.get_type()?;
+        let matching = fields.iter()
+            .filter(|(_, typ)| typ == &value_type)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            return Err(type_error(format!(
+                "No field in type {:?} has type {:?}", object_type, value_type
+            )));
+        }
+        // if the field name is known now, it can be checked now instead of at runtime
+        if let Some(name) = self.field.downcast_ref::<Literal>().and_then(|lit| lit.str_value()) {
+            if !fields.iter().any(|(field, _)| field == name) {
+                return Err(type_error(format!("Field {:?} not found in type {:?}", name, object_type)));
+            }
+            if !matching.iter().any(|field| field == name) {
+                return Err(type_error(format!(
+                    "Field {:?} of type {:?} cannot be set to a value of type {:?}", name, object_type, value_type
+                )));
+            }
+        }
+        Ok(matching)
+    }
+}
+
+impl Expression for SetField {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        self.matching_fields()?;
+        self.object.get_type()
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.object.set_parent(Some(self_ptr))?;
+        self.field.set_parent(Some(self_ptr))?;
+        self.value.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let matching_fields = self.matching_fields()?
+            .into_iter()
+            .map(|name| HeapValue::String(Rc::new(name)))
+            .collect::<Vec<_>>();
+        let value_type = self.value.get_type()?;
+        self.object.compile(compiler)?;
+        self.field.compile(compiler)?;
+        self.value.compile(compiler)?;
+        compiler.write_heap_constant(HeapValue::ArrayHeap(Rc::from(matching_fields)))?;
+        compiler.write_string(format!("{:?}", value_type))?;
+        compiler.write_opcode(
+            if value_type.is_heap() { OpCode::SetHeapField } else { OpCode::SetField }
+        );
+        Ok(())
+    }
+}
+
+// `field_names(object)` gives the names of the object's fields as an array, in declaration order
+#[derive(Debug)]
+pub struct FieldNames {
+    object: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl FieldNames {
+    pub fn new(object: Box<dyn Expression>) -> Self {
+        Self { object, parent: None }
+    }
+}
+
+impl Expression for FieldNames {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        match self.object.get_type()? {
+            Type::Object(..) => Ok(Type::Arr(Box::new(Type::Str))),
+            x => Err(type_error(format!("field_names expected an object, got {:?}", x))),
+        }
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.object.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;
+        self.object.compile(compiler)?;
+        compiler.write_opcode(OpCode::FieldNames);
+        Ok(())
+    }
+}
@@ -21,27 +21,27 @@ impl IfStatement {
 }
 
 impl Expression for IfStatement {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let condition_type = self.condition.get_type()?;
         if condition_type != Type::Bool {
-            return Err(format!(
+            return Err(type_error(format!(
                 "If condition must be a boolean, but got {:?}",
                 condition_type
-            ));
+            )));
         }
         let then_branch_type = self.then_branch.get_type()?;
         let else_branch_type = self.else_branch.get_type()?;
         if then_branch_type != else_branch_type {
-            Err(format!(
+            Err(type_error(format!(
                 "If and else branches have different types: {:?} and {:?}",
                 then_branch_type, else_branch_type
-            ))
+            )))
         }
         else {
             Ok(then_branch_type)
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.condition.set_parent(Some(self_ptr))?;
@@ -53,7 +53,7 @@ impl Expression for IfStatement {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let _typ = self.get_type()?; // will error if types don't match or condition is not a bool
         self.condition.compile(compiler)?;
         let jump_if_idx = compiler.write_jump(OpCode::JumpIfFalse)?;
@@ -74,4 +74,7 @@ impl Expression for IfStatement {
         wasmizer.write_end()?;
         Ok(0)
     }
+    fn has_tail_call_to(&self, name: &str) -> bool {
+        self.then_branch.has_tail_call_to(name) || self.else_branch.has_tail_call_to(name)
+    }
 }   
@@ -0,0 +1,116 @@
+use crate::chunk::OpCode;
+
+use super::*;
+
+// serializes a value to a JSON string
+#[derive(Debug)]
+pub struct ToJson {
+    expr: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl ToJson {
+    pub fn new(expr: Box<dyn Expression>) -> Self {
+        Self { expr, parent: None }
+    }
+}
+
+// whether values of the given type have a JSON representation
+fn is_serializable(typ: &Type) -> bool {
+    match typ {
+        Type::Int | Type::Float | Type::Bool | Type::Str => true,
+        Type::Arr(t) | Type::Iter(t) | Type::Maybe(t) => is_serializable(t),
+        Type::Object(_, fields) => fields.iter().all(|(_, t)| is_serializable(t)),
+        Type::Func(..) | Type::TypeDef(..) => false,
+    }
+}
+
+impl Expression for ToJson {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let expr_type = self.expr.get_type()?;
+        if !is_serializable(&expr_type) {
+            return Err(type_error(format!("Cannot convert a {:?} to JSON", expr_type)));
+        }
+        Ok(Type::Str)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.expr.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;  // just to check that type is valid
+        self.expr.compile(compiler)?;
+        // values don't record their own types, so the type is stored for the VM to interpret the value with
+        compiler.write_typed(OpCode::ToJson, self.expr.get_type()?)
+    }
+}
+
+
+// parses a JSON string into a value, with the target type taken from a type ascription
+// e.g. `from_json(s): Maybe(Point)`
+#[derive(Debug)]
+pub struct FromJson {
+    expr: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl FromJson {
+    pub fn new(expr: Box<dyn Expression>) -> Self {
+        Self { expr, parent: None }
+    }
+
+    // the type to parse into, i.e. T when the ascribed type is Maybe(T)
+    fn target_type(&self) -> Result<Type, CompileError> {
+        let ascription = self.parent
+            .and_then(|p| unsafe { &*p }.downcast_ref::<TypeAscription>());
+        match ascription.map(|a| a.ascribed_type()) {
+            Some(Ok(Type::Maybe(t))) => Ok(*t),
+            Some(Ok(t)) => Err(type_error(format!("from_json must be annotated with a Maybe type, but got {:?}", t))),
+            Some(Err(e)) => Err(e),
+            None => Err(type_error("from_json must be annotated with the type to parse into".to_string())),
+        }
+    }
+}
+
+// whether values of the given type can be parsed from JSON
+fn is_deserializable(typ: &Type) -> bool {
+    match typ {
+        Type::Int | Type::Float | Type::Bool | Type::Str => true,
+        Type::Arr(t) | Type::Maybe(t) => is_deserializable(t),
+        Type::Object(_, fields) => fields.iter().all(|(_, t)| is_deserializable(t)),
+        Type::Iter(_) | Type::Func(..) | Type::TypeDef(..) => false,
+    }
+}
+
+impl Expression for FromJson {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let expr_type = self.expr.get_type()?;
+        if expr_type != Type::Str {
+            return Err(type_error(format!("from_json expected a Str, got {:?}", expr_type)));
+        }
+        let target_type = self.target_type()?;
+        if !is_deserializable(&target_type) {
+            return Err(type_error(format!("Cannot parse a {:?} from JSON", target_type)));
+        }
+        Ok(Type::Maybe(Box::new(target_type)))
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.expr.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;  // just to check that type is valid
+        self.expr.compile(compiler)?;
+        compiler.write_typed(OpCode::FromJson, self.target_type()?)
+    }
+}
@@ -1,3 +1,5 @@
+use std::num::IntErrorKind;
+
 use crate::values::Value;
 
 use super::*;
@@ -13,22 +15,39 @@ impl Literal {
     pub fn new(typ: Type, value: String) -> Self {
         Self { typ, value, parent: None }
     }
+    pub fn int_value(&self) -> Option<i64> {
+        match self.typ {
+            Type::Int => self.value.parse::<i64>().ok(),
+            _ => None,
+        }
+    }
+    pub fn str_value(&self) -> Option<&str> {
+        match self.typ {
+            Type::Str => Some(&self.value[1..self.value.len() - 1]),
+            _ => None,
+        }
+    }
 }
 
 impl Expression for Literal {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         Ok(self.typ.clone())
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         Ok(())
     }
     fn get_parent(&self) -> Option<*const dyn Expression> {
         self.parent
     }
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let value = match self.typ {
-            Type::Int => Value::from_i64(self.value.parse::<i64>().unwrap()),
+            Type::Int => Value::from_i64(self.value.parse::<i64>().map_err(|e| other_error(match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => format!(
+                    "Integer literal exceeds 64-bit range: {}", self.value
+                ),
+                _ => e.to_string(),
+            }))?),
             Type::Float => Value::from_f64(self.value.parse::<f64>().unwrap()),
             Type::Bool => Value::from_bool(self.value.parse::<bool>().unwrap()),
             Type::Str => {
@@ -44,4 +63,7 @@ impl Expression for Literal {
         wasmizer.write_const(&self.value, &self.typ)?;
         Ok(0)
     }
+    fn is_inlinable(&self, _param: &str) -> bool {
+        matches!(self.typ, Type::Int | Type::Float | Type::Bool)
+    }
 }
@@ -1,4 +1,6 @@
-use crate::{chunk::OpCode, values::HeapValue};
+use std::rc::Rc;
+
+use crate::{chunk::OpCode, values::{HeapValue, Value}};
 
 use super::*;
 
@@ -24,13 +26,13 @@ impl Maybe {
 }
 
 impl Expression for Maybe {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         Ok(Type::Maybe(Box::new(match &self.value {
             MaybeValue::Some(e) => e.get_type()?,
             MaybeValue::Null(t) => t.get_type()?,
         })))
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         match &mut self.value {
@@ -42,7 +44,7 @@ impl Expression for Maybe {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         match &self.value {
             MaybeValue::Some(e) => {
                 e.compile(compiler)?;
@@ -92,10 +94,10 @@ impl IsSome {
 }
 
 impl Expression for IsSome {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         Ok(Type::Bool)
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.value.set_parent(Some(self_ptr))
@@ -104,10 +106,10 @@ impl Expression for IsSome {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         // check that value is a Maybe
         if !matches!(self.value.get_type()?, Type::Maybe(_)) {
-            return Err(format!("IsSome expected Maybe type, got {:?}", self.value.get_type()?));
+            return Err(type_error(format!("IsSome expected Maybe type, got {:?}", self.value.get_type()?)));
         }
         self.value.compile(compiler)?;
         compiler.write_opcode(OpCode::IsSome);
@@ -140,18 +142,18 @@ impl Unwrap {
 }
 
 impl Expression for Unwrap {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let inner_type = match self.value.get_type()? {
             Type::Maybe(t) => *t,
-            x => return Err(format!("Unwrap expected Maybe, got {:?}", x))
+            x => return Err(type_error(format!("Unwrap expected Maybe, got {:?}", x)))
         };
         let default_type = self.default.get_type()?;
         if inner_type != default_type {
-            return Err(format!("Unwrap default does not match inner type, got default {:?} and inner type {:?}", default_type, inner_type));
+            return Err(type_error(format!("Unwrap default does not match inner type, got default {:?} and inner type {:?}", default_type, inner_type)));
         }
         Ok(inner_type)
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.value.set_parent(Some(self_ptr))?;
@@ -162,7 +164,7 @@ impl Expression for Unwrap {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let typ = self.get_type()?;
         self.default.compile(compiler)?;
         self.value.compile(compiler)?;
@@ -183,3 +185,123 @@ impl Expression for Unwrap {
         Ok(0)
     }
 }   
+
+#[derive(Debug)]
+pub struct Match {
+    value: Box<dyn Expression>,
+    binding: String,
+    some_arm: Box<dyn Expression>,
+    null_arm: Box<dyn Expression>,
+    parent: Option<*const dyn Expression>,
+}
+
+impl Match {
+    pub fn new(
+        value: Box<dyn Expression>,
+        binding: String,
+        some_arm: Box<dyn Expression>,
+        null_arm: Box<dyn Expression>,
+    ) -> Self {
+        Self { value, binding, some_arm, null_arm, parent: None }
+    }
+
+    fn inner_type(&self) -> Result<Type, CompileError> {
+        match self.value.get_type()? {
+            Type::Maybe(t) => Ok(*t),
+            x => Err(type_error(format!("Match expected Maybe type, got {:?}", x))),
+        }
+    }
+
+    // get name of the bound variable, appending param types if it is a function
+    fn get_expanded_binding(&self) -> Result<String, CompileError> {
+        Ok(match self.inner_type()? {
+            Type::Func(paramtypes, _) if !paramtypes.is_empty() => {
+                format!("{}{:?}", self.binding, paramtypes)
+            },
+            _ => self.binding.clone(),
+        })
+    }
+}
+
+impl Expression for Match {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        self.inner_type()?;
+        let some_type = self.some_arm.get_type()?;
+        let null_type = self.null_arm.get_type()?;
+        if some_type != null_type {
+            return Err(type_error(format!(
+                "Match arms have different types: {:?} and {:?}",
+                some_type, null_type
+            )));
+        }
+        Ok(some_type)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.value.set_parent(Some(self_ptr))?;
+        self.some_arm.set_parent(Some(self_ptr))?;
+        self.null_arm.set_parent(Some(self_ptr))?;
+        Ok(())
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
+        // the bound variable is only visible within the some arm
+        if self.some_arm.as_ref() as *const _ as *const () != upto as *const () {
+            return Ok(None);
+        }
+        if &self.get_expanded_binding()? == name {
+            return Ok(Some(self.inner_type()?));
+        }
+        Ok(None)
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let typ = self.get_type()?; // will error if arm types don't match or value is not a Maybe
+        let maybe_type = self.value.get_type()?;
+        let inner_type = self.inner_type()?;
+
+        // store the value in a local so it can be checked and then unwrapped
+        compiler.begin_scope();
+        compiler.create_variable("<match>".to_string(), &maybe_type)?;
+        self.value.compile(compiler)?;
+        compiler.set_variable(None, true)?;
+        compiler.write_opcode(OpCode::EndHeapExpr);
+
+        compiler.get_variable("<match>".to_string(), true)?;
+        compiler.write_opcode(OpCode::IsSome);
+        let jump_if_idx = compiler.write_jump(OpCode::JumpIfFalse)?;
+
+        // some arm: bind the unwrapped value
+        compiler.begin_scope();
+        compiler.create_variable(self.get_expanded_binding()?, &inner_type)?;
+        // value is known to be some, so the default is never used
+        if inner_type.is_heap() {
+            compiler.write_heap_constant(HeapValue::String(Rc::new(String::new())))?;
+        }
+        else {
+            compiler.write_constant(Value::from_i64(0))?;
+        }
+        compiler.get_variable("<match>".to_string(), true)?;
+        if inner_type.is_heap() {
+            compiler.write_opcode(OpCode::UnwrapHeap);
+        }
+        else {
+            compiler.write_opcode(OpCode::Unwrap);
+        }
+        compiler.set_variable(None, inner_type.is_heap())?;
+        compiler.write_opcode(
+            if inner_type.is_heap() { OpCode::EndHeapExpr } else { OpCode::EndExpr }
+        );
+        self.some_arm.compile(compiler)?;
+        compiler.end_scope(typ.is_heap())?;
+        let jump_else_idx = compiler.write_jump(OpCode::Jump)?;
+
+        compiler.patch_jump(jump_if_idx)?;
+        self.null_arm.compile(compiler)?;
+        compiler.patch_jump(jump_else_idx)?;
+        compiler.end_scope(typ.is_heap())
+    }
+}
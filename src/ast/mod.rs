@@ -7,6 +7,7 @@ mod function;
 mod functional_ops;
 mod get_field;
 mod if_statement;
+mod json;
 mod literal;
 mod maybe;
 mod top_level;
@@ -24,6 +25,7 @@ pub use function::*;
 pub use functional_ops::*;
 pub use get_field::*;
 pub use if_statement::*;
+pub use json::*;
 pub use literal::*;
 pub use maybe::*;
 pub use top_level::*;
@@ -34,7 +36,7 @@ pub use variable::*;
 
 use downcast_rs::{Downcast, impl_downcast};
 
-use crate::{compiler::Compiler, wasmizer::Wasmizer};
+use crate::{compiler::Compiler, vm::ErrorKind, wasmizer::Wasmizer};
 
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -72,30 +74,81 @@ impl VarType {
 
 
 pub trait Expression: std::fmt::Debug + Downcast {
-    fn get_type(&self) -> Result<Type, String>;
+    fn get_type(&self) -> Result<Type, CompileError>;
 
     // set parent should set the parent for this expression,
     // then call set_parent on all of its children
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String>;
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError>;
     fn get_parent(&self) -> Option<*const dyn Expression>;
     // get a list of variables and their types that are defined in this expression
     // will stop looking if the given expression if reached
     #[allow(unused_variables)]
-    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String>;
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError>;
     #[allow(unused_variables)]
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
         Err(format!("wasmize not implemented for {}", std::any::type_name::<Self>()))
     }
+    // whether this expression is simple enough to be spliced directly into a builtin wasm function,
+    // i.e., it is built only from numeric literals, operators, and the given parameter
+    #[allow(unused_variables)]
+    fn is_inlinable(&self, param: &str) -> bool {
+        false
+    }
+    // whether evaluating this expression can end with a call to the named function,
+    // i.e. whether it makes a tail call to that function
+    #[allow(unused_variables)]
+    fn has_tail_call_to(&self, name: &str) -> bool {
+        false
+    }
 }
 
 impl_downcast!(Expression);
 
 
-fn resolve_type(name: &String, origin: *const dyn Expression) -> Result<Type, String> {
+// an error found while type checking or compiling the AST, with its kind decided where the error is raised
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError(pub ErrorKind, pub String);
+
+impl CompileError {
+    // adds context to the message while keeping the kind of the original error
+    pub fn context(self, context: &str) -> Self {
+        Self(self.0, format!("{}: {}", context, self.1))
+    }
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.1)
+    }
+}
+
+// the wasm backend only reports error messages
+impl From<CompileError> for String {
+    fn from(e: CompileError) -> Self {
+        e.1
+    }
+}
+
+impl From<CompileError> for (ErrorKind, String) {
+    fn from(e: CompileError) -> Self {
+        (e.0, e.1)
+    }
+}
+
+pub fn type_error(message: String) -> CompileError {
+    CompileError(ErrorKind::TypeMismatch, message)
+}
+
+// for limits of the compiler, like the number of locals in a function, rather than problems with the program's types
+pub fn other_error(message: String) -> CompileError {
+    CompileError(ErrorKind::Other, message)
+}
+
+fn resolve_type(name: &String, origin: *const dyn Expression) -> Result<Type, CompileError> {
     // climb up the tree looking for a VarType with a matching name
     let mut e = origin;
     loop {
@@ -105,8 +158,8 @@ fn resolve_type(name: &String, origin: *const dyn Expression) -> Result<Type, St
         }
         else {
             // reached top of tree
-            return Err(format!(
-                "Could not find definition for variable {}", name
+            return Err(CompileError(
+                ErrorKind::UndefinedVariable, format!("Could not find definition for variable {}", name)
             ));
         }
         let typ = unsafe { (*e).find_vartype(&name, last_e) };
@@ -125,16 +178,16 @@ fn truncate_template_types(name: &String) -> &str {
 pub struct ErrorExpression;
 
 impl Expression for ErrorExpression {
-    fn get_type(&self) -> Result<Type, String> {
-        Err("ErrorExpressions have no type".to_string())
+    fn get_type(&self) -> Result<Type, CompileError> {
+        Err(other_error("ErrorExpressions have no type".to_string()))
     }
-    fn set_parent(&mut self, _parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, _parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         Ok(())
     }
     fn get_parent(&self) -> Option<*const dyn Expression> {
         None
     }
-    fn compile(&self, _compiler: &mut Compiler) -> Result<(), String> {
-        Err("Tried to compile an ErrorExpression".to_string())
+    fn compile(&self, _compiler: &mut Compiler) -> Result<(), CompileError> {
+        Err(other_error("Tried to compile an ErrorExpression".to_string()))
     }
 }
@@ -18,18 +18,39 @@ impl ASTTopLevel {
     }
 }
 
+impl ASTTopLevel {
+    // get the name and type of each top-level binding, without compiling anything
+    // top-level expressions that aren't bindings are also type-checked, and are included only if they have errors
+    pub fn binding_types(&self) -> Vec<(String, Result<Type, String>)> {
+        let expressions = match self.child.downcast_ref::<Block>() {
+            Some(block) => block.expressions(),
+            None => std::slice::from_ref(&self.child),
+        };
+        let mut bindings = Vec::new();
+        for e in expressions.iter() {
+            if let Some(assignment) = e.downcast_ref::<Assignment>() {
+                bindings.push((assignment.get_name().to_string(), e.get_type().map_err(String::from)));
+            }
+            else if let Err(err) = e.get_type() {
+                bindings.push(("<expression>".to_string(), Err(err.into())));
+            }
+        }
+        bindings
+    }
+}
+
 impl Expression for ASTTopLevel {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         self.child.get_type()
     }
-    fn set_parent(&mut self, _parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, _parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         let self_ptr = self as *const dyn Expression;
         self.child.set_parent(Some(self_ptr))
     }
     fn get_parent(&self) -> Option<*const dyn Expression> {
         None
     }
-    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         for t in self.types.iter() {
             if &t.name == name {
                 return Ok(Some(t.typ.clone()));
@@ -38,7 +59,7 @@ impl Expression for ASTTopLevel {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         self.child.compile(compiler)?;
         compiler.write_opcode(OpCode::Return);
         Ok(())
@@ -12,28 +12,28 @@ impl TypeAnnotation {
         Self { typename, children, parent: None }
     }
 
-    fn resolve_typedef(&self) -> Result<Type, String> {
+    fn resolve_typedef(&self) -> Result<Type, CompileError> {
         let parent = match self.parent {
             Some(x) => x,
-            None => return Err(format!("Could not resolve type for {}; parent is None", self.typename)),
+            None => return Err(other_error(format!("Could not resolve type for {}; parent is None", self.typename))),
         };
         let typ = resolve_type(&self.typename, parent)?;
         let objtype = match typ {
             Type::TypeDef(_, t) => *t,
-            _ => return Err(format!("When resolving type, expected an Object definition, but got {:?}", typ)),
+            _ => return Err(type_error(format!("When resolving type, expected an Object definition, but got {:?}", typ))),
         };
         if let Type::Object(n, _) = &objtype {
             debug_assert_eq!(n, &self.typename);
             Ok(objtype)
         }
         else {
-            Err(format!("When resolving type, expected an Object definition, but got {:?}", objtype))
+            Err(type_error(format!("When resolving type, expected an Object definition, but got {:?}", objtype)))
         }
     }
 }
 
 impl Expression for TypeAnnotation {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         if self.children.is_empty() {
             return match self.typename.as_str() {
                 "Int" => Ok(Type::Int),
@@ -43,13 +43,13 @@ impl Expression for TypeAnnotation {
                 _ => self.resolve_typedef(),
             }
         }
-        let child_types = self.children.iter().map(|a| a.get_type()).collect::<Result<Vec<Type>, String>>()?;
+        let child_types = self.children.iter().map(|a| a.get_type()).collect::<Result<Vec<Type>, CompileError>>()?;
         match self.typename.as_str() {
             "Func" => {
                 if child_types.len() < 1 {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Function must be annotated with at least a return type"
-                    ));
+                    )));
                 }
                 Ok(Type::Func(
                     child_types[..child_types.len()-1].to_vec(),
@@ -58,36 +58,36 @@ impl Expression for TypeAnnotation {
             },
             "Arr" => {
                 if child_types.len() != 1 {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Array must be annotated with exactly one type, but got {:?}",
                         child_types
-                    ));
+                    )));
                 }
                 Ok(Type::Arr(Box::new(child_types[0].clone())))
             },
             "Iter" => {
                 if child_types.len() != 1 {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Iterator must be annotated with exactly one type, but got {:?}",
                         child_types
-                    ));
+                    )));
                 }
                 Ok(Type::Iter(Box::new(child_types[0].clone())))
             },
             "Maybe" => {
                 if child_types.len() != 1 {
-                    return Err(format!(
+                    return Err(type_error(format!(
                         "Maybe must be annotated with exactly one type, but got {:?}",
                         child_types
-                    ));
+                    )));
                 }
                 Ok(Type::Maybe(Box::new(child_types[0].clone())))
             },
-            _ => Err(format!("Unknown type annotation: {}", self.typename))
+            _ => Err(type_error(format!("Unknown type annotation: {}", self.typename)))
         }
     }
     
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         for c in self.children.iter_mut() {
@@ -99,12 +99,66 @@ impl Expression for TypeAnnotation {
         self.parent
     }
 
-    fn compile(&self, _compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, _compiler: &mut Compiler) -> Result<(), CompileError> {
         Ok(())
     }
 }
 
 
+// `expr : Type`, which checks that the expression has the annotated type
+#[derive(Debug)]
+pub struct TypeAscription {
+    expr: Box<dyn Expression>,
+    typ: TypeAnnotation,
+    parent: Option<*const dyn Expression>,
+}
+
+impl TypeAscription {
+    pub fn new(expr: Box<dyn Expression>, typ: TypeAnnotation) -> Self {
+        Self { expr, typ, parent: None }
+    }
+    // used by untyped empty arrays to get their type
+    pub fn ascribed_type(&self) -> Result<Type, CompileError> {
+        self.typ.get_type()
+    }
+    pub fn expr(&self) -> &dyn Expression {
+        self.expr.as_ref()
+    }
+}
+
+impl Expression for TypeAscription {
+    fn get_type(&self) -> Result<Type, CompileError> {
+        let ascribed_type = self.typ.get_type()?;
+        let expr_type = self.expr.get_type()?;
+        if expr_type != ascribed_type {
+            return Err(type_error(format!(
+                "Expression was annotated with type {:?}, but has type {:?}", ascribed_type, expr_type
+            )));
+        }
+        Ok(expr_type)
+    }
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
+        self.parent = parent;
+        let self_ptr = self as *const dyn Expression;
+        self.typ.set_parent(Some(self_ptr))?;
+        self.expr.set_parent(Some(self_ptr))
+    }
+    fn get_parent(&self) -> Option<*const dyn Expression> {
+        self.parent
+    }
+
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.get_type()?;  // just to check that type is valid
+        self.expr.compile(compiler)
+    }
+
+    fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
+        self.get_type()?;  // just to check that type is valid
+        self.expr.wasmize(wasmizer)
+    }
+}
+
+
 #[derive(Debug)]
 pub struct NameAndType {
     pub name: String,
@@ -115,7 +169,7 @@ impl NameAndType {
     pub fn new(name: String, typ: TypeAnnotation) -> Self {
         Self { name, typ }
     }
-    pub fn get_type(&self) -> Result<Type, String> {
+    pub fn get_type(&self) -> Result<Type, CompileError> {
         self.typ.get_type()
     }
 }
@@ -17,7 +17,7 @@ impl TypeDef {
         Self { name, fields, parent: None }
     }
 
-    fn field_types(&self) -> Result<Vec<(String, Type)>, String> {
+    fn field_types(&self) -> Result<Vec<(String, Type)>, CompileError> {
         let mut field_types = Vec::new();
         for p in self.fields.iter() {
             field_types.push((p.name.clone(), p.get_type()?));
@@ -27,7 +27,7 @@ impl TypeDef {
 }
 
 impl Expression for TypeDef {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let field_types = self.field_types()?;
         let types_only = field_types.iter().map(|(_, t)| t.clone()).collect::<Vec<_>>();
         Ok(Type::TypeDef(
@@ -35,7 +35,7 @@ impl Expression for TypeDef {
             Box::new(Type::Object(self.name.clone(), field_types))
         ))
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         for field in self.fields.iter_mut() {
@@ -46,7 +46,7 @@ impl Expression for TypeDef {
     fn get_parent(&self) -> Option<*const dyn Expression> {
         self.parent
     }
-    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, String> {
+    fn find_vartype(&self, name: &String, _upto: *const dyn Expression) -> Result<Option<Type>, CompileError> {
         for f in self.fields.iter() {
             if &f.name == name {
                 return Ok(Some(f.get_type()?));
@@ -55,7 +55,7 @@ impl Expression for TypeDef {
         Ok(None)
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let mut fields = Vec::new();
         for f in self.fields.iter() {
             fields.push((f.name.clone(), f.get_type()?.is_heap()));
@@ -17,19 +17,19 @@ impl Unary {
 }
 
 impl Expression for Unary {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let right_type = self.right.get_type()?;
         if self.op == TokenType::At {
             match right_type {
                 Type::Iter(typ) => Ok(Type::Arr(typ)),
-                x => Err(format!("@ operator must be used with an iterator, got {:?}", x)),
+                x => Err(type_error(format!("@ operator must be used with an iterator, got {:?}", x))),
             }
         }
         else {
             Ok(right_type)
         }
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         let self_ptr = self as *const dyn Expression;
         self.right.set_parent(Some(self_ptr))
@@ -38,38 +38,38 @@ impl Expression for Unary {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         self.right.compile(compiler)?;
         match self.right.get_type()? {
             Type::Int => match self.op {
                 TokenType::Minus => compiler.write_opcode(OpCode::IntNegate),
-                x => return Err(format!(
+                x => return Err(type_error(format!(
                     "Unary operator {:?} is not defined for type Int",
                     x
-                )),
+                ))),
             },
             Type::Float => match self.op {
                 TokenType::Minus => compiler.write_opcode(OpCode::FloatNegate),
-                x => return Err(format!(
+                x => return Err(type_error(format!(
                     "Unary operator {:?} is not defined for type Float",
                     x
-                )),
+                ))),
             },
             Type::Bool => match self.op {
                 TokenType::Bang => compiler.write_opcode(OpCode::Not),
-                x => return Err(format!(
+                x => return Err(type_error(format!(
                     "Unary operator {:?} is not defined for type Bool",
                     x
-                ))
+                )))
             },
             Type::Iter(_) => match self.op {
                 TokenType::At => compiler.write_opcode(OpCode::Collect),
-                x => return Err(format!(
+                x => return Err(type_error(format!(
                     "Unary operator {:?} is not defined for type Iterator",
                     x
-                ))
+                )))
             }
-            x => return Err(format!("Type {:?} not yet supported for unary operation", x)),
+            x => return Err(type_error(format!("Type {:?} not yet supported for unary operation", x))),
         };
         Ok(())
     }
@@ -85,4 +85,7 @@ impl Expression for Unary {
         }
         Ok(0)
     }
+    fn is_inlinable(&self, param: &str) -> bool {
+        matches!(self.op, TokenType::Bang | TokenType::Minus) && self.right.is_inlinable(param)
+    }
 }
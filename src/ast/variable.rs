@@ -13,20 +13,24 @@ impl Variable {
         Self { name, template_params, template_types: vec![], parent: None }
     }
 
-    pub fn set_template_types(&mut self, template_params: Vec<Type>) -> Result<(), String> {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_template_types(&mut self, template_params: Vec<Type>) -> Result<(), CompileError> {
         let my_params = self.template_params.iter().map(|a| a.get_type()).collect::<Result<Vec<_>, _>>()?;
         if !my_params.is_empty() && my_params != template_params {
-            return Err(format!(
+            return Err(type_error(format!(
                 "Template parameters do not match; expected {:?} but got {:?}",
                 my_params, template_params
-            ))
+            )))
         }
         self.template_types = template_params;
         Ok(())
     }
 
     // get name, appending template types if any
-    fn get_expanded_name(&self) -> Result<String, String> {
+    fn get_expanded_name(&self) -> Result<String, CompileError> {
         Ok(if self.template_types.is_empty() {
             let template_types = self.template_params.iter().map(|a| a.get_type()).collect::<Result<Vec<_>, _>>()?;
             if template_types.is_empty() {
@@ -40,14 +44,28 @@ impl Variable {
             format!("{}{:?}", self.name, self.template_types)
         })
     }
+
+    // get the name the variable is stored under
+    // functions with parameters are always stored with their parameter types appended,
+    // so a bare reference to one (e.g. as an array element) needs to be expanded here
+    fn get_storage_name(&self) -> Result<String, CompileError> {
+        let name = self.get_expanded_name()?;
+        if name != self.name {
+            return Ok(name);
+        }
+        Ok(match self.get_type()? {
+            Type::Func(argtypes, _) if !argtypes.is_empty() => format!("{}{:?}", self.name, argtypes),
+            _ => name,
+        })
+    }
 }
 
 impl Expression for Variable {
-    fn get_type(&self) -> Result<Type, String> {
+    fn get_type(&self) -> Result<Type, CompileError> {
         let name = self.get_expanded_name()?;
         resolve_type(&name, self)
     }
-    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), String> {
+    fn set_parent(&mut self, parent: Option<*const dyn Expression>) -> Result<(), CompileError> {
         self.parent = parent;
         Ok(())
     }
@@ -55,14 +73,21 @@ impl Expression for Variable {
         self.parent
     }
 
-    fn compile(&self, compiler: &mut Compiler) -> Result<(), String> {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
         let is_heap = self.get_type()?.is_heap();
-        let name = self.get_expanded_name()?;
+        let name = self.get_storage_name()?;
+        if name != self.name {
+            compiler.note_resolved_name(&self.name, &name);
+        }
         compiler.get_variable(name, is_heap)
     }
 
     fn wasmize(&self, wasmizer: &mut Wasmizer) -> Result<i32, String> {
-        let name = self.get_expanded_name()?;
+        let name = self.get_storage_name()?;
+        #[cfg(feature = "debug")]
+        if name != self.name {
+            println!("resolved {} to {}", self.name, name);
+        }
         // need to figure out whether or not the variable in question is a function within the function's definition
         // i.e., a recursive function call
         let mut name_matches_func = None;
@@ -81,4 +106,7 @@ impl Expression for Variable {
         }
         wasmizer.get_variable(name, &self.get_type()?, name_matches_func)
     }
+    fn is_inlinable(&self, param: &str) -> bool {
+        self.name == param && self.template_params.is_empty() && self.template_types.is_empty()
+    }
 }
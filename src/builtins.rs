@@ -1,9 +1,13 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
 use rustc_hash::FxHashMap;
 
 use lazy_static::lazy_static;
 
 use crate::ast::Type;
-use crate::values::{HeapValue, NativeFunction, Value};
+use crate::values::{ArrayIter, DedupIter, DropIter, HeapValue, IntersperseIter, LazyIter, NativeFunction, Object, RunLengthIter, ScanIter, TakeIter, TypeDef, Value, WindowIter};
+use crate::vm::{ErrorKind, InterpreterError, VM};
 
 lazy_static! {
     static ref PRINTI: NativeFunction = NativeFunction {
@@ -17,80 +21,1017 @@ lazy_static! {
             Ok(())
         }
     };
-    static ref PRINTF: NativeFunction = NativeFunction {
-        name: "printf",
+    static ref PRINTF: NativeFunction = NativeFunction {
+        name: "printf",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            println!("{}", unsafe { args[0].f });
+            vm.stack.push(args[0]);
+            Ok(())
+        }
+    };
+    static ref PRINT: NativeFunction = NativeFunction {
+        name: "print",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            Ok(match &heap_args[0] {
+                HeapValue::String(x) => {
+                    println!("{}", x);
+                    vm.heap_stack.push(HeapValue::String(x.clone()));
+                },
+                _ => unreachable!()
+            })
+        }
+    };
+    static ref ITOF: NativeFunction = NativeFunction {
+        name: "itof",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].i as f64) } });
+            Ok(())
+        }
+    };
+    static ref FTOI: NativeFunction = NativeFunction {
+        name: "ftoi",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { i: (args[0].f as i64) } });
+            Ok(())
+        }
+    };
+    static ref BTOI: NativeFunction = NativeFunction {
+        name: "btoi",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { i: (args[0].b as i64) } });
+            Ok(())
+        }
+    };
+    static ref ITOB: NativeFunction = NativeFunction {
+        name: "itob",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value::from_bool(args[0].i != 0) });
+            Ok(())
+        }
+    };
+
+    static ref MOD: NativeFunction = NativeFunction {
+        name: "mod",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            let (x, m) = unsafe { (args[0].i, args[1].i) };
+            if m == 0 {
+                return Err(vm.runtime_err_of_kind(ErrorKind::DivisionByZero, "Modulo by zero".to_string()));
+            }
+            vm.stack.push(Value::from_i64(x.rem_euclid(m)));
+            Ok(())
+        }
+    };
+    // bit operations work on all 64 bits of an Int, so they can differ from the 32-bit wasm versions
+    // for negative numbers, large numbers, and shifts by 32 or more
+    static ref POPCOUNT: NativeFunction = NativeFunction {
+        name: "popcount",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_i64(unsafe { args[0].i }.count_ones() as i64));
+            Ok(())
+        }
+    };
+    static ref SHL: NativeFunction = NativeFunction {
+        name: "shl",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            // like in wasm, the shift amount wraps around the bit width instead of overflowing
+            let (x, n) = unsafe { (args[0].i, args[1].i) };
+            vm.stack.push(Value::from_i64(x.wrapping_shl(n as u32)));
+            Ok(())
+        }
+    };
+    static ref SHR: NativeFunction = NativeFunction {
+        name: "shr",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            let (x, n) = unsafe { (args[0].i, args[1].i) };
+            vm.stack.push(Value::from_i64(x.wrapping_shr(n as u32)));
+            Ok(())
+        }
+    };
+    static ref BAND: NativeFunction = NativeFunction {
+        name: "band",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_i64(unsafe { args[0].i & args[1].i }));
+            Ok(())
+        }
+    };
+    static ref BOR: NativeFunction = NativeFunction {
+        name: "bor",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_i64(unsafe { args[0].i | args[1].i }));
+            Ok(())
+        }
+    };
+    static ref BXOR: NativeFunction = NativeFunction {
+        name: "bxor",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_i64(unsafe { args[0].i ^ args[1].i }));
+            Ok(())
+        }
+    };
+    static ref POWI: NativeFunction = NativeFunction {
+        name: "powi",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { i: (args[0].i.pow(args[1].i as u32)) } });
+            Ok(())
+        }
+    };
+    static ref POWF: NativeFunction = NativeFunction {
+        name: "powf",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].f.powf(args[1].f)) } });
+            Ok(())
+        }
+    };
+    static ref POWFI: NativeFunction = NativeFunction {
+        name: "powfi",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            let (x, n) = unsafe { (args[0].f, args[1].i) };
+            // powi only takes an i32, so fall back to powf for exponents that don't fit
+            let result = match i32::try_from(n) {
+                Ok(n) => x.powi(n),
+                Err(_) => x.powf(n as f64),
+            };
+            vm.stack.push(Value::from_f64(result));
+            Ok(())
+        }
+    };
+
+    static ref CHARS: NativeFunction = NativeFunction {
+        name: "chars",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    let chars = s.chars().map(|c| HeapValue::String(Rc::new(c.to_string()))).collect::<Vec<_>>();
+                    vm.heap_stack.push(HeapValue::ArrayHeap(Rc::from(chars)));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    // splits on `\n`, also accepting `\r\n` line endings; a trailing newline doesn't give an empty last line
+    static ref LINES: NativeFunction = NativeFunction {
+        name: "lines",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    let lines = s.lines().map(|l| HeapValue::String(Rc::new(l.to_string()))).collect::<Vec<_>>();
+                    vm.heap_stack.push(HeapValue::ArrayHeap(Rc::from(lines)));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    // splits on any run of (unicode) whitespace, ignoring leading and trailing whitespace
+    static ref WORDS: NativeFunction = NativeFunction {
+        name: "words",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    let words = s.split_whitespace().map(|w| HeapValue::String(Rc::new(w.to_string()))).collect::<Vec<_>>();
+                    vm.heap_stack.push(HeapValue::ArrayHeap(Rc::from(words)));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    // splits on a separator and parses each piece as an Int, ignoring whitespace around the pieces
+    // gives null if any piece isn't an Int, and an empty array for an empty (or all-whitespace) string
+    static ref PARSE_INTS: NativeFunction = NativeFunction {
+        name: "parse_ints",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let (s, sep) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(s), HeapValue::String(sep)) => (s, sep),
+                _ => unreachable!()
+            };
+            if sep.is_empty() {
+                return Err(vm.runtime_err("Cannot split on an empty separator".to_string()));
+            }
+            let ints = if s.trim().is_empty() {
+                Some(Vec::new())
+            }
+            else {
+                s.split(sep.as_str())
+                    .map(|piece| piece.trim().parse::<i64>().ok().map(Value::from_i64))
+                    .collect::<Option<Vec<_>>>()
+            };
+            vm.heap_stack.push(HeapValue::MaybeHeap(
+                ints.map(|ints| Box::new(HeapValue::Array(Rc::from(ints))))
+            ));
+            Ok(())
+        }
+    };
+    static ref BYTES: NativeFunction = NativeFunction {
+        name: "bytes",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    let bytes = s.bytes().map(|b| Value::from_i64(b as i64)).collect::<Vec<_>>();
+                    vm.heap_stack.push(HeapValue::Array(Rc::from(bytes)));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+
+    static ref SUB_ARR: NativeFunction = NativeFunction {
+        name: "sub",
+        arity: 2,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let (start, end) = unsafe { (args[0].i, args[1].i) };
+            match &heap_args[0] {
+                HeapValue::Array(arr) => {
+                    let (start, end) = slice_bounds(start, end, arr.len());
+                    vm.heap_stack.push(HeapValue::Array(Rc::from(&arr[start..end])));
+                    Ok(())
+                },
+                HeapValue::ArrayHeap(arr) => {
+                    let (start, end) = slice_bounds(start, end, arr.len());
+                    vm.heap_stack.push(HeapValue::ArrayHeap(Rc::from(&arr[start..end])));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    static ref SUB_STR: NativeFunction = NativeFunction {
+        name: "sub",
+        arity: 2,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let (start, end) = unsafe { (args[0].i, args[1].i) };
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    // indices are in characters, not bytes
+                    let (start, end) = slice_bounds(start, end, s.chars().count());
+                    let sub = s.chars().skip(start).take(end - start).collect::<String>();
+                    vm.heap_stack.push(HeapValue::String(Rc::new(sub)));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+
+    static ref BYTE_LEN: NativeFunction = NativeFunction {
+        name: "byte_len",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: false,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    vm.stack.push(Value::from_i64(s.len() as i64));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    static ref REPEAT_STR: NativeFunction = NativeFunction {
+        name: "repeat_str",
+        arity: 1,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let n = unsafe { args[0].i };
+            if n < 0 {
+                return Err(vm.runtime_err(format!("Cannot repeat a string a negative number of times; got {}", n)));
+            }
+            match &heap_args[0] {
+                HeapValue::String(s) => {
+                    vm.heap_stack.push(HeapValue::String(Rc::new(s.repeat(n as usize))));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+    static ref REPLACE_FIRST: NativeFunction = NativeFunction {
+        name: "replace_first",
+        arity: 0,
+        heap_arity: 3,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| replace_n(vm, heap_args, 1)
+    };
+    static ref REPLACE_N: NativeFunction = NativeFunction {
+        name: "replace_n",
+        arity: 1,
+        heap_arity: 3,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let n = unsafe { args[0].i };
+            if n < 0 {
+                return Err(vm.runtime_err(format!("Cannot replace a negative number of occurrences; got {}", n)));
+            }
+            replace_n(vm, heap_args, n as usize)
+        }
+    };
+    static ref CENTER: NativeFunction = NativeFunction {
+        name: "center",
+        arity: 1,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, args, heap_args| center(vm, args, heap_args, false)
+    };
+    static ref CENTER_TRUNC: NativeFunction = NativeFunction {
+        name: "center_trunc",
+        arity: 1,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, args, heap_args| center(vm, args, heap_args, true)
+    };
+
+    static ref REVERSE: NativeFunction = NativeFunction {
+        name: "reverse",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            match &heap_args[0] {
+                HeapValue::Array(arr) => {
+                    vm.heap_stack.push(HeapValue::Array(arr.iter().rev().copied().collect()));
+                    Ok(())
+                },
+                HeapValue::ArrayHeap(arr) => {
+                    vm.heap_stack.push(HeapValue::ArrayHeap(arr.iter().rev().cloned().collect()));
+                    Ok(())
+                },
+                _ => unreachable!()
+            }
+        }
+    };
+
+    static ref LAZY: NativeFunction = NativeFunction {
+        name: "lazy",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            // wrapping an array in an iterator means later maps and filters over it are evaluated lazily, not eagerly
+            match &heap_args[0] {
+                HeapValue::Array(arr) => vm.heap_stack.push(HeapValue::LazyIter(Box::new(ArrayIter::new(arr.clone())))),
+                HeapValue::ArrayHeap(arr) => vm.heap_stack.push(HeapValue::LazyIterHeap(Box::new(ArrayIter::new(arr.clone())))),
+                _ => unreachable!()
+            }
+            Ok(())
+        }
+    };
+
+    static ref TRANSPOSE: NativeFunction = NativeFunction {
+        name: "transpose",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let rows = match &heap_args[0] {
+                HeapValue::ArrayHeap(rows) => rows,
+                _ => unreachable!()
+            };
+            // rows are arrays of either plain or heap values, depending on the element type
+            let result = match rows.first() {
+                None => Ok(vec![]),
+                Some(HeapValue::Array(_)) => transpose(rows, |row| match row {
+                    HeapValue::Array(row) => row,
+                    _ => unreachable!()
+                }).map(|columns| columns.into_iter().map(HeapValue::Array).collect()),
+                Some(HeapValue::ArrayHeap(_)) => transpose(rows, |row| match row {
+                    HeapValue::ArrayHeap(row) => row,
+                    _ => unreachable!()
+                }).map(|columns| columns.into_iter().map(HeapValue::ArrayHeap).collect()),
+                _ => unreachable!()
+            };
+            match result {
+                Ok(columns) => {
+                    vm.heap_stack.push(HeapValue::ArrayHeap(Rc::from(columns)));
+                    Ok(())
+                }
+                Err(e) => Err(vm.runtime_err(e)),
+            }
+        }
+    };
+
+    static ref RUNNING_SUM_INT: NativeFunction = NativeFunction {
+        name: "running_sum",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { i: acc.i.wrapping_add(x.i) } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+    static ref RUNNING_SUM_FLOAT: NativeFunction = NativeFunction {
+        name: "running_sum",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { f: acc.f + x.f } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+    static ref RUNNING_MAX_INT: NativeFunction = NativeFunction {
+        name: "running_max",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { i: acc.i.max(x.i) } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+    static ref RUNNING_MAX_FLOAT: NativeFunction = NativeFunction {
+        name: "running_max",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { f: acc.f.max(x.f) } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+    static ref CUMULATIVE_PRODUCT_INT: NativeFunction = NativeFunction {
+        name: "cumulative_product",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { i: acc.i.wrapping_mul(x.i) } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+    static ref CUMULATIVE_PRODUCT_FLOAT: NativeFunction = NativeFunction {
+        name: "cumulative_product",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter = running(&heap_args[0], |acc, x| unsafe { Value { f: acc.f * x.f } });
+            vm.heap_stack.push(iter);
+            Ok(())
+        }
+    };
+
+    static ref DEDUP_INT: NativeFunction = NativeFunction {
+        name: "dedup",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(dedup(&heap_args[0], |a, b| unsafe { a.i == b.i }));
+            Ok(())
+        }
+    };
+    static ref DEDUP_FLOAT: NativeFunction = NativeFunction {
+        name: "dedup",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(dedup(&heap_args[0], |a, b| unsafe { a.f == b.f }));
+            Ok(())
+        }
+    };
+    static ref DEDUP_BOOL: NativeFunction = NativeFunction {
+        name: "dedup",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(dedup(&heap_args[0], |a, b| unsafe { a.b == b.b }));
+            Ok(())
+        }
+    };
+    static ref DEDUP_STR: NativeFunction = NativeFunction {
+        name: "dedup",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter: Box<dyn LazyIter<HeapValue>> = match &heap_args[0] {
+                HeapValue::LazyIterHeap(iter) => iter.clone(),
+                HeapValue::ArrayHeap(arr) => Box::new(ArrayIter::new(arr.clone())),
+                _ => unreachable!()
+            };
+            let iter = DedupIter::new(iter, |a, b| match (a, b) {
+                (HeapValue::String(a), HeapValue::String(b)) => a == b,
+                _ => unreachable!()
+            });
+            vm.heap_stack.push(HeapValue::LazyIterHeap(Box::new(iter)));
+            Ok(())
+        }
+    };
+
+    static ref SORT_INT: NativeFunction = NativeFunction {
+        name: "sort",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { a.i.cmp(&b.i) }));
+            Ok(())
+        }
+    };
+    static ref SORT_DESC_INT: NativeFunction = NativeFunction {
+        name: "sort_desc",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { b.i.cmp(&a.i) }));
+            Ok(())
+        }
+    };
+    static ref SORT_FLOAT: NativeFunction = NativeFunction {
+        name: "sort",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { float_order(a.f, b.f, false) }));
+            Ok(())
+        }
+    };
+    static ref SORT_DESC_FLOAT: NativeFunction = NativeFunction {
+        name: "sort_desc",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { float_order(a.f, b.f, true) }));
+            Ok(())
+        }
+    };
+    static ref SORT_BOOL: NativeFunction = NativeFunction {
+        name: "sort",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { a.b.cmp(&b.b) }));
+            Ok(())
+        }
+    };
+    static ref SORT_DESC_BOOL: NativeFunction = NativeFunction {
+        name: "sort_desc",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_values(&heap_args[0], |a, b| unsafe { b.b.cmp(&a.b) }));
+            Ok(())
+        }
+    };
+    static ref SORT_STR: NativeFunction = NativeFunction {
+        name: "sort",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_strs(&heap_args[0], false));
+            Ok(())
+        }
+    };
+    static ref SORT_DESC_STR: NativeFunction = NativeFunction {
+        name: "sort_desc",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(sort_strs(&heap_args[0], true));
+            Ok(())
+        }
+    };
+
+    static ref RUN_LENGTH_INT: NativeFunction = NativeFunction {
+        name: "run_length",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(run_length(&heap_args[0], |a, b| unsafe { a.i == b.i }));
+            Ok(())
+        }
+    };
+    static ref RUN_LENGTH_FLOAT: NativeFunction = NativeFunction {
+        name: "run_length",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(run_length(&heap_args[0], |a, b| unsafe { a.f == b.f }));
+            Ok(())
+        }
+    };
+    static ref RUN_LENGTH_BOOL: NativeFunction = NativeFunction {
+        name: "run_length",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(run_length(&heap_args[0], |a, b| unsafe { a.b == b.b }));
+            Ok(())
+        }
+    };
+    static ref RUN_LENGTH_STR: NativeFunction = NativeFunction {
+        name: "run_length",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let iter: Box<dyn LazyIter<HeapValue>> = match &heap_args[0] {
+                HeapValue::LazyIterHeap(iter) => iter.clone(),
+                HeapValue::ArrayHeap(arr) => Box::new(ArrayIter::new(arr.clone())),
+                _ => unreachable!()
+            };
+            let iter = RunLengthIter::new(
+                iter,
+                Rc::new(run_length_typedef(true)),
+                |a, b| match (a, b) {
+                    (HeapValue::String(a), HeapValue::String(b)) => a == b,
+                    _ => unreachable!()
+                },
+                |obj, x| { obj.heap_fields.insert("value".to_string(), x); },
+            );
+            vm.heap_stack.push(HeapValue::LazyIterHeap(Box::new(iter)));
+            Ok(())
+        }
+    };
+
+    static ref FORMAT: NativeFunction = NativeFunction {
+        name: "format",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let (template, args) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(template), HeapValue::ArrayHeap(args)) => (template, args),
+                _ => unreachable!()
+            };
+            match format_template(template, args) {
+                Ok(s) => {
+                    vm.heap_stack.push(HeapValue::String(Rc::new(s)));
+                    Ok(())
+                }
+                Err(e) => Err(vm.runtime_err(e)),
+            }
+        }
+    };
+
+    static ref FIND_STR: NativeFunction = NativeFunction {
+        name: "find",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let (s, sub) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(s), HeapValue::String(sub)) => (s, sub),
+                _ => unreachable!()
+            };
+            // str::find gives a byte offset, which is converted to an index in characters
+            let index = s.find(sub.as_str()).map(|byte_index| {
+                Value::from_i64(s.char_indices().take_while(|(i, _)| *i < byte_index).count() as i64)
+            });
+            vm.heap_stack.push(HeapValue::Maybe(index));
+            Ok(())
+        }
+    };
+
+    // case-insensitive comparisons use full Unicode lowercasing, so they also work for non-ASCII letters (e.g. "É" and "é")
+    static ref EQ_IGNORE_CASE: NativeFunction = NativeFunction {
+        name: "eq_ignore_case",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: false,
+        function: |vm, _args, heap_args| {
+            let (a, b) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(a), HeapValue::String(b)) => (a, b),
+                _ => unreachable!()
+            };
+            vm.stack.push(Value::from_bool(a.to_lowercase() == b.to_lowercase()));
+            Ok(())
+        }
+    };
+    static ref CONTAINS_IGNORE_CASE: NativeFunction = NativeFunction {
+        name: "contains_ignore_case",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: false,
+        function: |vm, _args, heap_args| {
+            let (s, sub) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(s), HeapValue::String(sub)) => (s, sub),
+                _ => unreachable!()
+            };
+            vm.stack.push(Value::from_bool(s.to_lowercase().contains(&sub.to_lowercase())));
+            Ok(())
+        }
+    };
+    // counts non-overlapping occurrences, scanning from the left; an empty substring is an error
+    static ref COUNT_STR: NativeFunction = NativeFunction {
+        name: "count_str",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: false,
+        function: |vm, _args, heap_args| {
+            let (s, sub) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(s), HeapValue::String(sub)) => (s, sub),
+                _ => unreachable!()
+            };
+            if sub.is_empty() {
+                return Err(vm.runtime_err("Cannot count occurrences of an empty string".to_string()));
+            }
+            vm.stack.push(Value::from_i64(s.matches(sub.as_str()).count() as i64));
+            Ok(())
+        }
+    };
+    static ref CHAR_CODE: NativeFunction = NativeFunction {
+        name: "char_code",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: false,
+        function: |vm, _args, heap_args| {
+            let s = match &heap_args[0] {
+                HeapValue::String(s) => s,
+                _ => unreachable!()
+            };
+            match s.chars().next() {
+                Some(c) => vm.stack.push(Value::from_i64(c as i64)),
+                None => return Err(vm.runtime_err("Cannot get the character code of an empty string".to_string())),
+            }
+            Ok(())
+        }
+    };
+    static ref FROM_CHAR_CODE: NativeFunction = NativeFunction {
+        name: "from_char_code",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: true,
+        function: |vm, args, _heap_args| {
+            // null for negative numbers, surrogates, and anything past the last code point
+            let c = u32::try_from(unsafe { args[0].i }).ok().and_then(char::from_u32);
+            vm.heap_stack.push(HeapValue::MaybeHeap(
+                c.map(|c| Box::new(HeapValue::String(Rc::new(c.to_string()))))
+            ));
+            Ok(())
+        }
+    };
+
+    static ref SPLIT_ONCE: NativeFunction = NativeFunction {
+        name: "split_once",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let (s, sep) = match (&heap_args[0], &heap_args[1]) {
+                (HeapValue::String(s), HeapValue::String(sep)) => (s, sep),
+                _ => unreachable!()
+            };
+            let split = s.split_once(sep.as_str()).map(|(before, after)| {
+                let mut heap_fields = FxHashMap::default();
+                heap_fields.insert("before".to_string(), HeapValue::String(Rc::new(before.to_string())));
+                heap_fields.insert("after".to_string(), HeapValue::String(Rc::new(after.to_string())));
+                Box::new(HeapValue::Object(Rc::new(
+                    Object::new(Rc::new(split_typedef()), FxHashMap::default(), heap_fields)
+                )))
+            });
+            vm.heap_stack.push(HeapValue::MaybeHeap(split));
+            Ok(())
+        }
+    };
+
+    static ref FIRST: NativeFunction = NativeFunction {
+        name: "first",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(end_elem(&heap_args[0], false));
+            Ok(())
+        }
+    };
+    static ref LAST: NativeFunction = NativeFunction {
+        name: "last",
+        arity: 0,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            vm.heap_stack.push(end_elem(&heap_args[0], true));
+            Ok(())
+        }
+    };
+
+    static ref INTERSPERSE: NativeFunction = NativeFunction {
+        name: "intersperse",
         arity: 1,
-        heap_arity: 0,
-        return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            println!("{}", unsafe { args[0].f });
-            vm.stack.push(args[0]);
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let iter = match &heap_args[0] {
+                HeapValue::LazyIter(iter) => iter.clone(),
+                HeapValue::Array(arr) => Box::new(ArrayIter::new(arr.clone())),
+                _ => unreachable!()
+            };
+            vm.heap_stack.push(HeapValue::LazyIter(Box::new(IntersperseIter::new(iter, args[0]))));
             Ok(())
         }
     };
-    static ref PRINT: NativeFunction = NativeFunction {
-        name: "print",
+    static ref INTERSPERSE_HEAP: NativeFunction = NativeFunction {
+        name: "intersperse",
         arity: 0,
-        heap_arity: 1,
+        heap_arity: 2,
         return_is_heap: true,
         function: |vm, _args, heap_args| {
-            Ok(match &heap_args[0] {
-                HeapValue::String(x) => {
-                    println!("{}", x);
-                    vm.heap_stack.push(HeapValue::String(x.clone()));
-                },
+            let iter = match &heap_args[0] {
+                HeapValue::LazyIterHeap(iter) => iter.clone(),
+                HeapValue::ArrayHeap(arr) => Box::new(ArrayIter::new(arr.clone())),
                 _ => unreachable!()
-            })
+            };
+            vm.heap_stack.push(HeapValue::LazyIterHeap(Box::new(IntersperseIter::new(iter, heap_args[1].clone()))));
+            Ok(())
         }
     };
-    static ref ITOF: NativeFunction = NativeFunction {
-        name: "itof",
+
+    static ref TAKE: NativeFunction = NativeFunction {
+        name: "take",
         arity: 1,
-        heap_arity: 0,
-        return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            vm.stack.push(unsafe { Value { f: (args[0].i as f64) } });
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let n = iter_count(vm, args[0], "take")?;
+            vm.heap_stack.push(match &heap_args[0] {
+                HeapValue::LazyIter(iter) => HeapValue::LazyIter(Box::new(TakeIter::new(iter.clone(), n))),
+                HeapValue::Array(arr) => HeapValue::LazyIter(Box::new(TakeIter::new(Box::new(ArrayIter::new(arr.clone())), n))),
+                HeapValue::LazyIterHeap(iter) => HeapValue::LazyIterHeap(Box::new(TakeIter::new(iter.clone(), n))),
+                HeapValue::ArrayHeap(arr) => HeapValue::LazyIterHeap(Box::new(TakeIter::new(Box::new(ArrayIter::new(arr.clone())), n))),
+                _ => unreachable!()
+            });
             Ok(())
         }
     };
-    static ref FTOI: NativeFunction = NativeFunction {
-        name: "ftoi",
+    static ref DROP: NativeFunction = NativeFunction {
+        name: "drop",
         arity: 1,
-        heap_arity: 0,
-        return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            vm.stack.push(unsafe { Value { i: (args[0].f as i64) } });
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let n = iter_count(vm, args[0], "drop")?;
+            vm.heap_stack.push(match &heap_args[0] {
+                HeapValue::LazyIter(iter) => HeapValue::LazyIter(Box::new(DropIter::new(iter.clone(), n))),
+                HeapValue::Array(arr) => HeapValue::LazyIter(Box::new(DropIter::new(Box::new(ArrayIter::new(arr.clone())), n))),
+                HeapValue::LazyIterHeap(iter) => HeapValue::LazyIterHeap(Box::new(DropIter::new(iter.clone(), n))),
+                HeapValue::ArrayHeap(arr) => HeapValue::LazyIterHeap(Box::new(DropIter::new(Box::new(ArrayIter::new(arr.clone())), n))),
+                _ => unreachable!()
+            });
             Ok(())
         }
     };
 
-    static ref MOD: NativeFunction = NativeFunction {
-        name: "mod",
-        arity: 2,
-        heap_arity: 0,
-        return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            vm.stack.push(unsafe { Value { i: (args[0].i.rem_euclid(args[1].i)) } });
+    static ref WINDOWS: NativeFunction = NativeFunction {
+        name: "windows",
+        arity: 1,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let size = unsafe { args[0].i };
+            if size <= 0 {
+                return Err(vm.runtime_err(format!("Window size must be positive; got {}", size)));
+            }
+            let size = size as usize;
+            let iter: Box<dyn LazyIter<HeapValue>> = match &heap_args[0] {
+                HeapValue::LazyIter(iter) => Box::new(WindowIter::new(iter.clone(), size, HeapValue::Array)),
+                HeapValue::Array(arr) => Box::new(WindowIter::new(Box::new(ArrayIter::new(arr.clone())), size, HeapValue::Array)),
+                HeapValue::LazyIterHeap(iter) => Box::new(WindowIter::new(iter.clone(), size, HeapValue::ArrayHeap)),
+                HeapValue::ArrayHeap(arr) => Box::new(WindowIter::new(Box::new(ArrayIter::new(arr.clone())), size, HeapValue::ArrayHeap)),
+                _ => unreachable!()
+            };
+            vm.heap_stack.push(HeapValue::LazyIterHeap(iter));
             Ok(())
         }
     };
-    static ref POWI: NativeFunction = NativeFunction {
-        name: "powi",
-        arity: 2,
-        heap_arity: 0,
-        return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            vm.stack.push(unsafe { Value { i: (args[0].i.pow(args[1].i as u32)) } });
+    static ref CHUNK: NativeFunction = NativeFunction {
+        name: "chunk",
+        arity: 1,
+        heap_arity: 1,
+        return_is_heap: true,
+        function: |vm, args, heap_args| {
+            let size = unsafe { args[0].i };
+            if size <= 0 {
+                return Err(vm.runtime_err(format!("Chunk size must be positive; got {}", size)));
+            }
+            let size = size as usize;
+            // the last chunk is shorter if the length isn't a multiple of the chunk size
+            let chunks = match &heap_args[0] {
+                HeapValue::Array(arr) => arr.chunks(size).map(|c| HeapValue::Array(c.into())).collect(),
+                HeapValue::ArrayHeap(arr) => arr.chunks(size).map(|c| HeapValue::ArrayHeap(c.into())).collect(),
+                _ => unreachable!()
+            };
+            vm.heap_stack.push(HeapValue::ArrayHeap(chunks));
             Ok(())
         }
     };
-    static ref POWF: NativeFunction = NativeFunction {
-        name: "powf",
-        arity: 2,
-        heap_arity: 0,
+
+    static ref FIRST_OR: NativeFunction = NativeFunction {
+        name: "first_or",
+        arity: 1,
+        heap_arity: 1,
         return_is_heap: false,
-        function: |vm, args, _heap_args| {
-            vm.stack.push(unsafe { Value { f: (args[0].f.powf(args[1].f)) } });
+        function: |vm, args, heap_args| {
+            let first = match &heap_args[0] {
+                HeapValue::LazyIter(iter) => iter.clone().next(),
+                HeapValue::Array(arr) => arr.first().copied(),
+                _ => unreachable!()
+            };
+            vm.stack.push(first.unwrap_or(args[0]));
+            Ok(())
+        }
+    };
+    static ref FIRST_OR_HEAP: NativeFunction = NativeFunction {
+        name: "first_or",
+        arity: 0,
+        heap_arity: 2,
+        return_is_heap: true,
+        function: |vm, _args, heap_args| {
+            let first = match &heap_args[0] {
+                HeapValue::LazyIterHeap(iter) => iter.clone().next(),
+                HeapValue::ArrayHeap(arr) => arr.first().cloned(),
+                _ => unreachable!()
+            };
+            vm.heap_stack.push(first.unwrap_or_else(|| heap_args[1].clone()));
             Ok(())
         }
     };
@@ -133,6 +1074,119 @@ lazy_static! {
             Ok(())
         }
     };
+    static ref HYPOT: NativeFunction = NativeFunction {
+        name: "hypot",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].f.hypot(args[1].f)) } });
+            Ok(())
+        }
+    };
+    static ref CLAMP01: NativeFunction = NativeFunction {
+        name: "clamp01",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].f.clamp(0.0, 1.0)) } });
+            Ok(())
+        }
+    };
+    static ref LERP: NativeFunction = NativeFunction {
+        name: "lerp",
+        arity: 3,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            let (a, b, t) = unsafe { (args[0].f, args[1].f, args[2].f) };
+            vm.stack.push(Value { f: a + (b - a) * t });
+            Ok(())
+        }
+    };
+    static ref ABS_DIFF_INT: NativeFunction = NativeFunction {
+        name: "abs_diff",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            // wraps instead of overflowing when the difference doesn't fit in an Int
+            let (a, b) = unsafe { (args[0].i, args[1].i) };
+            vm.stack.push(Value::from_i64(a.abs_diff(b) as i64));
+            Ok(())
+        }
+    };
+    static ref ABS_DIFF_FLOAT: NativeFunction = NativeFunction {
+        name: "abs_diff",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].f - args[1].f).abs() } });
+            Ok(())
+        }
+    };
+    static ref IS_FINITE: NativeFunction = NativeFunction {
+        name: "is_finite",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_bool(unsafe { args[0].f }.is_finite()));
+            Ok(())
+        }
+    };
+    static ref IS_INF: NativeFunction = NativeFunction {
+        name: "is_inf",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(Value::from_bool(unsafe { args[0].f }.is_infinite()));
+            Ok(())
+        }
+    };
+    static ref ATAN2: NativeFunction = NativeFunction {
+        name: "atan2",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(unsafe { Value { f: (args[0].f.atan2(args[1].f)) } });
+            Ok(())
+        }
+    };
+    static ref RAND_INT: NativeFunction = NativeFunction {
+        name: "rand_int",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            let (lo, hi) = unsafe { (args[0].i, args[1].i) };
+            if lo > hi {
+                return Err(vm.runtime_err(format!("rand_int expected lo <= hi; got {} and {}", lo, hi)));
+            }
+            // number of possible values, which wraps to 0 when the range covers every Int
+            let span = (hi.wrapping_sub(lo) as u64).wrapping_add(1);
+            let bits = vm.next_random();
+            let offset = if span == 0 { bits } else { ((bits as u128 * span as u128) >> 64) as u64 };
+            vm.stack.push(Value::from_i64(lo.wrapping_add(offset as i64)));
+            Ok(())
+        }
+    };
+    static ref RAND_FLOAT: NativeFunction = NativeFunction {
+        name: "rand_float",
+        arity: 0,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, _args, _heap_args| {
+            // uniform in [0, 1), using the top 53 bits
+            let f = (vm.next_random() >> 11) as f64 / (1u64 << 53) as f64;
+            vm.stack.push(Value::from_f64(f));
+            Ok(())
+        }
+    };
 
     static ref SUMF: NativeFunction = NativeFunction {
         name: "sumf",
@@ -200,6 +1254,241 @@ lazy_static! {
     };
 }
 
+// convert start (inclusive) and end (exclusive) indices into a valid range for a collection of length len
+// negative indices count from the end, out-of-bounds indices are clamped, and reversed ranges are empty
+fn slice_bounds(start: i64, end: i64, len: usize) -> (usize, usize) {
+    let len = len as i64;
+    let clamp = |idx: i64| {
+        let idx = if idx < 0 { len + idx } else { idx };
+        idx.clamp(0, len) as usize
+    };
+    let (start, end) = (clamp(start), clamp(end));
+    (start, end.max(start))
+}
+
+// create an iterator over the running results of applying reducer to an array or iterator
+fn running(iter_over: &HeapValue, reducer: fn(Value, Value) -> Value) -> HeapValue {
+    let iter: Box<dyn LazyIter<Value>> = match iter_over {
+        HeapValue::LazyIter(iter) => iter.clone(),
+        HeapValue::Array(arr) => Box::new(ArrayIter::new(arr.clone())),
+        _ => unreachable!()
+    };
+    HeapValue::LazyIter(Box::new(ScanIter::new(iter, reducer)))
+}
+
+// create an iterator that skips consecutive duplicates in an array or iterator of scalar values
+fn dedup(iter_over: &HeapValue, eq: fn(&Value, &Value) -> bool) -> HeapValue {
+    let iter: Box<dyn LazyIter<Value>> = match iter_over {
+        HeapValue::LazyIter(iter) => iter.clone(),
+        HeapValue::Array(arr) => Box::new(ArrayIter::new(arr.clone())),
+        _ => unreachable!()
+    };
+    HeapValue::LazyIter(Box::new(DedupIter::new(iter, eq)))
+}
+
+// sorts the elements of an array or iterator of scalar values into a new array
+// sort_by is stable, so elements that compare equal keep their order
+fn sort_values(iter_over: &HeapValue, cmp: fn(&Value, &Value) -> Ordering) -> HeapValue {
+    let mut elems = match iter_over {
+        HeapValue::Array(arr) => arr.to_vec(),
+        HeapValue::LazyIter(iter) => iter.clone().into_array().to_vec(),
+        _ => unreachable!()
+    };
+    elems.sort_by(cmp);
+    HeapValue::Array(elems.into())
+}
+
+fn sort_strs(iter_over: &HeapValue, descending: bool) -> HeapValue {
+    let mut elems = match iter_over {
+        HeapValue::ArrayHeap(arr) => arr.to_vec(),
+        HeapValue::LazyIterHeap(iter) => iter.clone().into_array().to_vec(),
+        _ => unreachable!()
+    };
+    elems.sort_by(|a, b| match (a, b) {
+        (HeapValue::String(a), HeapValue::String(b)) => if descending { b.cmp(a) } else { a.cmp(b) },
+        _ => unreachable!()
+    });
+    HeapValue::ArrayHeap(elems.into())
+}
+
+// NaN is sorted after all other floats, whether sorting in ascending or descending order
+fn float_order(a: f64, b: f64, descending: bool) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) if descending => b.partial_cmp(&a).unwrap(),
+        (false, false) => a.partial_cmp(&b).unwrap(),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+// the number of elements for take or drop, which can't be negative
+fn iter_count(vm: &VM, n: Value, name: &str) -> Result<usize, InterpreterError> {
+    let n = unsafe { n.i };
+    if n < 0 {
+        return Err(vm.runtime_err(format!("Number of elements to {} must not be negative; got {}", name, n)));
+    }
+    Ok(n as usize)
+}
+
+fn run_length(iter_over: &HeapValue, eq: fn(&Value, &Value) -> bool) -> HeapValue {
+    let iter: Box<dyn LazyIter<Value>> = match iter_over {
+        HeapValue::LazyIter(iter) => iter.clone(),
+        HeapValue::Array(arr) => Box::new(ArrayIter::new(arr.clone())),
+        _ => unreachable!()
+    };
+    let iter = RunLengthIter::new(
+        iter,
+        Rc::new(run_length_typedef(false)),
+        eq,
+        |obj, x| { obj.fields.insert("value".to_string(), x); },
+    );
+    HeapValue::LazyIterHeap(Box::new(iter))
+}
+
+// the type `Run { value, count }` of the objects yielded by run_length
+// a program can declare `Run := type { value: T, count: Int }` to write functions that take runs of T
+fn run_length_typedef(value_is_heap: bool) -> TypeDef {
+    TypeDef::new(
+        "Run".to_string(),
+        vec![("value".to_string(), value_is_heap), ("count".to_string(), false)]
+    )
+}
+
+fn run_length_type(typ: Type) -> Type {
+    Type::Object("Run".to_string(), vec![("value".to_string(), typ), ("count".to_string(), Type::Int)])
+}
+
+// the type `Split { before, after }` of the objects returned by split_once
+// a program can declare `Split := type { before: Str, after: Str }` to give a default to unwrap
+fn split_typedef() -> TypeDef {
+    TypeDef::new(
+        "Split".to_string(),
+        vec![("before".to_string(), true), ("after".to_string(), true)]
+    )
+}
+
+fn split_type() -> Type {
+    Type::Object("Split".to_string(), vec![("before".to_string(), Type::Str), ("after".to_string(), Type::Str)])
+}
+
+// centers heap_args[0] in a string args[0] characters wide, padding with the single character heap_args[1]
+// when the padding can't be split evenly, the extra character goes on the right
+// strings longer than the width are returned unchanged, or cut down to their first `width` characters if truncate is set
+fn center(vm: &mut VM, args: &[Value], heap_args: &[HeapValue], truncate: bool) -> Result<(), InterpreterError> {
+    let (s, fill) = match (&heap_args[0], &heap_args[1]) {
+        (HeapValue::String(s), HeapValue::String(fill)) => (s, fill),
+        _ => unreachable!()
+    };
+    let width = unsafe { args[0].i };
+    if width < 0 {
+        return Err(vm.runtime_err(format!("Cannot center a string in a negative width; got {}", width)));
+    }
+    let mut fill_chars = fill.chars();
+    let fill = match (fill_chars.next(), fill_chars.next()) {
+        (Some(c), None) => c,
+        _ => return Err(vm.runtime_err(format!("Fill for center must be a single character; got {:?}", fill))),
+    };
+    let width = width as usize;
+    let len = s.chars().count();
+    let result = if len >= width {
+        if truncate { s.chars().take(width).collect() } else { s.to_string() }
+    }
+    else {
+        let left = (width - len) / 2;
+        let right = width - len - left;
+        let mut result = String::with_capacity(s.len() + (left + right) * fill.len_utf8());
+        result.extend(std::iter::repeat_n(fill, left));
+        result.push_str(s);
+        result.extend(std::iter::repeat_n(fill, right));
+        result
+    };
+    vm.heap_stack.push(HeapValue::String(Rc::new(result)));
+    Ok(())
+}
+
+// replaces the first n non-overlapping occurrences of heap_args[1] in heap_args[0] with heap_args[2]
+fn replace_n(vm: &mut VM, heap_args: &[HeapValue], n: usize) -> Result<(), InterpreterError> {
+    let (s, from, to) = match (&heap_args[0], &heap_args[1], &heap_args[2]) {
+        (HeapValue::String(s), HeapValue::String(from), HeapValue::String(to)) => (s, from, to),
+        _ => unreachable!()
+    };
+    if from.is_empty() {
+        return Err(vm.runtime_err("Cannot replace occurrences of an empty string".to_string()));
+    }
+    vm.heap_stack.push(HeapValue::String(Rc::new(s.replacen(from.as_str(), to, n))));
+    Ok(())
+}
+
+// gets the first (or last) element of an array, or character of a string, wrapped in a Maybe that is null if it is empty
+fn end_elem(hv: &HeapValue, from_end: bool) -> HeapValue {
+    match hv {
+        HeapValue::Array(arr) => HeapValue::Maybe(
+            if from_end { arr.last() } else { arr.first() }.copied()
+        ),
+        HeapValue::ArrayHeap(arr) => HeapValue::MaybeHeap(
+            if from_end { arr.last() } else { arr.first() }.cloned().map(Box::new)
+        ),
+        HeapValue::String(s) => HeapValue::MaybeHeap(
+            if from_end { s.chars().last() } else { s.chars().next() }
+                .map(|c| Box::new(HeapValue::String(Rc::new(c.to_string()))))
+        ),
+        _ => unreachable!()
+    }
+}
+
+// swaps the rows and columns of a nested array, requiring that all rows have the same length
+fn transpose<T: Clone>(rows: &[HeapValue], unwrap: fn(&HeapValue) -> &Rc<[T]>) -> Result<Vec<Rc<[T]>>, String> {
+    let width = unwrap(&rows[0]).len();
+    if let Some(i) = rows.iter().position(|row| unwrap(row).len() != width) {
+        return Err(format!(
+            "Cannot transpose ragged array: row 0 has length {} but row {} has length {}",
+            width, i, unwrap(&rows[i]).len()
+        ));
+    }
+    Ok((0..width).map(|j| rows.iter().map(|row| unwrap(row)[j].clone()).collect()).collect())
+}
+
+// replace placeholders like `{0}` in template with the corresponding entry of args
+// literal braces are written as `{{` and `}}`
+fn format_template(template: &str, args: &[HeapValue]) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(format!("Unclosed placeholder in format template `{}`", template)),
+                    }
+                }
+                let i = index.parse::<usize>().map_err(|_| format!(
+                    "Invalid placeholder `{{{}}}` in format template; expected a non-negative integer index", index
+                ))?;
+                match args.get(i) {
+                    Some(HeapValue::String(s)) => result.push_str(s),
+                    Some(_) => unreachable!(),
+                    None => return Err(format!(
+                        "Placeholder `{{{}}}` is out of range for {} format arguments", i, args.len()
+                    )),
+                }
+            }
+            '}' => return Err(format!("Unmatched `}}` in format template `{}`; use `}}}}` for a literal brace", template)),
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
 pub fn builtin_types() -> FxHashMap<String, Type> {
     let mut map = FxHashMap::default();
     map.insert("print[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Int)));
@@ -207,12 +1496,190 @@ pub fn builtin_types() -> FxHashMap<String, Type> {
     map.insert("print[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Str)));
     map.insert("float[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Float)));
     map.insert("int[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Int)));
+    map.insert("int[Bool]".to_string(), Type::Func(vec![Type::Bool], Box::new(Type::Int)));
+    map.insert("bool[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Bool)));
 
     map.insert("mod[Int, Int]".to_string(), Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)));
+    map.insert("popcount[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Int)));
+    for name in ["shl", "shr", "band", "bor", "bxor"] {
+        map.insert(format!("{}[Int, Int]", name), Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)));
+    }
     map.insert("pow[Int, Int]".to_string(), Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)));
     map.insert("pow[Float, Float]".to_string(), Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)));
+    map.insert("pow[Float, Int]".to_string(), Type::Func(vec![Type::Float, Type::Int], Box::new(Type::Float)));
 
     map.insert("sqrt[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Float)));
+    map.insert("hypot[Float, Float]".to_string(), Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)));
+    map.insert("atan2[Float, Float]".to_string(), Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)));
+    map.insert("clamp01[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Float)));
+    map.insert("lerp[Float, Float, Float]".to_string(), Type::Func(vec![Type::Float, Type::Float, Type::Float], Box::new(Type::Float)));
+    map.insert("abs_diff[Int, Int]".to_string(), Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)));
+    map.insert("abs_diff[Float, Float]".to_string(), Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)));
+    map.insert("is_finite[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Bool)));
+    map.insert("is_inf[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Bool)));
+
+    map.insert("rand_int[Int, Int]".to_string(), Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)));
+    map.insert("rand_float".to_string(), Type::Func(vec![], Box::new(Type::Float)));
+
+    map.insert("chars[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Arr(Box::new(Type::Str)))));
+    map.insert("bytes[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Arr(Box::new(Type::Int)))));
+    map.insert("lines[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Arr(Box::new(Type::Str)))));
+    map.insert("words[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Arr(Box::new(Type::Str)))));
+    map.insert("parse_ints[Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Maybe(Box::new(Type::Arr(Box::new(Type::Int)))))));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ));
+        map.insert(
+            format!("sub[{:?}, Int, Int]", arr_type),
+            Type::Func(vec![arr_type.clone(), Type::Int, Type::Int], Box::new(arr_type))
+        );
+    }
+    map.insert("sub[Str, Int, Int]".to_string(), Type::Func(vec![Type::Str, Type::Int, Type::Int], Box::new(Type::Str)));
+    map.insert("byte_len[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Int)));
+    map.insert("repeat_str[Str, Int]".to_string(), Type::Func(vec![Type::Str, Type::Int], Box::new(Type::Str)));
+    map.insert("replace_first[Str, Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str, Type::Str], Box::new(Type::Str)));
+    map.insert("replace_n[Str, Str, Str, Int]".to_string(), Type::Func(vec![Type::Str, Type::Str, Type::Str, Type::Int], Box::new(Type::Str)));
+    for name in ["center", "center_trunc"] {
+        map.insert(format!("{}[Str, Int, Str]", name), Type::Func(vec![Type::Str, Type::Int, Type::Str], Box::new(Type::Str)));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ));
+        map.insert(format!("reverse[{:?}]", arr_type), Type::Func(vec![arr_type.clone()], Box::new(arr_type)));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        map.insert(
+            format!("lazy[{:?}]", Type::Arr(Box::new(typ.clone()))),
+            Type::Func(vec![Type::Arr(Box::new(typ.clone()))], Box::new(Type::Iter(Box::new(typ))))
+        );
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let matrix_type = Type::Arr(Box::new(Type::Arr(Box::new(typ))));
+        map.insert(format!("transpose[{:?}]", matrix_type), Type::Func(vec![matrix_type.clone()], Box::new(matrix_type)));
+    }
+
+    for typ in [Type::Int, Type::Float] {
+        let iter_type = Type::Iter(Box::new(typ.clone()));
+        for arg_type in [Type::Arr(Box::new(typ.clone())), iter_type.clone()] {
+            for name in ["running_sum", "running_max", "cumulative_product"] {
+                map.insert(
+                    format!("{}[{:?}]", name, arg_type),
+                    Type::Func(vec![arg_type.clone()], Box::new(iter_type.clone()))
+                );
+            }
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let iter_type = Type::Iter(Box::new(typ.clone()));
+        for arg_type in [Type::Arr(Box::new(typ.clone())), iter_type.clone()] {
+            map.insert(
+                format!("dedup[{:?}]", arg_type),
+                Type::Func(vec![arg_type], Box::new(iter_type.clone()))
+            );
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ.clone()));
+        for arg_type in [arr_type.clone(), Type::Iter(Box::new(typ.clone()))] {
+            for name in ["sort", "sort_desc"] {
+                map.insert(
+                    format!("{}[{:?}]", name, arg_type),
+                    Type::Func(vec![arg_type.clone()], Box::new(arr_type.clone()))
+                );
+            }
+        }
+    }
+
+    map.insert(
+        "format[Str, Arr(Str)]".to_string(),
+        Type::Func(vec![Type::Str, Type::Arr(Box::new(Type::Str))], Box::new(Type::Str))
+    );
+    map.insert(
+        "find[Str, Str]".to_string(),
+        Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Maybe(Box::new(Type::Int))))
+    );
+    map.insert("eq_ignore_case[Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Bool)));
+    map.insert("contains_ignore_case[Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Bool)));
+    map.insert("count_str[Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Int)));
+    map.insert("char_code[Str]".to_string(), Type::Func(vec![Type::Str], Box::new(Type::Int)));
+    map.insert("from_char_code[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Maybe(Box::new(Type::Str)))));
+    map.insert("split_once[Str, Str]".to_string(), Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Maybe(Box::new(split_type())))));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ.clone()));
+        for name in ["first", "last"] {
+            map.insert(
+                format!("{}[{:?}]", name, arr_type),
+                Type::Func(vec![arr_type.clone()], Box::new(Type::Maybe(Box::new(typ.clone()))))
+            );
+        }
+    }
+    for name in ["first", "last"] {
+        map.insert(format!("{}[Str]", name), Type::Func(vec![Type::Str], Box::new(Type::Maybe(Box::new(Type::Str)))));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let iter_type = Type::Iter(Box::new(typ.clone()));
+        for arg_type in [Type::Arr(Box::new(typ.clone())), iter_type.clone()] {
+            map.insert(
+                format!("intersperse[{:?}, {:?}]", arg_type, typ),
+                Type::Func(vec![arg_type, typ.clone()], Box::new(iter_type.clone()))
+            );
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let run_iter_type = Type::Iter(Box::new(run_length_type(typ.clone())));
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(
+                format!("run_length[{:?}]", arg_type),
+                Type::Func(vec![arg_type], Box::new(run_iter_type.clone()))
+            );
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ));
+        map.insert(
+            format!("chunk[{:?}, Int]", arr_type),
+            Type::Func(vec![arr_type.clone(), Type::Int], Box::new(Type::Arr(Box::new(arr_type))))
+        );
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let iter_type = Type::Iter(Box::new(typ.clone()));
+        for arg_type in [Type::Arr(Box::new(typ)), iter_type.clone()] {
+            for name in ["take", "drop"] {
+                map.insert(
+                    format!("{}[{:?}, Int]", name, arg_type),
+                    Type::Func(vec![arg_type.clone(), Type::Int], Box::new(iter_type.clone()))
+                );
+            }
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let window_iter_type = Type::Iter(Box::new(Type::Arr(Box::new(typ.clone()))));
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(
+                format!("windows[{:?}, Int]", arg_type),
+                Type::Func(vec![arg_type, Type::Int], Box::new(window_iter_type.clone()))
+            );
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(
+                format!("first_or[{:?}, {:?}]", arg_type, typ),
+                Type::Func(vec![arg_type, typ.clone()], Box::new(typ.clone()))
+            );
+        }
+    }
 
     map.insert("sum[Iter(Int)]".to_string(), Type::Func(vec![Type::Iter(Box::new(Type::Int))], Box::new(Type::Int)));
     map.insert("prod[Iter(Int)]".to_string(), Type::Func(vec![Type::Iter(Box::new(Type::Int))], Box::new(Type::Int)));
@@ -238,12 +1705,159 @@ pub fn heap_builtins() -> FxHashMap<String, HeapValue> {
     map.insert("print[Str]".to_string(), HeapValue::NativeFunction(&PRINT));
     map.insert("float[Int]".to_string(), HeapValue::NativeFunction(&ITOF));
     map.insert("int[Float]".to_string(), HeapValue::NativeFunction(&FTOI));
+    map.insert("int[Bool]".to_string(), HeapValue::NativeFunction(&BTOI));
+    map.insert("bool[Int]".to_string(), HeapValue::NativeFunction(&ITOB));
 
     map.insert("mod[Int, Int]".to_string(), HeapValue::NativeFunction(&MOD));
+    map.insert("popcount[Int]".to_string(), HeapValue::NativeFunction(&POPCOUNT));
+    map.insert("shl[Int, Int]".to_string(), HeapValue::NativeFunction(&SHL));
+    map.insert("shr[Int, Int]".to_string(), HeapValue::NativeFunction(&SHR));
+    map.insert("band[Int, Int]".to_string(), HeapValue::NativeFunction(&BAND));
+    map.insert("bor[Int, Int]".to_string(), HeapValue::NativeFunction(&BOR));
+    map.insert("bxor[Int, Int]".to_string(), HeapValue::NativeFunction(&BXOR));
     map.insert("pow[Int, Int]".to_string(), HeapValue::NativeFunction(&POWI));
     map.insert("pow[Float, Float]".to_string(), HeapValue::NativeFunction(&POWF));
+    map.insert("pow[Float, Int]".to_string(), HeapValue::NativeFunction(&POWFI));
 
     map.insert("sqrt[Float]".to_string(), HeapValue::NativeFunction(&SQRT));
+    map.insert("hypot[Float, Float]".to_string(), HeapValue::NativeFunction(&HYPOT));
+    map.insert("atan2[Float, Float]".to_string(), HeapValue::NativeFunction(&ATAN2));
+    map.insert("clamp01[Float]".to_string(), HeapValue::NativeFunction(&CLAMP01));
+    map.insert("lerp[Float, Float, Float]".to_string(), HeapValue::NativeFunction(&LERP));
+    map.insert("abs_diff[Int, Int]".to_string(), HeapValue::NativeFunction(&ABS_DIFF_INT));
+    map.insert("abs_diff[Float, Float]".to_string(), HeapValue::NativeFunction(&ABS_DIFF_FLOAT));
+    map.insert("is_finite[Float]".to_string(), HeapValue::NativeFunction(&IS_FINITE));
+    map.insert("is_inf[Float]".to_string(), HeapValue::NativeFunction(&IS_INF));
+
+    map.insert("rand_int[Int, Int]".to_string(), HeapValue::NativeFunction(&RAND_INT));
+    map.insert("rand_float".to_string(), HeapValue::NativeFunction(&RAND_FLOAT));
+
+    map.insert("chars[Str]".to_string(), HeapValue::NativeFunction(&CHARS));
+    map.insert("bytes[Str]".to_string(), HeapValue::NativeFunction(&BYTES));
+    map.insert("lines[Str]".to_string(), HeapValue::NativeFunction(&LINES));
+    map.insert("words[Str]".to_string(), HeapValue::NativeFunction(&WORDS));
+    map.insert("parse_ints[Str, Str]".to_string(), HeapValue::NativeFunction(&PARSE_INTS));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        map.insert(format!("sub[{:?}, Int, Int]", Type::Arr(Box::new(typ))), HeapValue::NativeFunction(&SUB_ARR));
+    }
+    map.insert("sub[Str, Int, Int]".to_string(), HeapValue::NativeFunction(&SUB_STR));
+    map.insert("byte_len[Str]".to_string(), HeapValue::NativeFunction(&BYTE_LEN));
+    map.insert("repeat_str[Str, Int]".to_string(), HeapValue::NativeFunction(&REPEAT_STR));
+    map.insert("replace_first[Str, Str, Str]".to_string(), HeapValue::NativeFunction(&REPLACE_FIRST));
+    map.insert("replace_n[Str, Str, Str, Int]".to_string(), HeapValue::NativeFunction(&REPLACE_N));
+    map.insert("center[Str, Int, Str]".to_string(), HeapValue::NativeFunction(&CENTER));
+    map.insert("center_trunc[Str, Int, Str]".to_string(), HeapValue::NativeFunction(&CENTER_TRUNC));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        map.insert(format!("reverse[{:?}]", Type::Arr(Box::new(typ))), HeapValue::NativeFunction(&REVERSE));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        map.insert(format!("lazy[{:?}]", Type::Arr(Box::new(typ))), HeapValue::NativeFunction(&LAZY));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let matrix_type = Type::Arr(Box::new(Type::Arr(Box::new(typ))));
+        map.insert(format!("transpose[{:?}]", matrix_type), HeapValue::NativeFunction(&TRANSPOSE));
+    }
+
+    for typ in [Type::Int, Type::Float] {
+        let (sum, max, product): (&'static NativeFunction, &'static NativeFunction, &'static NativeFunction) = match typ {
+            Type::Int => (&RUNNING_SUM_INT, &RUNNING_MAX_INT, &CUMULATIVE_PRODUCT_INT),
+            _ => (&RUNNING_SUM_FLOAT, &RUNNING_MAX_FLOAT, &CUMULATIVE_PRODUCT_FLOAT),
+        };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("running_sum[{:?}]", arg_type), HeapValue::NativeFunction(sum));
+            map.insert(format!("running_max[{:?}]", arg_type), HeapValue::NativeFunction(max));
+            map.insert(format!("cumulative_product[{:?}]", arg_type), HeapValue::NativeFunction(product));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let func: &'static NativeFunction = match typ {
+            Type::Int => &DEDUP_INT,
+            Type::Float => &DEDUP_FLOAT,
+            Type::Bool => &DEDUP_BOOL,
+            _ => &DEDUP_STR,
+        };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("dedup[{:?}]", arg_type), HeapValue::NativeFunction(func));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let (sort, sort_desc): (&'static NativeFunction, &'static NativeFunction) = match typ {
+            Type::Int => (&SORT_INT, &SORT_DESC_INT),
+            Type::Float => (&SORT_FLOAT, &SORT_DESC_FLOAT),
+            Type::Bool => (&SORT_BOOL, &SORT_DESC_BOOL),
+            _ => (&SORT_STR, &SORT_DESC_STR),
+        };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("sort[{:?}]", arg_type), HeapValue::NativeFunction(sort));
+            map.insert(format!("sort_desc[{:?}]", arg_type), HeapValue::NativeFunction(sort_desc));
+        }
+    }
+
+    map.insert("format[Str, Arr(Str)]".to_string(), HeapValue::NativeFunction(&FORMAT));
+    map.insert("find[Str, Str]".to_string(), HeapValue::NativeFunction(&FIND_STR));
+    map.insert("eq_ignore_case[Str, Str]".to_string(), HeapValue::NativeFunction(&EQ_IGNORE_CASE));
+    map.insert("contains_ignore_case[Str, Str]".to_string(), HeapValue::NativeFunction(&CONTAINS_IGNORE_CASE));
+    map.insert("count_str[Str, Str]".to_string(), HeapValue::NativeFunction(&COUNT_STR));
+    map.insert("char_code[Str]".to_string(), HeapValue::NativeFunction(&CHAR_CODE));
+    map.insert("from_char_code[Int]".to_string(), HeapValue::NativeFunction(&FROM_CHAR_CODE));
+    map.insert("split_once[Str, Str]".to_string(), HeapValue::NativeFunction(&SPLIT_ONCE));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let arr_type = Type::Arr(Box::new(typ));
+        map.insert(format!("first[{:?}]", arr_type), HeapValue::NativeFunction(&FIRST));
+        map.insert(format!("last[{:?}]", arr_type), HeapValue::NativeFunction(&LAST));
+    }
+    map.insert("first[Str]".to_string(), HeapValue::NativeFunction(&FIRST));
+    map.insert("last[Str]".to_string(), HeapValue::NativeFunction(&LAST));
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let func: &'static NativeFunction = if typ.is_heap() { &INTERSPERSE_HEAP } else { &INTERSPERSE };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("intersperse[{:?}, {:?}]", arg_type, typ), HeapValue::NativeFunction(func));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let func: &'static NativeFunction = match typ {
+            Type::Int => &RUN_LENGTH_INT,
+            Type::Float => &RUN_LENGTH_FLOAT,
+            Type::Bool => &RUN_LENGTH_BOOL,
+            _ => &RUN_LENGTH_STR,
+        };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("run_length[{:?}]", arg_type), HeapValue::NativeFunction(func));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        map.insert(format!("chunk[{:?}, Int]", Type::Arr(Box::new(typ))), HeapValue::NativeFunction(&CHUNK));
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ))] {
+            map.insert(format!("take[{:?}, Int]", arg_type), HeapValue::NativeFunction(&TAKE));
+            map.insert(format!("drop[{:?}, Int]", arg_type), HeapValue::NativeFunction(&DROP));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ))] {
+            map.insert(format!("windows[{:?}, Int]", arg_type), HeapValue::NativeFunction(&WINDOWS));
+        }
+    }
+
+    for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+        let func: &'static NativeFunction = if typ.is_heap() { &FIRST_OR_HEAP } else { &FIRST_OR };
+        for arg_type in [Type::Arr(Box::new(typ.clone())), Type::Iter(Box::new(typ.clone()))] {
+            map.insert(format!("first_or[{:?}, {:?}]", arg_type, typ), HeapValue::NativeFunction(func));
+        }
+    }
 
     map.insert("sum[Iter(Int)]".to_string(), HeapValue::NativeFunction(&SUMI));
     map.insert("prod[Iter(Int)]".to_string(), HeapValue::NativeFunction(&PRODI));
@@ -2,6 +2,7 @@ use std::rc::Rc;
 
 use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
 
+use crate::ast::Type;
 use crate::compiler;
 use crate::values::{Closure, HeapValue, Value};
 
@@ -36,12 +37,15 @@ pub enum OpCode {
 
     HeapEqual,
     HeapNotEqual,
+    FloatArrEqual,
+    FloatArrNotEqual,
     
     // Binary operations
     IntAdd,
     IntSubtract,
     IntMultiply,
     IntDivide,
+    IntMod,
 
     FloatAdd,
     FloatSubtract,
@@ -70,6 +74,8 @@ pub enum OpCode {
     Call,
     Array,
     ArrayHeap,
+    ArrayRepeat,
+    ArrayHeapRepeat,
 
     SetGlobal,
     SetHeapGlobal,
@@ -84,6 +90,10 @@ pub enum OpCode {
     GetUpvalue,
     GetHeapUpvalue,
 
+    SetField,
+    SetHeapField,
+    FieldNames,
+
     // Maybe ops
     WrapSome,
     WrapHeapSome,
@@ -94,9 +104,32 @@ pub enum OpCode {
     // Array/Iterator ops
     Map,
     Reduce,
+    ReduceWhile,
+    ReduceWhileHeap,
+    ReduceCount,
+    ReduceCountHeap,
+    Scan,
+    ScanHeap,
     Filter,
+    FlatMap,
+    FlatMapHeap,
+    CountWhere,
+    FindIndex,
+    Inspect,
     Len,
     ZipMap,
+    ZipLongest,
+    ZipPair,
+    IndexIter,
+    Cache,
+    MaxBy,
+    MinBy,
+    ChunkBy,
+    SumBy,
+    ToJson,
+    FromJson,
+    SortByKey,
+    SortBy,
 }
 
 impl From<u8> for OpCode {
@@ -105,12 +138,30 @@ impl From<u8> for OpCode {
     }
 }
 
+// the type of the keys compared by MaxBy, MinBy, ChunkBy, and SortByKey, or summed by SumBy
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum KeyType {
+    Int,
+    Float,
+    Str,
+    Bool,
+}
+
+impl From<u8> for KeyType {
+    fn from(value: u8) -> Self {
+        unsafe { std::mem::transmute(value) }
+    }
+}
+
 pub struct Chunk {
     bytes: Vec<u8>,
     // for storing 64-bit values
     constants: Vec<Value>,
     // for storing larger values (strings, arrays, etc.)
     heap_constants: Vec<HeapValue>,
+    // for storing types needed at runtime, for opcodes that must know how to interpret untagged values
+    types: Vec<Type>,
     newlines: Vec<usize>,
 }
 
@@ -120,6 +171,7 @@ impl Chunk {
             bytes: Vec::new(),
             constants: Vec::new(),
             heap_constants: Vec::new(),
+            types: Vec::new(),
             newlines: Vec::new(),
         }
     }
@@ -171,6 +223,16 @@ impl Chunk {
         self.bytes.write_u16::<BigEndian>(idx).map_err(|_| "Failed to write index of constant to bytes")
     }
 
+    pub fn write_typed(&mut self, opcode: OpCode, typ: Type, line: usize) -> Result<(), &'static str> {
+        self.types.push(typ);
+        let idx = self.types.len() - 1;
+        if idx > u16::MAX as usize {
+            return Err("Too many types in one chunk");
+        }
+        self.write_opcode(opcode, line);
+        self.bytes.write_u16::<BigEndian>(idx as u16).map_err(|_| "Failed to write index of type to bytes")
+    }
+
     pub fn write_closure(
         &mut self,
         closure: Closure,
@@ -267,6 +329,20 @@ impl Chunk {
         self.bytes.write_u16::<BigEndian>(num_elems).map_err(|_| "Failed to write number of elements to bytes")
     }
 
+    pub fn write_array_repeat(&mut self, num_elems: u16, line: usize) -> Result<(), &'static str> {
+        self.write_opcode(OpCode::ArrayRepeat, line);
+        self.bytes.write_u16::<BigEndian>(num_elems).map_err(|_| "Failed to write number of elements to bytes")
+    }
+    pub fn write_array_heap_repeat(&mut self, num_elems: u16, line: usize) -> Result<(), &'static str> {
+        self.write_opcode(OpCode::ArrayHeapRepeat, line);
+        self.bytes.write_u16::<BigEndian>(num_elems).map_err(|_| "Failed to write number of elements to bytes")
+    }
+
+    pub fn write_by_key(&mut self, opcode: OpCode, key_type: KeyType, line: usize) {
+        self.write_opcode(opcode, line);
+        self.bytes.write_u8(key_type as u8).unwrap();
+    }
+
     pub fn read_u8(&self, ip: &mut usize) -> u8 {
         let out = self.bytes[*ip];
         *ip += 1;
@@ -285,6 +361,10 @@ impl Chunk {
         let index = self.read_u16(ip);
         &self.heap_constants[index as usize]
     }
+    pub fn read_type(&self, ip: &mut usize) -> &Type {
+        let index = self.read_u16(ip);
+        &self.types[index as usize]
+    }
 
     // figures out line number for a given byte index
     pub fn line_num(&self, index: usize) -> usize {
@@ -298,46 +378,83 @@ impl Chunk {
         line_count
     }
 
-    #[cfg(feature = "debug")]
-    pub fn disassemble_instruction(&self, ip: &mut usize) {
+    // a readable description of the instruction at ip, advancing ip past it
+    pub fn describe_instruction(&self, ip: &mut usize) -> String {
         let ip0 = *ip;
         let opcode = OpCode::from(self.read_u8(ip));
         match opcode {
             OpCode::Constant => {
                 let constant = self.read_constant(ip);
-                println!("{:04} Constant {:?}", ip0, constant);
+                format!("{:04} Constant {:?}", ip0, constant)
             },
             OpCode::HeapConstant => {
                 let constant = self.read_heap_constant(ip);
-                println!("{:04} HeapConstant {:?}", ip0, constant);
+                format!("{:04} HeapConstant {:?}", ip0, constant)
             },
 
             OpCode::EndBlock => {
                 let n_pops = self.read_u16(ip);
                 let n_heap_pops = self.read_u16(ip);
-                println!("{:04} EndBlock {:?} {:?}", ip0, n_pops, n_heap_pops);
+                format!("{:04} EndBlock {:?} {:?}", ip0, n_pops, n_heap_pops)
             },
             OpCode::EndHeapBlock => {
                 let n_pops = self.read_u16(ip);
                 let n_heap_pops = self.read_u16(ip);
-                println!("{:04} EndHeapBlock {:?} {:?}", ip0, n_pops, n_heap_pops);
+                format!("{:04} EndHeapBlock {:?} {:?}", ip0, n_pops, n_heap_pops)
             },
             OpCode::Jump => {
                 let offset = self.read_u16(ip);
-                println!("{:04} Jump {:?}", ip0, offset);
+                format!("{:04} Jump {:?}", ip0, offset)
             },
             OpCode::JumpIfFalse => {
                 let offset = self.read_u16(ip);
-                println!("{:04} JumpIfFalse {:?}", ip0, offset);
+                format!("{:04} JumpIfFalse {:?}", ip0, offset)
             },
 
             OpCode::Array => {
                 let num_elems = self.read_u16(ip);
-                println!("{:04} Array {}", ip0, num_elems);
+                format!("{:04} Array {}", ip0, num_elems)
             },
             OpCode::ArrayHeap => {
                 let num_elems = self.read_u16(ip);
-                println!("{:04} ArrayHeap {}", ip0, num_elems);
+                format!("{:04} ArrayHeap {}", ip0, num_elems)
+            },
+            OpCode::ArrayRepeat => {
+                let num_elems = self.read_u16(ip);
+                format!("{:04} ArrayRepeat {}", ip0, num_elems)
+            },
+            OpCode::ArrayHeapRepeat => {
+                let num_elems = self.read_u16(ip);
+                format!("{:04} ArrayHeapRepeat {}", ip0, num_elems)
+            },
+
+            OpCode::MaxBy => {
+                let key_type = KeyType::from(self.read_u8(ip));
+                format!("{:04} MaxBy {:?}", ip0, key_type)
+            },
+            OpCode::MinBy => {
+                let key_type = KeyType::from(self.read_u8(ip));
+                format!("{:04} MinBy {:?}", ip0, key_type)
+            },
+            OpCode::ChunkBy => {
+                let key_type = KeyType::from(self.read_u8(ip));
+                format!("{:04} ChunkBy {:?}", ip0, key_type)
+            },
+            OpCode::SumBy => {
+                let key_type = KeyType::from(self.read_u8(ip));
+                format!("{:04} SumBy {:?}", ip0, key_type)
+            },
+            OpCode::ToJson => {
+                let typ = self.read_type(ip);
+                format!("{:04} ToJson {:?}", ip0, typ)
+            },
+            OpCode::FromJson => {
+                let typ = self.read_type(ip);
+                format!("{:04} FromJson {:?}", ip0, typ)
+            },
+            OpCode::SortByKey => {
+                let key_type = KeyType::from(self.read_u8(ip));
+                format!("{:04} SortByKey {:?}", ip0, key_type)
             },
 
             OpCode::SetGlobal => {
@@ -345,43 +462,50 @@ impl Chunk {
                     HeapValue::String(s) => s.clone(),
                     _ => unreachable!(),
                 };
-                println!("{:04} SetGlobal {}", ip0, name);
+                format!("{:04} SetGlobal {}", ip0, name)
             },
             OpCode::SetHeapGlobal => {
                 let name = match self.read_heap_constant(ip) {
                     HeapValue::String(s) => s.clone(),
                     _ => unreachable!(),
                 };
-                println!("{:04} SetHeapGlobal {}", ip0, name);
+                format!("{:04} SetHeapGlobal {}", ip0, name)
             },
             OpCode::GetGlobal => {
                 let name = match self.read_heap_constant(ip) {
                     HeapValue::String(s) => s.clone(),
                     _ => unreachable!(),
                 };
-                println!("{:04} GetGlobal {}", ip0, name);
+                format!("{:04} GetGlobal {}", ip0, name)
             },
             OpCode::GetHeapGlobal => {
                 let name = match self.read_heap_constant(ip) {
                     HeapValue::String(s) => s.clone(),
                     _ => unreachable!(),
                 };
-                println!("{:04} GetHeapGlobal {}", ip0, name);
+                format!("{:04} GetHeapGlobal {}", ip0, name)
             },
             OpCode::SetLocal => {
-                let idx = self.read_u16(ip);
-                println!("{:04} SetLocal {}", ip0, idx);
+                format!("{:04} SetLocal", ip0)
             },
             OpCode::SetHeapLocal => {
-                println!("{:04} SetHeapLocal", ip0);
+                format!("{:04} SetHeapLocal", ip0)
             },
             OpCode::GetLocal => {
                 let idx = self.read_u16(ip);
-                println!("{:04} GetLocal {}", ip0, idx);
+                format!("{:04} GetLocal {}", ip0, idx)
             },
             OpCode::GetHeapLocal => {
                 let idx = self.read_u16(ip);
-                println!("{:04} GetHeapLocal {}", ip0, idx);
+                format!("{:04} GetHeapLocal {}", ip0, idx)
+            },
+            OpCode::GetUpvalue => {
+                let idx = self.read_u16(ip);
+                format!("{:04} GetUpvalue {}", ip0, idx)
+            },
+            OpCode::GetHeapUpvalue => {
+                let idx = self.read_u16(ip);
+                format!("{:04} GetHeapUpvalue {}", ip0, idx)
             },
             
             OpCode::Closure => {
@@ -391,16 +515,32 @@ impl Chunk {
                 };
                 let n_upvalues = closure.function.num_upvalues;
                 let n_heap_upvalues = closure.function.num_heap_upvalues;
-                println!("{:04} Closure {} {}", ip0, n_upvalues, n_heap_upvalues);
+                let mut text = format!("{:04} Closure {} {}", ip0, n_upvalues, n_heap_upvalues);
                 for _ in 0..(n_upvalues+n_heap_upvalues) {
                     let is_local = self.read_u8(ip) == 1;
                     let index = self.read_u16(ip);
-                    println!(" | local: {}, idx: {}", is_local, index);
+                    text += &format!("\n | local: {}, idx: {}", is_local, index);
                 }
+                text
             },
 
-            x => println!("{:04} {:?}", ip0, x),
+            x => format!("{:04} {:?}", ip0, x),
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn disassemble_instruction(&self, ip: &mut usize) {
+        println!("{}", self.describe_instruction(ip));
+    }
+
+    // the description of each instruction in the chunk, in order
+    pub fn instructions(&self) -> Vec<String> {
+        let mut ip = 0;
+        let mut instructions = Vec::new();
+        while ip < self.bytes.len() {
+            instructions.push(self.describe_instruction(&mut ip));
         }
+        instructions
     }
 
     #[cfg(feature = "debug")]
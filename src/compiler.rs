@@ -1,10 +1,13 @@
 use std::cell::RefCell;
 use rustc_hash::FxHashMap;
+use std::path::Path;
 use std::rc::Rc;
 
 use crate::{ast, parser};
-use crate::chunk::{Chunk, OpCode};
+use crate::chunk::{Chunk, KeyType, OpCode};
 use crate::scanner;
+use crate::token::Token;
+use crate::vm::ErrorKind;
 use crate::values::{Closure, Function, HeapValue, Value};
 
 #[derive(Debug)]
@@ -80,6 +83,8 @@ pub struct Compiler {
     locals: LocalData,
     upvalues: Vec<Upvalue>,
     heap_upvalues: Vec<Upvalue>,
+    // (name, expanded name) of each variable reference that was resolved to an overload, shared with child compilers
+    resolved_names: Rc<RefCell<Vec<(String, String)>>>,
     // I'm aware that storing pointers rather than references is not ideal, but this drastically simplifies the code, making it so there aren't lifetimes attached to everything
     // In practice, this should be fine: Compilers are only ever created and used by their parents.
     parent: *mut Compiler,
@@ -93,13 +98,22 @@ impl Compiler {
             locals: LocalData::default(),
             upvalues: Vec::new(),
             heap_upvalues: Vec::new(),
+            resolved_names: Rc::new(RefCell::new(Vec::new())),
             parent: std::ptr::null_mut(),
         }
     }
 
     pub fn new_from(parent: &mut Compiler) -> Self {
         let typecontext = parent.typecontext.clone();
-        Self { parent, ..Self::new(typecontext) }
+        let resolved_names = parent.resolved_names.clone();
+        Self { parent, resolved_names, ..Self::new(typecontext) }
+    }
+
+    // record that a reference to name was resolved to the overload stored as expanded_name
+    pub fn note_resolved_name(&mut self, name: &str, expanded_name: &str) {
+        #[cfg(feature = "debug")]
+        println!("resolved {} to {}", name, expanded_name);
+        self.resolved_names.borrow_mut().push((name.to_string(), expanded_name.to_string()));
     }
 
     fn chunk(&mut self) -> &mut Chunk {
@@ -109,37 +123,49 @@ impl Compiler {
     pub fn write_opcode(&mut self, opcode: OpCode) {
         self.chunk().write_opcode(opcode, 0);
     }
-    pub fn write_constant(&mut self, value: Value) -> Result<(), String> {
-        self.chunk().write_constant(value, 0).map_err(|e| e.to_string())
+    pub fn write_constant(&mut self, value: Value) -> Result<(), ast::CompileError> {
+        self.chunk().write_constant(value, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn write_heap_constant(&mut self, value: HeapValue) -> Result<(), String> {
-        self.chunk().write_heap_constant(value, 0).map_err(|e| e.to_string())
+    pub fn write_heap_constant(&mut self, value: HeapValue) -> Result<(), ast::CompileError> {
+        self.chunk().write_heap_constant(value, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn write_string(&mut self, s: String) -> Result<(), String> {
+    pub fn write_string(&mut self, s: String) -> Result<(), ast::CompileError> {
         let s = HeapValue::String(Rc::new(s));
-        self.chunk().write_heap_constant(s, 0).map_err(|e| e.to_string())
+        self.chunk().write_heap_constant(s, 0).map_err(|e| ast::other_error(e.to_string()))
+    }
+    pub fn write_array(&mut self, len: u16) -> Result<(), ast::CompileError> {
+        self.chunk().write_array(len, 0).map_err(|e| ast::other_error(e.to_string()))
+    }
+    pub fn write_array_heap(&mut self, len: u16) -> Result<(), ast::CompileError> {
+        self.chunk().write_array_array(len, 0).map_err(|e| ast::other_error(e.to_string()))
+    }
+    pub fn write_array_repeat(&mut self, len: u16) -> Result<(), ast::CompileError> {
+        self.chunk().write_array_repeat(len, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn write_array(&mut self, len: u16) -> Result<(), String> {
-        self.chunk().write_array(len, 0).map_err(|e| e.to_string())
+    pub fn write_array_heap_repeat(&mut self, len: u16) -> Result<(), ast::CompileError> {
+        self.chunk().write_array_heap_repeat(len, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn write_array_heap(&mut self, len: u16) -> Result<(), String> {
-        self.chunk().write_array_array(len, 0).map_err(|e| e.to_string())
+    pub fn write_by_key(&mut self, opcode: OpCode, key_type: KeyType) {
+        self.chunk().write_by_key(opcode, key_type, 0);
     }
-    pub fn write_jump(&mut self, opcode: OpCode) -> Result<usize, String> {
-        self.chunk().write_jump(opcode, 0).map_err(|e| e.to_string())
+    pub fn write_typed(&mut self, opcode: OpCode, typ: ast::Type) -> Result<(), ast::CompileError> {
+        self.chunk().write_typed(opcode, typ, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn patch_jump(&mut self, offset: usize) -> Result<(), String> {
-        self.chunk().patch_jump(offset).map_err(|e| e.to_string())
+    pub fn write_jump(&mut self, opcode: OpCode) -> Result<usize, ast::CompileError> {
+        self.chunk().write_jump(opcode, 0).map_err(|e| ast::other_error(e.to_string()))
     }
-    pub fn write_function(&mut self, inner_compiler: Compiler) -> Result<(), String> {
+    pub fn patch_jump(&mut self, offset: usize) -> Result<(), ast::CompileError> {
+        self.chunk().patch_jump(offset).map_err(|e| ast::other_error(e.to_string()))
+    }
+    pub fn write_function(&mut self, inner_compiler: Compiler) -> Result<(), ast::CompileError> {
         let closure = Closure::new(Rc::new(inner_compiler.function));
-        self.chunk().write_closure(closure, inner_compiler.upvalues, inner_compiler.heap_upvalues, 0).map_err(|e| e.to_string())
+        self.chunk().write_closure(closure, inner_compiler.upvalues, inner_compiler.heap_upvalues, 0).map_err(|e| ast::other_error(e.to_string()))
     }
 
     pub fn begin_scope(&mut self) {
         self.locals.scope_depth += 1;
     }
-    pub fn end_scope(&mut self, is_heap: bool) -> Result<(), String> {
+    pub fn end_scope(&mut self, is_heap: bool) -> Result<(), ast::CompileError> {
         self.locals.scope_depth -= 1;
         let mut n_pops = 0;
         let mut n_heap_pops = 0;
@@ -161,10 +187,10 @@ impl Compiler {
             }
             self.locals.heap_locals.pop();
         }
-        self.chunk().write_endblock(n_pops, n_heap_pops, is_heap, 0).map_err(|e| e.to_string())
+        self.chunk().write_endblock(n_pops, n_heap_pops, is_heap, 0).map_err(|e| ast::other_error(e.to_string()))
     }
 
-    pub fn create_variable(&mut self, name: String, typ: &ast::Type) -> Result<Option<u16>, String> {
+    pub fn create_variable(&mut self, name: String, typ: &ast::Type) -> Result<Option<u16>, ast::CompileError> {
         if self.locals.scope_depth == 0 {
             // create a global variable
             let name_hv = HeapValue::String(Rc::new(name.clone()));
@@ -173,7 +199,7 @@ impl Compiler {
                     self.typecontext.borrow_mut().insert(name, typ.clone());
                     Ok(Some(idx))
                 },
-                Err(e) => return Err(e.to_string()),
+                Err(e) => return Err(ast::other_error(e.to_string())),
             };
         }
         // create a local variable
@@ -181,14 +207,14 @@ impl Compiler {
             name,
             depth: self.locals.scope_depth,
         };
-        self.locals.push(local, typ.is_heap()).map_err(|e| e.to_string())?;
+        self.locals.push(local, typ.is_heap()).map_err(|e| ast::other_error(e.to_string()))?;
         Ok(None)
     }
-    pub fn set_variable(&mut self, idx: Option<u16>, is_heap: bool) -> Result<(), String> {
+    pub fn set_variable(&mut self, idx: Option<u16>, is_heap: bool) -> Result<(), ast::CompileError> {
         match idx {
             // set global
             Some(idx) => {
-                self.chunk().write_set_global(idx, is_heap, 0).map_err(|e| e.to_string())
+                self.chunk().write_set_global(idx, is_heap, 0).map_err(|e| ast::other_error(e.to_string()))
             },
             // set local
             None => {
@@ -199,7 +225,7 @@ impl Compiler {
         }
     }
 
-    fn add_heap_upvalue(&mut self, index: u16, is_local: bool) -> Result<u16, String> {
+    fn add_heap_upvalue(&mut self, index: u16, is_local: bool) -> Result<u16, ast::CompileError> {
         let uv = Upvalue { index, is_local };
         for (i, upvalue) in self.heap_upvalues.iter().enumerate() {
             if *upvalue == uv {
@@ -207,13 +233,13 @@ impl Compiler {
             }
         }
         if self.heap_upvalues.len() == u16::MAX as usize {
-            return Err("Too many heap upvalues in current function".to_string());
+            return Err(ast::other_error("Too many heap upvalues in current function".to_string()));
         }
         self.heap_upvalues.push(uv);
         self.function.num_heap_upvalues += 1;
         Ok((self.heap_upvalues.len() - 1) as u16)
     }
-    fn add_upvalue(&mut self, index: u16, is_local: bool, is_heap: bool) -> Result<u16, String> {
+    fn add_upvalue(&mut self, index: u16, is_local: bool, is_heap: bool) -> Result<u16, ast::CompileError> {
         if is_heap {
             return self.add_heap_upvalue(index, is_local);
         }
@@ -225,13 +251,13 @@ impl Compiler {
             }
         }
         if self.upvalues.len() == u16::MAX as usize {
-            return Err("Too many upvalues in current function".to_string());
+            return Err(ast::other_error("Too many upvalues in current function".to_string()));
         }
         self.upvalues.push(uv);
         self.function.num_upvalues += 1;
         Ok((self.upvalues.len() - 1) as u16)
     }
-    fn resolve_upvalue(&mut self, name: &String, is_heap: bool) -> Result<Option<u16>, String> {
+    fn resolve_upvalue(&mut self, name: &String, is_heap: bool) -> Result<Option<u16>, ast::CompileError> {
         if self.parent.is_null() {
             return Ok(None);
         }
@@ -251,7 +277,7 @@ impl Compiler {
         Ok(None)
     }
 
-    pub fn get_variable(&mut self, name: String, is_heap: bool) -> Result<(), String> {
+    pub fn get_variable(&mut self, name: String, is_heap: bool) -> Result<(), ast::CompileError> {
         let local_idx = self.locals.get_idx(&name, is_heap);
         let res = if let Some(idx) = local_idx {
             self.chunk().write_get_local(idx, is_heap, 0)
@@ -262,13 +288,12 @@ impl Compiler {
         else {
             self.chunk().write_get_global(name, is_heap, 0)
         };
-        res.map_err(|e| e.to_string())
+        res.map_err(|e| ast::other_error(e.to_string()))
     }
 }
 
-pub fn compile(source: &str, typecontext: TypeContext) -> Result<(Function, ast::Type), String> {
-    let tokens = scanner::scan(source);
-    let ast = parser::parse(tokens, typecontext.clone()).map_err(|_| "Compilation halted due to parsing error.")?;
+pub fn compile(tokens: Vec<Token>, path: Option<&Path>, typecontext: TypeContext) -> Result<(Function, ast::Type), (ErrorKind, String)> {
+    let ast = parser::parse(tokens, typecontext.clone(), path)?;
     #[cfg(feature = "debug")]
     println!("{:?}", ast);
     let mut compiler = Compiler::new(typecontext);
@@ -277,3 +302,45 @@ pub fn compile(source: &str, typecontext: TypeContext) -> Result<(Function, ast:
 
     Ok((compiler.function, return_type))
 }
+
+// compile a program without running it, and get the (name, expanded name) of each reference to an overloaded
+// or templated variable, in the order they were compiled
+pub fn resolved_names(source: &str, path: Option<&Path>, typecontext: TypeContext) -> Result<Vec<(String, String)>, (ErrorKind, String)> {
+    // work on a copy of the type context, since compiling adds global variables to it
+    let typecontext = Rc::new(RefCell::new(typecontext.borrow().clone()));
+    let ast = parser::parse(scanner::scan(source), typecontext.clone(), path)?;
+    let mut compiler = Compiler::new(typecontext);
+    ast.compile(&mut compiler)?;
+    let names = compiler.resolved_names.borrow().clone();
+    Ok(names)
+}
+
+// compile a program without running it, and get a description of each instruction in its top-level chunk
+pub fn instructions(source: &str, path: Option<&Path>, typecontext: TypeContext) -> Result<Vec<String>, (ErrorKind, String)> {
+    // work on a copy of the type context, since compiling adds global variables to it
+    let typecontext = Rc::new(RefCell::new(typecontext.borrow().clone()));
+    let (function, _) = compile(scanner::scan(source), path, typecontext)?;
+    Ok(function.chunk.instructions())
+}
+
+// name and type (or type error) of each top-level binding in a program
+pub type BindingTypes = Vec<(String, Result<ast::Type, String>)>;
+
+// parse a program and get the types of its top-level bindings, without running it
+pub fn check(source: &str, path: Option<&Path>, typecontext: TypeContext) -> Result<BindingTypes, (ErrorKind, String)> {
+    // work on a copy of the type context, since compiling adds global variables to it
+    let typecontext = Rc::new(RefCell::new(typecontext.borrow().clone()));
+    let tokens = scanner::scan(source);
+    let ast = parser::parse(tokens, typecontext.clone(), path)?;
+    let mut bindings = match ast.downcast_ref::<ast::ASTTopLevel>() {
+        Some(top_level) => top_level.binding_types(),
+        None => return Err((ErrorKind::Other, "Parser did not produce a top-level expression".to_string())),
+    };
+    // some type errors are only detected during compilation
+    if bindings.iter().all(|(_, t)| t.is_ok()) {
+        if let Err(e) = ast.compile(&mut Compiler::new(typecontext)) {
+            bindings.push(("<program>".to_string(), Err(e.into())));
+        }
+    }
+    Ok(bindings)
+}
@@ -0,0 +1,59 @@
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+// get the names starting with the identifier that ends at pos in line
+// returns the position where the identifier starts, along with the matching names
+pub fn complete_name(line: &str, pos: usize, names: &[String]) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(pos);
+    let prefix = &line[start..pos];
+    if prefix.is_empty() {
+        return (pos, vec![]);
+    }
+    let candidates = names.iter()
+        .filter(|name| name.starts_with(prefix))
+        .cloned()
+        .collect();
+    (start, candidates)
+}
+
+// rustyline helper that completes the names of builtins and globals in the REPL
+pub struct ReplHelper {
+    names: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn new(names: Vec<String>) -> Self {
+        Self { names }
+    }
+
+    pub fn set_names(&mut self, names: Vec<String>) {
+        self.names = names;
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_name(line, pos, &self.names))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
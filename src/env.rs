@@ -24,6 +24,16 @@ fn powf(x: f32, y: f32) -> f32 {
     x.powf(y)
 }
 
+#[cfg(feature = "wasmer")]
+fn hypot(x: f32, y: f32) -> f32 {
+    x.hypot(y)
+}
+
+#[cfg(feature = "wasmer")]
+fn atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
 #[cfg(feature = "wasmer")]
 pub fn get_wasmer_imports(store: &mut wasmer::Store) -> wasmer::Imports {
     wasmer::imports! {
@@ -33,6 +43,9 @@ pub fn get_wasmer_imports(store: &mut wasmer::Store) -> wasmer::Imports {
 
             "pow[Int, Int]" => wasmer::Function::new_typed(store, powi),
             "pow[Float, Float]" => wasmer::Function::new_typed(store, powf),
+
+            "hypot[Float, Float]" => wasmer::Function::new_typed(store, hypot),
+            "atan2[Float, Float]" => wasmer::Function::new_typed(store, atan2),
         }
     }
 }
@@ -64,6 +77,8 @@ impl Default for Env {
         global_vars.insert("print[Float]".to_string(), 1);
         global_vars.insert("pow[Int, Int]".to_string(), 2);
         global_vars.insert("pow[Float, Float]".to_string(), 3);
+        global_vars.insert("hypot[Float, Float]".to_string(), 4);
+        global_vars.insert("atan2[Float, Float]".to_string(), 5);
         let global_scope = Rc::new(RefCell::new(global_vars));
 
         let mut global_types = FxHashMap::default();
@@ -84,6 +99,14 @@ impl Default for Env {
             "pow[Float, Float]".to_string(),
             Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)),
         );
+        global_types.insert(
+            "hypot[Float, Float]".to_string(),
+            Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)),
+        );
+        global_types.insert(
+            "atan2[Float, Float]".to_string(),
+            Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)),
+        );
         // add type for callable builtins
         global_types.insert(
             "abs[Int]".to_string(),
@@ -93,6 +116,26 @@ impl Default for Env {
             "abs[Float]".to_string(),
             Type::Func(vec![Type::Float], Box::new(Type::Float)),
         );
+        global_types.insert(
+            "abs_diff[Int, Int]".to_string(),
+            Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)),
+        );
+        global_types.insert(
+            "abs_diff[Float, Float]".to_string(),
+            Type::Func(vec![Type::Float, Type::Float], Box::new(Type::Float)),
+        );
+        global_types.insert(
+            "is_finite[Float]".to_string(),
+            Type::Func(vec![Type::Float], Box::new(Type::Bool)),
+        );
+        global_types.insert(
+            "is_inf[Float]".to_string(),
+            Type::Func(vec![Type::Float], Box::new(Type::Bool)),
+        );
+        global_types.insert(
+            "byte_len[Str]".to_string(),
+            Type::Func(vec![Type::Str], Box::new(Type::Int)),
+        );
         global_types.insert(
             "int[Float]".to_string(),
             Type::Func(vec![Type::Float], Box::new(Type::Int)),
@@ -101,14 +144,58 @@ impl Default for Env {
             "float[Int]".to_string(),
             Type::Func(vec![Type::Int], Box::new(Type::Float)),
         );
+        global_types.insert(
+            "int[Bool]".to_string(),
+            Type::Func(vec![Type::Bool], Box::new(Type::Int)),
+        );
+        global_types.insert(
+            "bool[Int]".to_string(),
+            Type::Func(vec![Type::Int], Box::new(Type::Bool)),
+        );
         global_types.insert(
             "mod[Int, Int]".to_string(),
             Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)),
         );
+        global_types.insert(
+            "popcount[Int]".to_string(),
+            Type::Func(vec![Type::Int], Box::new(Type::Int)),
+        );
+        for name in ["shl", "shr", "band", "bor", "bxor"] {
+            global_types.insert(
+                format!("{}[Int, Int]", name),
+                Type::Func(vec![Type::Int, Type::Int], Box::new(Type::Int)),
+            );
+        }
+        global_types.insert(
+            "pow[Float, Int]".to_string(),
+            Type::Func(vec![Type::Float, Type::Int], Box::new(Type::Float)),
+        );
+        global_types.insert(
+            "reverse[Arr(Int)]".to_string(),
+            Type::Func(
+                vec![Type::Arr(Box::new(Type::Int))],
+                Box::new(Type::Arr(Box::new(Type::Int))),
+            ),
+        );
+        global_types.insert(
+            "reverse[Arr(Float)]".to_string(),
+            Type::Func(
+                vec![Type::Arr(Box::new(Type::Float))],
+                Box::new(Type::Arr(Box::new(Type::Float))),
+            ),
+        );
         global_types.insert(
             "sqrt[Float]".to_string(),
             Type::Func(vec![Type::Float], Box::new(Type::Float)),
         );
+        global_types.insert(
+            "clamp01[Float]".to_string(),
+            Type::Func(vec![Type::Float], Box::new(Type::Float)),
+        );
+        global_types.insert(
+            "lerp[Float, Float, Float]".to_string(),
+            Type::Func(vec![Type::Float, Type::Float, Type::Float], Box::new(Type::Float)),
+        );
         global_types.insert(
             "sum[Iter(Int)]".to_string(),
             Type::Func(vec![Type::Iter(Box::new(Type::Int))], Box::new(Type::Int)),
@@ -139,6 +226,34 @@ impl Default for Env {
             "any[Iter(Bool)]".to_string(),
             Type::Func(vec![Type::Iter(Box::new(Type::Bool))], Box::new(Type::Bool)),
         );
+        for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+            let arr_type = Type::Arr(Box::new(typ));
+            global_types.insert(
+                format!("chunk[{:?}, Int]", arr_type),
+                Type::Func(vec![arr_type.clone(), Type::Int], Box::new(Type::Arr(Box::new(arr_type)))),
+            );
+        }
+        for typ in [Type::Int, Type::Float, Type::Bool] {
+            let iter_type = Type::Iter(Box::new(typ.clone()));
+            let arr_type = Type::Arr(Box::new(typ));
+            for name in ["take", "drop"] {
+                global_types.insert(
+                    format!("{}[{:?}, Int]", name, iter_type),
+                    Type::Func(vec![iter_type.clone(), Type::Int], Box::new(iter_type.clone())),
+                );
+                // arrays are converted to iterators first, as with make_array_iter
+                global_types.insert(
+                    format!("{}[{:?}, Int]", name, arr_type),
+                    Type::Func(vec![arr_type.clone(), Type::Int], Box::new(iter_type.clone())),
+                );
+            }
+        }
+        for typ in [Type::Int, Type::Float, Type::Bool, Type::Str] {
+            global_types.insert(
+                format!("first_or[Iter({:?}), {:?}]", typ, typ),
+                Type::Func(vec![Type::Iter(Box::new(typ.clone())), typ.clone()], Box::new(typ)),
+            );
+        }
         let global_types = Rc::new(RefCell::new(global_types));
 
         Self::new(global_scope, global_types)
@@ -168,6 +283,16 @@ impl Env {
                 "pow[Float, Float]",
                 FuncTypeSignature::new(vec![Numtype::F32, Numtype::F32], Some(Numtype::F32)),
             ),
+            Import::new(
+                "env",
+                "hypot[Float, Float]",
+                FuncTypeSignature::new(vec![Numtype::F32, Numtype::F32], Some(Numtype::F32)),
+            ),
+            Import::new(
+                "env",
+                "atan2[Float, Float]",
+                FuncTypeSignature::new(vec![Numtype::F32, Numtype::F32], Some(Numtype::F32)),
+            ),
         ];
         Self {
             global_vars,
@@ -305,6 +430,32 @@ fn view_memory(memview: &wasmer::MemoryView, fatptr: i64, typ: Type) -> Result<S
     Ok(result)
 }
 
+// Converts a runtime error from wasmer into a readable message
+#[cfg(feature = "wasmer")]
+fn trap_to_error(e: wasmer::RuntimeError, store: &mut wasmer::Store, instance: &wasmer::Instance) -> String {
+    // alloc advances memptr before growing memory, so if memptr is past the end of memory, the grow failed
+    let memptr = instance.exports.get_global("memptr").map(|g| g.get(store));
+    let memory = instance.exports.get_memory("memory");
+    if let (Ok(wasmer::Value::I32(memptr)), Ok(memory)) = (memptr, memory) {
+        if memptr as u32 as u64 > memory.view(store).data_size() {
+            return "Out of memory: failed to grow memory for allocation".to_string();
+        }
+    }
+    // wasmer doesn't re-export its trap codes, so classify traps by their messages instead
+    let message = e.message();
+    match message.as_str() {
+        "out of bounds memory access" | "misaligned heap" => "Memory access out of bounds".to_string(),
+        "undefined element: out of bounds table access" => "Function table access out of bounds".to_string(),
+        "uninitialized element" | "indirect call type mismatch" => "Called an invalid function reference".to_string(),
+        "call stack exhausted" => "Stack overflow; this may be caused by infinite recursion".to_string(),
+        "integer divide by zero" => "Integer division by zero".to_string(),
+        "integer overflow" => "Integer overflow".to_string(),
+        "invalid conversion to integer" => "Could not convert Float to Int".to_string(),
+        "unreachable" => "Reached unreachable code".to_string(),
+        _ => message,
+    }
+}
+
 #[cfg(feature = "wasmer")]
 pub fn run_wasm(bytes: &[u8], typ: Type) -> Result<String, String> {
     let mut store = wasmer::Store::default();
@@ -317,7 +468,7 @@ pub fn run_wasm(bytes: &[u8], typ: Type) -> Result<String, String> {
         .exports
         .get_function("main")
         .map_err(|e| format!("{}", e))?;
-    let result = main.call(&mut store, &[]).map_err(|e| format!("{}", e))?;
+    let result = main.call(&mut store, &[]).map_err(|e| trap_to_error(e, &mut store, &instance))?;
 
     let result = match (&result[0], &typ) {
         (wasmer::Value::I32(i), Type::Int) => format!("{}", i),
@@ -376,6 +527,8 @@ const importObject = {
         "print[Float]": (x) => { console.log(x); return x; },
         "pow[Int, Int]": (x, y) => x ** y,
         "pow[Float, Float]": (x, y) => x ** y,
+        "hypot[Float, Float]": (x, y) => Math.hypot(x, y),
+        "atan2[Float, Float]": (y, x) => Math.atan2(y, x),
     }
 }
 
@@ -449,6 +602,9 @@ function unwrap_arr(result, memory, subtype) {
     else if (subtype.startsWith("Float")) {
         out = Array.from(new Float32Array(memory.buffer, ptr, size / 4)).map(format_float);
     }
+    else if (subtype.startsWith("Bool")) {
+        out = Array.from(new Int32Array(memory.buffer, ptr, size / 4)).map((x) => x !== 0);
+    }
     else if (subtype.startsWith("Func")) {
         const data_arr = new Uint32Array(memory.buffer, ptr, size / 4);
         out = Array.from(data_arr).map((x) => unwrap_complex_type(x, memory, subtype));
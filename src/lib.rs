@@ -1,7 +1,11 @@
+// builtins.rs defines its native functions in a single lazy_static block, which needs more than the default limit to expand
+#![recursion_limit = "256"]
+
 mod ast;
 mod builtins;
 mod chunk;
 mod compiler;
+pub mod completion;
 mod env;
 mod parser;
 mod scanner;
@@ -11,8 +15,11 @@ mod wasmizer;
 mod vm;
 
 pub use ast::Type;
-pub use vm::VM;
-pub use wasmizer::wasmize;
+pub use scanner::scan;
+pub use token::{Token, TokenType};
+pub use values::TaggedValue;
+pub use vm::{ErrorKind, InterpreterError, VM};
+pub use wasmizer::{wasmize, wasmize_file};
 pub use env::{Env, save_wasm};
 
 #[cfg(feature = "wasmer")]
@@ -1,6 +1,7 @@
-use rustyline::{error::ReadlineError, DefaultEditor};
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
 
 use henrylang::*;
+use henrylang::completion::ReplHelper;
 
 const HISTORY_FILE: &str = ".henrylang_history";
 const TITLE: &str = r#"
@@ -17,15 +18,13 @@ o888o o888o `Y8bod8P' o888o o888o d888b        .8'
 
 #[allow(unused_variables)]
 fn repl(wasm_run: bool) {
-    let mut rl = DefaultEditor::new().unwrap();
+    let mut rl = Editor::<ReplHelper, DefaultHistory>::new().unwrap();
     let _ = rl.load_history(HISTORY_FILE);
-    rl.bind_sequence(
-        rustyline::KeyEvent::new('\t', rustyline::Modifiers::NONE),
-        rustyline::Cmd::HistorySearchForward,
-    );
     println!("{}", TITLE);
     #[cfg(not(feature = "wasm_repl"))]
     let mut vm = VM::new();
+    // tab completes the names of builtins and globals
+    rl.set_helper(Some(ReplHelper::new(vm.global_names())));
     loop {
         let readline = rl.readline("\x1b[1mhenry>\x1b[0m ");
         match readline {
@@ -33,15 +32,21 @@ fn repl(wasm_run: bool) {
                 if line == "exit" {
                     break;
                 }
+                if line == ":ls" {
+                    for (name, typ) in vm.globals_list() {
+                        println!("{}: {:?}", name, typ);
+                    }
+                    continue;
+                }
                 rl.add_history_entry(&line).unwrap();
                 if wasm_run {
                     #[cfg(feature = "wasmer")]
                     match wasmize(&line, Env::default()) {
                         Ok((bytes, typ)) => match run_wasm(&bytes, typ) {
                             Ok(x) => println!("{}", x),
-                            Err(e) => println!("Runtime Error: {}", e),
+                            Err(e) => println!("Runtime error: {}", e),
                         },
-                        Err(e) => println!("Compile Error: {}", e),
+                        Err(e) => println!("Compile error: {}", e),
                     }
                     #[cfg(not(feature = "wasmer"))]
                     println!("Cannot run REPL in wasm mode unless compiled with `wasmer` feature ");
@@ -51,6 +56,10 @@ fn repl(wasm_run: bool) {
                         Ok(x) => println!("{}", x),
                         Err(e) => println!("{}", e),
                     }
+                    // pick up any globals defined by this line
+                    if let Some(helper) = rl.helper_mut() {
+                        helper.set_names(vm.global_names());
+                    }
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -70,26 +79,18 @@ fn repl(wasm_run: bool) {
 }
 
 fn run_file(path: &str, wasm_run: bool, wasm_save: bool) {
-    // read file to string
-    let contents = match std::fs::read_to_string(path) {
-        Ok(x) => x,
-        Err(_) => {
-            println!("Could not read file `{}`", path);
-            return;
-        }
-    };
     if !wasm_run && !wasm_save {
-        match VM::new().interpret(&contents) {
+        match VM::new().interpret_file(path) {
             Ok(x) => println!("{}", x),
             Err(e) => println!("{}", e),
         };
         return;
     }
 
-    let (bytes, result_type) = match wasmize(&contents, Env::default()) {
+    let (bytes, result_type) = match wasmize_file(path, Env::default()) {
         Ok((bytes, result_type)) => (bytes, result_type),
         Err(e) => {
-            println!("Compile Error: {}", e);
+            println!("Compile error: {}", e);
             return;
         }
     };
@@ -105,23 +106,50 @@ fn run_file(path: &str, wasm_run: bool, wasm_save: bool) {
         #[cfg(feature = "wasmer")]
         match run_wasm(&bytes, result_type) {
             Ok(x) => println!("{}", x),
-            Err(e) => println!("Runtime Error: {}", e),
+            Err(e) => println!("Runtime error: {}", e),
         };
         #[cfg(not(feature = "wasmer"))]
         println!("Cannot run wasm unless compiled with `wasmer` feature");
     }
 }
 
+fn check_file(path: &str) {
+    let bindings = match VM::new().check_file(path) {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut had_error = false;
+    for (name, typ) in bindings {
+        match typ {
+            Ok(typ) => println!("{}: {:?}", name, typ),
+            Err(e) => {
+                println!("{}: Type error: {}", name, e);
+                had_error = true;
+            }
+        }
+    }
+    if had_error {
+        std::process::exit(1);
+    }
+}
+
 fn main() {
     let args = std::env::args().filter(|x| !x.starts_with("-")).collect::<Vec<_>>();
     let flags = std::env::args().filter(|x| x.starts_with("-")).collect::<Vec<_>>();
 
     let wasm_run = flags.iter().any(|x| x == "--wasm");
     let wasm_save = flags.iter().any(|x| x == "--save");
+    let check = flags.iter().any(|x| x == "--check");
     
     if args.len() == 1 {
         repl(wasm_run);
     }
+    else if args.len() == 2 && check {
+        check_file(&args[1]);
+    }
     else if args.len() == 2 {
         run_file(&args[1], wasm_run, wasm_save);
     }
@@ -131,5 +159,6 @@ fn main() {
         #[cfg(feature = "wasmer")]
         println!("  --wasm   Compile to wasm and run it using the Wasmer runtime");
         println!("  --save   Compile script to wasm and save it to a .wasm file. Does not apply in REPL mode");
+        println!("  --check  Type-check script without running it, printing the type of each top-level binding");
     }
 }
@@ -1,10 +1,14 @@
-use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use lazy_static::lazy_static;
 
 use crate::ast::{self, Expression};
 use crate::compiler::TypeContext;
+use crate::scanner;
 use crate::token::{TokenType, Token};
+use crate::vm::ErrorKind;
 
 #[derive(PartialEq, PartialOrd, Copy, Clone)]
 enum Precedence {
@@ -78,6 +82,10 @@ lazy_static! {
             TokenType::Dot,
             ParseRule::new(None, Some(Parser::get_field), Precedence::Call),
         );
+        map.insert(
+            TokenType::With,
+            ParseRule::new(None, Some(Parser::with), Precedence::Call),
+        );
 
         // control flow
         map.insert(
@@ -142,6 +150,10 @@ lazy_static! {
             TokenType::Slash,
             ParseRule::new(None, Some(Parser::binary), Precedence::Factor),
         );
+        map.insert(
+            TokenType::Percent,
+            ParseRule::new(None, Some(Parser::binary), Precedence::Factor),
+        );
         
         map.insert(
             TokenType::And,
@@ -151,6 +163,10 @@ lazy_static! {
             TokenType::Or,
             ParseRule::new(None, Some(Parser::binary), Precedence::Or),
         );
+        map.insert(
+            TokenType::Xor,
+            ParseRule::new(None, Some(Parser::binary), Precedence::Or),
+        );
         
         map.insert(
             TokenType::To,
@@ -197,22 +213,107 @@ lazy_static! {
             ParseRule::new(Some(Parser::variable), None, Precedence::None),
         );
 
+        map.insert(
+            TokenType::Colon,
+            ParseRule::new(None, Some(Parser::type_ascription), Precedence::Assignment),
+        );
+
         map.insert(
             TokenType::Reduce,
             ParseRule::new(Some(Parser::reduce), None, Precedence::None),
         );
+        map.insert(
+            TokenType::ReduceWhile,
+            ParseRule::new(Some(Parser::reduce_while), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::ReduceCount,
+            ParseRule::new(Some(Parser::reduce_count), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::Scan,
+            ParseRule::new(Some(Parser::scan), None, Precedence::None),
+        );
         map.insert(
             TokenType::Filter,
             ParseRule::new(Some(Parser::filter), None, Precedence::None),
         );
+        map.insert(
+            TokenType::CountWhere,
+            ParseRule::new(Some(Parser::count_where), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::FindIndex,
+            ParseRule::new(Some(Parser::find_index), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::Inspect,
+            ParseRule::new(Some(Parser::inspect), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::ChunkBy,
+            ParseRule::new(Some(Parser::chunk_by), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::SumBy,
+            ParseRule::new(Some(Parser::sum_by), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::ToJson,
+            ParseRule::new(Some(Parser::json_string), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::FromJson,
+            ParseRule::new(Some(Parser::json_parse), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::FlatMap,
+            ParseRule::new(Some(Parser::flat_map), None, Precedence::None),
+        );
         map.insert(
             TokenType::Len,
             ParseRule::new(Some(Parser::len), None, Precedence::None),
         );
+        map.insert(
+            TokenType::Cache,
+            ParseRule::new(Some(Parser::cache), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::MaxBy,
+            ParseRule::new(Some(Parser::extremum_by), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::MinBy,
+            ParseRule::new(Some(Parser::extremum_by), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::SortBy,
+            ParseRule::new(Some(Parser::sort_by), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::SortByKey,
+            ParseRule::new(Some(Parser::sort_by), None, Precedence::None),
+        );
         map.insert(
             TokenType::ZipMap,
             ParseRule::new(Some(Parser::zipmap), None, Precedence::None),
         );
+        map.insert(
+            TokenType::ZipLongest,
+            ParseRule::new(Some(Parser::zip_longest), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::Zip,
+            ParseRule::new(Some(Parser::zip), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::ZipMapIndexed,
+            ParseRule::new(Some(Parser::zipmap_indexed), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::WithIndex,
+            ParseRule::new(Some(Parser::with_index), None, Precedence::None),
+        );
         map.insert(
             TokenType::Some,
             ParseRule::new(Some(Parser::some), None, Precedence::None),
@@ -225,6 +326,22 @@ lazy_static! {
             TokenType::Unwrap,
             ParseRule::new(Some(Parser::unwrap), None, Precedence::None),
         );
+        map.insert(
+            TokenType::Match,
+            ParseRule::new(Some(Parser::match_maybe), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::SetField,
+            ParseRule::new(Some(Parser::set_field), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::FieldNames,
+            ParseRule::new(Some(Parser::field_names), None, Precedence::None),
+        );
+        map.insert(
+            TokenType::Import,
+            ParseRule::new(Some(Parser::misplaced_import), None, Precedence::None),
+        );
 
         // define default rules
         for ttype in enum_iterator::all::<TokenType>() {
@@ -237,6 +354,14 @@ lazy_static! {
     };
 }
 
+#[derive(Default)]
+struct ImportState {
+    // files that are currently being imported, used to detect circular imports
+    in_progress: Vec<PathBuf>,
+    // files that have already been imported; importing them again is a no-op
+    finished: FxHashSet<PathBuf>,
+}
+
 struct Parser {
     tokens: Vec<Token>,
     current: usize,
@@ -246,11 +371,29 @@ struct Parser {
     panic_mode: bool,
 
     last_name: Option<String>,
+
+    block_depth: usize,
+    // path of the file being parsed, if any; imports are resolved relative to its directory
+    path: Option<PathBuf>,
+    // name to report in error messages when parsing an imported file
+    source_name: Option<String>,
+    imports: ImportState,
 }
 
 impl Parser {
     fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0, previous: 0, had_error: false, panic_mode: false, last_name: None }
+        Self {
+            tokens,
+            current: 0,
+            previous: 0,
+            had_error: false,
+            panic_mode: false,
+            last_name: None,
+            block_depth: 0,
+            path: None,
+            source_name: None,
+            imports: ImportState::default(),
+        }
     }
     
     fn previous_token(&self) -> &Token {
@@ -272,7 +415,10 @@ impl Parser {
         }
         self.panic_mode = true;
         let token = self.previous_token();
-        eprint!("Error on line {} ", token.line);
+        match &self.source_name {
+            Some(name) => eprint!("Error in {} on line {} ", name, token.line),
+            None => eprint!("Error on line {} ", token.line),
+        }
         if token.ttype == TokenType::EoF {
             eprint!("at end")
         }
@@ -470,6 +616,16 @@ impl Parser {
 
     }
 
+    fn type_ascription(&mut self, left: Box<dyn ast::Expression>) -> Box<dyn ast::Expression> {
+        match self.type_annotation() {
+            Ok(typ) => Box::new(ast::TypeAscription::new(left, typ)),
+            Err(e) => {
+                self.error(Some(e));
+                Box::new(ast::ErrorExpression{})
+            }
+        }
+    }
+
     fn grouping(&mut self) -> Box<dyn ast::Expression> {
         if self.consume_if_match(TokenType::RParen) {
             self.error(Some(
@@ -493,21 +649,61 @@ impl Parser {
         }
     }
 
-    fn block(&mut self) -> Box<dyn ast::Expression> {
-        // we've started a new context, so we can start reporting errors again
-        self.panic_mode = false;
+    // reads expressions until the end of the current block
+    // at the top level, this is also where imports get spliced in
+    fn block_expressions(&mut self) -> Option<Vec<Box<dyn ast::Expression>>> {
+        let top_level = self.block_depth == 1;
+        // non-function names defined at the top level, used to check for collisions with imports
+        let mut local_names = FxHashSet::default();
+        let mut imported_names = FxHashSet::default();
         let mut expressions = Vec::new();
         while !self.consume_if_match(TokenType::RBrace) && !self.is_eof() {
+            if top_level && self.consume_if_match(TokenType::Import) {
+                let imported = self.import();
+                for name in imported.iter().filter_map(|e| top_level_name(e.as_ref())) {
+                    if local_names.contains(name) || !imported_names.insert(name.to_string()) {
+                        self.error(Some(
+                            format!("Imported name {} is already defined at the top level.", name)
+                        ));
+                    }
+                }
+                expressions.extend(imported);
+                continue;
+            }
             match self.expression() {
-                Some(expr) => expressions.push(expr),
+                Some(expr) => {
+                    let name = if top_level { top_level_name(expr.as_ref()) } else { None };
+                    if let Some(name) = name {
+                        if imported_names.contains(name) {
+                            self.error(Some(
+                                format!("Cannot redefine {}, which was imported from another file.", name)
+                            ));
+                        }
+                        local_names.insert(name.to_string());
+                    }
+                    expressions.push(expr)
+                },
                 None => {
                     self.error(Some(
                         format!("Expected expression in block but found {} instead.", self.current_token().text)
                     ));
-                    return Box::new(ast::ErrorExpression{});
+                    return None;
                 }
             }
         }
+        Some(expressions)
+    }
+
+    fn block(&mut self) -> Box<dyn ast::Expression> {
+        // we've started a new context, so we can start reporting errors again
+        self.panic_mode = false;
+        self.block_depth += 1;
+        let expressions = self.block_expressions();
+        self.block_depth -= 1;
+        let expressions = match expressions {
+            Some(expressions) => expressions,
+            None => return Box::new(ast::ErrorExpression{}),
+        };
         if expressions.is_empty() {
             self.consume(TokenType::Colon, format!(
                 "Type annotation is required after empty block."
@@ -539,7 +735,29 @@ impl Parser {
         };
         // read parameter list
         let mut params = Vec::new();
+        // fields bound by destructured object parameters, as (parameter name, field names)
+        let mut destructured = Vec::new();
         while !self.consume_if_match(TokenType::Pipe) && !self.is_eof() {
+            if self.consume_if_match(TokenType::LBrace) {
+                let fields = match self.destructured_fields() {
+                    Some(fields) => fields,
+                    None => return Box::new(ast::ErrorExpression{}),
+                };
+                // the object is bound to a hidden parameter, whose fields are unpacked at the start of the body
+                let name = format!("<param{}>", params.len());
+                destructured.push((name.clone(), fields));
+                self.consume(TokenType::Colon, "Missing type annotation for destructured parameter.".to_string());
+                let typ = match self.type_annotation() {
+                    Ok(type_annotation) => type_annotation,
+                    Err(e) => {
+                        self.error(Some(e));
+                        return Box::new(ast::ErrorExpression{});
+                    }
+                };
+                params.push(ast::NameAndType::new(name, typ));
+                self.consume_if_match(TokenType::Comma);
+                continue;
+            }
             let name = self.current_token();
             if name.ttype != TokenType::Ident {
                 self.error(Some(
@@ -552,6 +770,19 @@ impl Parser {
             self.consume(TokenType::Colon, format!(
                 "Missing type annotation for parameter {}.", name
             ));
+            // `_` discards its argument, so it is bound to a hidden parameter that the body can't refer to
+            let name = if name == "_" {
+                format!("<param{}>", params.len())
+            }
+            else if params.iter().any(|p: &ast::NameAndType| p.name == name) {
+                self.error(Some(
+                    format!("Duplicate parameter name {} in function definition.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+            else {
+                name
+            };
             let typ = match self.type_annotation() {
                 Ok(type_annotation) => type_annotation,
                 Err(e) => {
@@ -583,10 +814,58 @@ impl Parser {
             None
         };
         self.consume(TokenType::LBrace, "Expected '{' after function parameters.".to_string());
-        let body = self.block();
+        let mut body = self.block();
+        if !destructured.is_empty() {
+            let mut expressions: Vec<Box<dyn ast::Expression>> = Vec::new();
+            for (param, fields) in destructured {
+                for field in fields {
+                    let object = Box::new(ast::Variable::new(param.clone(), vec![]));
+                    expressions.push(Box::new(ast::Assignment::new(
+                        field.clone(), Box::new(ast::GetField::new(object, field))
+                    )));
+                }
+            }
+            expressions.push(body);
+            body = match ast::Block::new(expressions) {
+                Ok(block) => Box::new(block),
+                Err(e) => {
+                    self.error(Some(e));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+        }
         Box::new(ast::Function::new(name, params, return_type, body))
     }
 
+    // reads the field names in a destructured parameter like `{x, y}`, after the opening brace
+    fn destructured_fields(&mut self) -> Option<Vec<String>> {
+        let mut fields = Vec::new();
+        while !self.consume_if_match(TokenType::RBrace) && !self.is_eof() {
+            let field = self.current_token();
+            if field.ttype != TokenType::Ident {
+                self.error(Some(
+                    format!("In destructured parameter, expected field name but found {} instead.", field.text)
+                ));
+                return None;
+            }
+            let field = field.text.clone();
+            if fields.contains(&field) {
+                self.error(Some(
+                    format!("Field {} appears more than once in destructured parameter.", field)
+                ));
+                return None;
+            }
+            fields.push(field);
+            self.advance();
+            self.consume_if_match(TokenType::Comma);
+        }
+        if fields.is_empty() {
+            self.error(Some("Destructured parameter must name at least one field.".to_string()));
+            return None;
+        }
+        Some(fields)
+    }
+
     fn array(&mut self) -> Box<dyn ast::Expression> {
         let mut entries = Vec::new();
         while !self.consume_if_match(TokenType::RSquare) && !self.is_eof() {
@@ -600,11 +879,17 @@ impl Parser {
                 }
             };
             entries.push(expr);
+            if entries.len() == 1 && self.consume_if_match(TokenType::Semicolon) {
+                return self.array_repeat(entries.pop().unwrap());
+            }
             self.consume_if_match(TokenType::Comma);
         }
 
         if entries.is_empty() {
-            self.consume(TokenType::Colon, "Empty arrays must be annoted with a type".to_string());
+            if !self.consume_if_match(TokenType::Colon) {
+                // type must be provided by a type ascription, otherwise this will fail at type checking
+                return Box::new(ast::Array::new_untyped());
+            }
             let typ = match self.type_annotation() {
                 Ok(type_annotation) => type_annotation,
                 Err(e) => {
@@ -623,6 +908,20 @@ impl Parser {
         Box::new(ast::Array::new(entries))
     }
 
+    fn array_repeat(&mut self, value: Box<dyn ast::Expression>) -> Box<dyn ast::Expression> {
+        let count = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected array length after ';' but found {} instead.", self.current_token().text)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RSquare, "Expected ']' after array length.".to_string());
+        Box::new(ast::ArrayRepeat::new(value, count))
+    }
+
     fn type_def(&mut self) -> Box<dyn ast::Expression> {
         let name = match &self.last_name {
             Some(name) => name.clone(),
@@ -712,6 +1011,43 @@ impl Parser {
         Box::new(ast::GetField::new(obj, name))
     }
 
+    // `obj with {a: x, b: y}` is the same as `set_field(set_field(obj, "a", x), "b", y)`
+    fn with(&mut self, obj: Box<dyn ast::Expression>) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LBrace, "Expected '{' after 'with'.".to_string());
+        let mut obj = obj;
+        let mut n_fields = 0;
+        while !self.consume_if_match(TokenType::RBrace) && !self.is_eof() {
+            let name = self.current_token();
+            if name.ttype != TokenType::Ident {
+                self.error(Some(
+                    format!("In 'with' expression, expected field name but found {} instead.", name.text)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+            let name = name.text.clone();
+            self.advance();
+            self.consume(TokenType::Colon, format!("Expected ':' after field name {} in 'with' expression.", name));
+            let value = match self.expression() {
+                Some(expr) => expr,
+                None => {
+                    self.error(Some(
+                        format!("Expected a value for field {} in 'with' expression.", name)
+                    ));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            let field = Box::new(ast::Literal::new(ast::Type::Str, format!("\"{}\"", name)));
+            obj = Box::new(ast::SetField::new(obj, field, value));
+            n_fields += 1;
+            self.consume_if_match(TokenType::Comma);
+        }
+        if n_fields == 0 {
+            self.error(Some("Expected at least one field in 'with' expression.".to_string()));
+            return Box::new(ast::ErrorExpression{});
+        }
+        obj
+    }
+
     fn if_statement(&mut self) -> Box<dyn ast::Expression> {
         let condition = match self.expression() {
             Some(expr) => expr,
@@ -814,13 +1150,23 @@ impl Parser {
         Box::new(ast::Reduce::new(fn_expr, arr_expr, init_expr))
     }
 
-    fn filter(&mut self) -> Box<dyn ast::Expression> {
-        self.consume(TokenType::LParen, "Expected '(' after 'filter'.".to_string());
+    fn reduce_while(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'reduce_while'.".to_string());
         let fn_expr = match self.expression() {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected function as first argument in 'filter' expression.")
+                    "Expected function as first argument in 'reduce_while' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let init_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected initial value as second argument in 'reduce_while' expression.".to_string()
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
@@ -830,102 +1176,711 @@ impl Parser {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected array as second argument in 'filter' expression.")
+                    "Expected array as third argument in 'reduce_while' expression.".to_string()
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
         };
-        self.consume(TokenType::RParen, "Expected ')' after 'filter' arguments.".to_string());
-        Box::new(ast::Filter::new(fn_expr, arr_expr))
+        self.consume(TokenType::RParen, "Expected ')' after 'reduce_while' arguments.".to_string());
+        Box::new(ast::ReduceWhile::new(fn_expr, arr_expr, init_expr))
     }
 
-    fn len(&mut self) -> Box<dyn ast::Expression> {
-        self.consume(TokenType::LParen, "Expected '(' after 'len'.".to_string());
-        let expr = match self.expression() {
+    fn reduce_count(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'reduce_count'.".to_string());
+        let fn_expr = match self.expression() {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected expression as argument in 'len' expression.")
+                    "Expected function as first argument in 'reduce_count' expression.".to_string()
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
         };
-        self.consume(TokenType::RParen, "Expected ')' after 'len' argument.".to_string());
-        Box::new(ast::Len::new(expr))
+        self.consume_if_match(TokenType::Comma);
+        let init_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected initial value as second argument in 'reduce_count' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as third argument in 'reduce_count' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'reduce_count' arguments.".to_string());
+        Box::new(ast::ReduceCount::new(fn_expr, arr_expr, init_expr))
     }
 
-    fn zipmap(&mut self) -> Box<dyn ast::Expression> {
-        self.consume(TokenType::LParen, "Expected '(' after 'zipmap'.".to_string());
+    fn scan(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'scan'.".to_string());
         let fn_expr = match self.expression() {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected function as first argument in 'zipmap' expression.")
+                    "Expected function as first argument in 'scan' expression.".to_string()
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
         };
         self.consume_if_match(TokenType::Comma);
-        let mut exprs = Vec::new();
-        while !self.consume_if_match(TokenType::RParen) {
-            let expr = match self.expression() {
-                Some(expr) => expr,
-                None => {
-                    self.error(Some(
-                        format!("Expected expression as argument in 'zipmap' expression.")
-                    ));
-                    return Box::new(ast::ErrorExpression{});
-                }
-            };
-            exprs.push(expr);
-            self.consume_if_match(TokenType::Comma);
-        }
-        Box::new(ast::ZipMap::new(fn_expr, exprs))
+        let init_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected initial value as second argument in 'scan' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as third argument in 'scan' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'scan' arguments.".to_string());
+        Box::new(ast::Scan::new(fn_expr, arr_expr, init_expr))
     }
 
-    fn unwrap(&mut self) -> Box<dyn ast::Expression> {
-        self.consume(TokenType::LParen, "Expected '(' after 'unwrap'.".to_string());
-        let value = match self.expression() {
+    fn filter(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'filter'.".to_string());
+        let fn_expr = match self.expression() {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected expression as argument in 'unwrap' expression.")
+                    format!("Expected function as first argument in 'filter' expression.")
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
         };
         self.consume_if_match(TokenType::Comma);
-        let default = match self.expression() {
+        let arr_expr = match self.expression() {
             Some(expr) => expr,
             None => {
                 self.error(Some(
-                    format!("Expected default value as second argument in 'unwrap' expression.")
+                    format!("Expected array as second argument in 'filter' expression.")
                 ));
                 return Box::new(ast::ErrorExpression{});
             }
         };
-        self.consume(TokenType::RParen, "Expected ')' after 'unwrap' argument.".to_string());
-        Box::new(ast::Unwrap::new(value, default))
+        self.consume(TokenType::RParen, "Expected ')' after 'filter' arguments.".to_string());
+        Box::new(ast::Filter::new(fn_expr, arr_expr))
     }
 
-    fn parse(&mut self, typecontext: TypeContext) -> Box<dyn ast::Expression> {
-        let block = self.block();
-        let mut top_level = Box::new(ast::ASTTopLevel::new(typecontext, block));
-        match top_level.set_parent(None) {
-            Ok(()) => top_level,
-            Err(e) => {
-                self.error(Some(e));
-                Box::new(ast::ErrorExpression{})
+    fn count_where(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'count_where'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'count_where' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as second argument in 'count_where' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'count_where' arguments.".to_string());
+        Box::new(ast::CountWhere::new(fn_expr, arr_expr))
+    }
+
+    fn find_index(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'find_index'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'find_index' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as second argument in 'find_index' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'find_index' arguments.".to_string());
+        Box::new(ast::FindIndex::new(fn_expr, arr_expr))
+    }
+
+    fn inspect(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'inspect'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'inspect' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let iter_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected iterator as second argument in 'inspect' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'inspect' arguments.".to_string());
+        Box::new(ast::Inspect::new(fn_expr, iter_expr))
+    }
+
+    fn chunk_by(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'chunk_by'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'chunk_by' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as second argument in 'chunk_by' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'chunk_by' arguments.".to_string());
+        Box::new(ast::ChunkBy::new(fn_expr, arr_expr))
+    }
+
+    fn sum_by(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'sum_by'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'sum_by' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as second argument in 'sum_by' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'sum_by' arguments.".to_string());
+        Box::new(ast::SumBy::new(fn_expr, arr_expr))
+    }
+
+    fn flat_map(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'flat_map'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'flat_map' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array as second argument in 'flat_map' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'flat_map' arguments.".to_string());
+        Box::new(ast::FlatMap::new(fn_expr, arr_expr))
+    }
+
+    fn len(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'len'.".to_string());
+        let expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected expression as argument in 'len' expression.")
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'len' argument.".to_string());
+        Box::new(ast::Len::new(expr))
+    }
+
+    fn json_string(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'to_json'.".to_string());
+        let expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected expression as argument in 'to_json' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'to_json' argument.".to_string());
+        Box::new(ast::ToJson::new(expr))
+    }
+
+    fn json_parse(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'from_json'.".to_string());
+        let expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected expression as argument in 'from_json' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'from_json' argument.".to_string());
+        Box::new(ast::FromJson::new(expr))
+    }
+
+    fn cache(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'cache'.".to_string());
+        let expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected expression as argument in 'cache' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'cache' argument.".to_string());
+        Box::new(ast::Cache::new(expr))
+    }
+
+    fn set_field(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'set_field'.".to_string());
+        let mut args = Vec::new();
+        for position in ["first", "second", "third"] {
+            match self.expression() {
+                Some(expr) => args.push(expr),
+                None => {
+                    self.error(Some(
+                        format!("Expected expression as {} argument in 'set_field' expression.", position)
+                    ));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            self.consume_if_match(TokenType::Comma);
+        }
+        self.consume(TokenType::RParen, "Expected ')' after 'set_field' arguments.".to_string());
+        let value = args.pop().unwrap();
+        let field = args.pop().unwrap();
+        let object = args.pop().unwrap();
+        Box::new(ast::SetField::new(object, field, value))
+    }
+
+    fn field_names(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'field_names'.".to_string());
+        let object = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected expression as argument in 'field_names' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'field_names' argument.".to_string());
+        Box::new(ast::FieldNames::new(object))
+    }
+
+    fn zip_longest(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'zip_longest'.".to_string());
+        let mut args = Vec::new();
+        for position in ["first", "second", "third", "fourth", "fifth"] {
+            match self.expression() {
+                Some(expr) => args.push(expr),
+                None => {
+                    self.error(Some(
+                        format!("Expected expression as {} argument in 'zip_longest' expression.", position)
+                    ));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            self.consume_if_match(TokenType::Comma);
+        }
+        self.consume(TokenType::RParen, "Expected ')' after 'zip_longest' arguments.".to_string());
+        let mut args = args.into_iter();
+        let (a, b, default_a, default_b, function) = (
+            args.next().unwrap(), args.next().unwrap(), args.next().unwrap(), args.next().unwrap(), args.next().unwrap()
+        );
+        Box::new(ast::ZipLongest::new(a, b, default_a, default_b, function))
+    }
+
+    fn zip(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'zip'.".to_string());
+        let mut args = Vec::new();
+        for position in ["first", "second"] {
+            match self.expression() {
+                Some(expr) => args.push(expr),
+                None => {
+                    self.error(Some(
+                        format!("Expected expression as {} argument in 'zip' expression.", position)
+                    ));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            self.consume_if_match(TokenType::Comma);
+        }
+        self.consume(TokenType::RParen, "Expected ')' after 'zip' arguments.".to_string());
+        let b = args.pop().unwrap();
+        let a = args.pop().unwrap();
+        Box::new(ast::Zip::new(a, b))
+    }
+
+    fn extremum_by(&mut self) -> Box<dyn ast::Expression> {
+        let is_max = self.previous_token().ttype == TokenType::MaxBy;
+        let name = self.previous_token().text.clone();
+        self.consume(TokenType::LParen, format!("Expected '(' after '{}'.", name));
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected array as first argument in '{}' expression.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected function as second argument in '{}' expression.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, format!("Expected ')' after '{}' arguments.", name));
+        Box::new(ast::ExtremumBy::new(arr_expr, fn_expr, is_max))
+    }
+
+    // `sort_by(x, cmp)` or `sort_by_key(x, f)`
+    fn sort_by(&mut self) -> Box<dyn ast::Expression> {
+        let by_key = self.previous_token().ttype == TokenType::SortByKey;
+        let name = self.previous_token().text.clone();
+        self.consume(TokenType::LParen, format!("Expected '(' after '{}'.", name));
+        let arr_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected array as first argument in '{}' expression.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected function as second argument in '{}' expression.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, format!("Expected ')' after '{}' arguments.", name));
+        if by_key {
+            Box::new(ast::SortByKey::new(arr_expr, fn_expr))
+        }
+        else {
+            Box::new(ast::SortBy::new(arr_expr, fn_expr))
+        }
+    }
+
+    fn zipmap(&mut self) -> Box<dyn ast::Expression> {
+        self.zipmap_args("zipmap", false)
+    }
+
+    fn zipmap_indexed(&mut self) -> Box<dyn ast::Expression> {
+        self.zipmap_args("zipmap_indexed", true)
+    }
+
+    fn zipmap_args(&mut self, name: &str, with_index: bool) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, format!("Expected '(' after '{}'.", name));
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected function as first argument in '{}' expression.", name)
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let mut exprs = Vec::new();
+        while !self.consume_if_match(TokenType::RParen) {
+            let expr = match self.expression() {
+                Some(expr) => expr,
+                None => {
+                    self.error(Some(
+                        format!("Expected expression as argument in '{}' expression.", name)
+                    ));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            exprs.push(expr);
+            self.consume_if_match(TokenType::Comma);
+        }
+        if with_index {
+            Box::new(ast::ZipMap::with_index(fn_expr, exprs))
+        }
+        else {
+            Box::new(ast::ZipMap::new(fn_expr, exprs))
+        }
+    }
+
+    fn with_index(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'with_index'.".to_string());
+        let fn_expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected function as first argument in 'with_index' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
             }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let expr = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected array or iterator as second argument in 'with_index' expression.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'with_index' arguments.".to_string());
+        Box::new(ast::ZipMap::with_index(fn_expr, vec![expr]))
+    }
+
+    fn unwrap(&mut self) -> Box<dyn ast::Expression> {
+        self.consume(TokenType::LParen, "Expected '(' after 'unwrap'.".to_string());
+        let value = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected expression as argument in 'unwrap' expression.")
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume_if_match(TokenType::Comma);
+        let default = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    format!("Expected default value as second argument in 'unwrap' expression.")
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::RParen, "Expected ')' after 'unwrap' argument.".to_string());
+        Box::new(ast::Unwrap::new(value, default))
+    }
+
+    fn match_maybe(&mut self) -> Box<dyn ast::Expression> {
+        let value = match self.expression() {
+            Some(expr) => expr,
+            None => {
+                self.error(Some(
+                    "Expected expression to match on after 'match'.".to_string()
+                ));
+                return Box::new(ast::ErrorExpression{});
+            }
+        };
+        self.consume(TokenType::LBrace, "Expected '{' after 'match' value.".to_string());
+        let mut some_arm = None;
+        let mut null_arm = None;
+        while !self.consume_if_match(TokenType::RBrace) {
+            if self.is_eof() {
+                self.error(Some("Expected '}' after 'match' arms.".to_string()));
+                return Box::new(ast::ErrorExpression{});
+            }
+            let binding = if self.consume_if_match(TokenType::Some) {
+                self.consume(TokenType::LParen, "Expected '(' after 'some' in 'match' arm.".to_string());
+                self.consume(TokenType::Ident, "Expected variable name in 'some' arm.".to_string());
+                let name = self.previous_token().text.clone();
+                self.consume(TokenType::RParen, "Expected ')' after variable name in 'some' arm.".to_string());
+                Some(name)
+            }
+            else if self.consume_if_match(TokenType::Null) {
+                None
+            }
+            else {
+                self.error(Some(format!(
+                    "Expected 'some(...)' or 'null' in 'match' arm but found {} instead.", self.current_token().text
+                )));
+                return Box::new(ast::ErrorExpression{});
+            };
+            self.consume(TokenType::FatArrow, "Expected '=>' after 'match' pattern.".to_string());
+            let arm = match self.expression() {
+                Some(expr) => expr,
+                None => {
+                    self.error(Some("Expected expression after '=>' in 'match' arm.".to_string()));
+                    return Box::new(ast::ErrorExpression{});
+                }
+            };
+            let is_duplicate = match binding {
+                Some(name) => some_arm.replace((name, arm)).is_some(),
+                None => null_arm.replace(arm).is_some(),
+            };
+            if is_duplicate {
+                self.error(Some("Duplicate arm in 'match' expression.".to_string()));
+                return Box::new(ast::ErrorExpression{});
+            }
+            self.consume_if_match(TokenType::Comma);
+        }
+        match (some_arm, null_arm) {
+            (Some((binding, some_arm)), Some(null_arm)) => {
+                Box::new(ast::Match::new(value, binding, some_arm, null_arm))
+            },
+            _ => {
+                self.error(Some("'match' expression must have both a 'some' arm and a 'null' arm.".to_string()));
+                Box::new(ast::ErrorExpression{})
+            }
+        }
+    }
+
+    fn import(&mut self) -> Vec<Box<dyn ast::Expression>> {
+        if self.current_ttype() != TokenType::Str {
+            self.error(Some(
+                format!("Expected file path string after 'import' but found {} instead.", self.current_token().text)
+            ));
+            return Vec::new();
         }
+        self.advance();
+        let text = &self.previous_token().text;
+        let relpath = text[1..text.len()-1].to_string();
+        let base_dir = self.path.as_ref()
+            .and_then(|p| p.parent())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        let path = match base_dir.join(&relpath).canonicalize() {
+            Ok(path) => path,
+            Err(_) => {
+                self.error(Some(format!("Could not find file {} to import.", relpath)));
+                return Vec::new();
+            }
+        };
+        if self.imports.in_progress.contains(&path) {
+            self.error(Some(format!("Circular import of {}.", relpath)));
+            return Vec::new();
+        }
+        if self.imports.finished.contains(&path) {
+            return Vec::new();
+        }
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(_) => {
+                self.error(Some(format!("Could not read file {} to import.", relpath)));
+                return Vec::new();
+            }
+        };
+
+        let mut parser = Parser::new(scanner::scan(&source));
+        parser.source_name = Some(relpath.clone());
+        parser.block_depth = 1;
+        parser.imports = std::mem::take(&mut self.imports);
+        parser.imports.in_progress.push(path.clone());
+        parser.path = Some(path);
+        let expressions = parser.block_expressions();
+        self.imports = std::mem::take(&mut parser.imports);
+        if let Some(path) = self.imports.in_progress.pop() {
+            self.imports.finished.insert(path);
+        }
+
+        if parser.had_error {
+            self.error(Some(format!("Failed to import {}.", relpath)));
+            return Vec::new();
+        }
+        expressions.unwrap_or_default()
+    }
+
+    fn misplaced_import(&mut self) -> Box<dyn ast::Expression> {
+        self.error(Some(
+            "Imports are only allowed at the top level of a file.".to_string()
+        ));
+        Box::new(ast::ErrorExpression{})
+    }
+
+    fn parse(&mut self, typecontext: TypeContext) -> Result<Box<dyn ast::Expression>, (ErrorKind, String)> {
+        let block = self.block();
+        if self.had_error {
+            return Err((ErrorKind::Parse, "Compilation halted due to parsing error.".to_string()));
+        }
+        let mut top_level = Box::new(ast::ASTTopLevel::new(typecontext, block));
+        match top_level.set_parent(None) {
+            Ok(()) => Ok(top_level),
+            Err(e) => {
+                self.error(Some(e.1.clone()));
+                Err(e.into())
+            }
+        }
+    }
+}
+
+// name defined by a top-level assignment, unless it's a function (functions may be overloaded across files)
+fn top_level_name(expr: &dyn ast::Expression) -> Option<&str> {
+    let assignment = expr.downcast_ref::<ast::Assignment>()?;
+    if assignment.is_function_def() {
+        None
+    }
+    else {
+        Some(assignment.get_name())
     }
 }
 
-pub fn parse(tokens: Vec<Token>, typecontext: TypeContext) -> Result<Box<dyn ast::Expression>, ()> {
+pub fn parse(tokens: Vec<Token>, typecontext: TypeContext, path: Option<&Path>) -> Result<Box<dyn ast::Expression>, (ErrorKind, String)> {
     let mut parser = Parser::new(tokens);
-    let ast = parser.parse(typecontext);
-    if parser.had_error {
-        return Err(())
+    if let Some(path) = path {
+        let path = path.canonicalize().unwrap_or(path.to_path_buf());
+        parser.imports.in_progress.push(path.clone());
+        parser.path = Some(path);
     }
-    Ok(ast)
+    parser.parse(typecontext)
 }
\ No newline at end of file
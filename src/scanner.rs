@@ -107,14 +107,28 @@ impl Scanner {
         while self.peek(0).is_ascii_digit() {
             self.advance();
         }
+        let mut ttype = TokenType::Int;
         if self.peek(0) == '.' && self.peek(1).is_ascii_digit() {
             self.advance();
             while self.peek(0).is_ascii_digit() {
                 self.advance();
             }
-            return self.make_token(TokenType::Float);
+            ttype = TokenType::Float;
         }
-        self.make_token(TokenType::Int)
+        // an optional suffix, `i` or `f`, sets the type of the literal
+        let suffix = self.peek(0);
+        if (suffix == 'i' || suffix == 'f') && !(self.peek(1).is_alphanumeric() || self.peek(1) == '_') {
+            let mut token = self.make_token(ttype);
+            self.advance();
+            if suffix == 'f' {
+                token.ttype = TokenType::Float;
+            }
+            else if ttype == TokenType::Float {
+                return self.error_token("Int suffix `i` used on a number with a fractional part");
+            }
+            return token;
+        }
+        self.make_token(ttype)
     }
 
     fn read_ident_or_keyword(&mut self) -> Token {
@@ -156,6 +170,17 @@ impl Scanner {
                     }
                 );
             },
+            '=' => {
+                let is_fat_arrow = self.match_char('>');
+                return self.make_token(
+                    if is_fat_arrow {
+                        TokenType::FatArrow
+                    }
+                    else {
+                        TokenType::Eq
+                    }
+                );
+            },
             '>' => {
                 let is_geq = self.match_char('=');
                 return self.make_token(
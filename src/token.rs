@@ -15,7 +15,9 @@ pub enum TokenType {
     Comma,
     Dot,
     Colon,
+    Semicolon,
     RightArrow,
+    FatArrow,
     At,
 
     Eq,
@@ -29,6 +31,7 @@ pub enum TokenType {
     Minus,
     Slash,
     Star,
+    Percent,
 
     Assign,
     Bang,
@@ -40,6 +43,7 @@ pub enum TokenType {
 
     And,
     Or,
+    Xor,
     Type,
     If,
     Else,
@@ -47,13 +51,41 @@ pub enum TokenType {
     False,
     To,
     Reduce,
+    ReduceWhile,
+    ReduceCount,
+    With,
+    Scan,
     Filter,
+    FlatMap,
+    CountWhere,
+    FindIndex,
+    Inspect,
+    ChunkBy,
+    SumBy,
+    ToJson,
+    FromJson,
     Len,
+    Cache,
+    MaxBy,
+    MinBy,
+    SortBy,
+    SortByKey,
     ZipMap,
+    ZipMapIndexed,
+    ZipLongest,
+    Zip,
+    WithIndex,
     
     Some,
     IsSome,
     Unwrap,
+    Match,
+    Null,
+
+    SetField,
+    FieldNames,
+
+    Import,
 
     Error,
     EoF,
@@ -71,10 +103,11 @@ lazy_static! {
         map.insert(']', TokenType::RSquare);
         map.insert('.', TokenType::Dot);
         map.insert(',', TokenType::Comma);
-        map.insert('=', TokenType::Eq);
+        map.insert(';', TokenType::Semicolon);
         map.insert('+', TokenType::Plus);
         map.insert('/', TokenType::Slash);
         map.insert('*', TokenType::Star);
+        map.insert('%', TokenType::Percent);
         map.insert('@', TokenType::At);
 
         map
@@ -84,6 +117,7 @@ lazy_static! {
         let mut map = FxHashMap::default();
         map.insert("and", TokenType::And);
         map.insert("or", TokenType::Or);
+        map.insert("xor", TokenType::Xor);
         map.insert("type", TokenType::Type);
         map.insert("if", TokenType::If);
         map.insert("else", TokenType::Else);
@@ -91,12 +125,38 @@ lazy_static! {
         map.insert("false", TokenType::False);
         map.insert("to", TokenType::To);
         map.insert("reduce", TokenType::Reduce);
+        map.insert("reduce_while", TokenType::ReduceWhile);
+        map.insert("reduce_count", TokenType::ReduceCount);
+        map.insert("with", TokenType::With);
+        map.insert("scan", TokenType::Scan);
         map.insert("filter", TokenType::Filter);
+        map.insert("flat_map", TokenType::FlatMap);
+        map.insert("count_where", TokenType::CountWhere);
+        map.insert("find_index", TokenType::FindIndex);
+        map.insert("inspect", TokenType::Inspect);
+        map.insert("chunk_by", TokenType::ChunkBy);
+        map.insert("sum_by", TokenType::SumBy);
+        map.insert("to_json", TokenType::ToJson);
+        map.insert("from_json", TokenType::FromJson);
         map.insert("len", TokenType::Len);
         map.insert("zipmap", TokenType::ZipMap);
+        map.insert("zipmap_indexed", TokenType::ZipMapIndexed);
+        map.insert("zip_longest", TokenType::ZipLongest);
+        map.insert("zip", TokenType::Zip);
+        map.insert("cache", TokenType::Cache);
+        map.insert("max_by", TokenType::MaxBy);
+        map.insert("min_by", TokenType::MinBy);
+        map.insert("sort_by", TokenType::SortBy);
+        map.insert("sort_by_key", TokenType::SortByKey);
+        map.insert("with_index", TokenType::WithIndex);
         map.insert("some", TokenType::Some);
         map.insert("issome", TokenType::IsSome);
         map.insert("unwrap", TokenType::Unwrap);
+        map.insert("match", TokenType::Match);
+        map.insert("null", TokenType::Null);
+        map.insert("set_field", TokenType::SetField);
+        map.insert("field_names", TokenType::FieldNames);
+        map.insert("import", TokenType::Import);
 
         map
     };
@@ -107,6 +167,11 @@ impl TokenType {
         SINGLE_CHAR_TOKENS.get(&c).copied()
     }
 
+    // all words that are parsed as keywords rather than identifiers, including builtins like `filter` and `len`
+    pub fn keywords() -> impl Iterator<Item = &'static str> {
+        KEYWORDS.keys().copied()
+    }
+
     pub fn keyword_or_ident(text: &str) -> TokenType {
         match KEYWORDS.get(text) {
             Some(ttype) => *ttype,
@@ -12,12 +12,14 @@ pub struct Function {
     pub arity: u8,
     pub heap_arity: u8,
     pub return_is_heap: bool,
+    // whether the function's body can end with a call to itself; used to suggest fixes for runaway recursion
+    pub is_tail_recursive: bool,
     pub chunk: Chunk,
 }
 
 impl Default for Function {
     fn default() -> Self {
-        Self { name: String::new(), num_upvalues: 0, num_heap_upvalues: 0, arity: 0, heap_arity: 0, return_is_heap: false, chunk: Chunk::new() }
+        Self { name: String::new(), num_upvalues: 0, num_heap_upvalues: 0, arity: 0, heap_arity: 0, return_is_heap: false, is_tail_recursive: false, chunk: Chunk::new() }
     }
 }
 
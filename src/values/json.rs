@@ -0,0 +1,244 @@
+use std::{iter::Peekable, rc::Rc, str::Chars};
+
+use rustc_hash::FxHashMap;
+
+use crate::ast::Type;
+
+use super::{HeapValue, Object, ReturnValue, TypeDef, Value};
+
+
+// a parsed JSON document, before it is converted to a value of some type
+enum Json {
+    Null,
+    Bool(bool),
+    // numbers are kept as text so integers can be parsed without going through f64
+    Number(String),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+// arrays and objects nested deeper than this are rejected rather than overflowing the stack
+const MAX_DEPTH: usize = 512;
+
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { chars: source.chars().peekable(), depth: 0 }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn expect_word(&mut self, word: &str) -> Option<()> {
+        for c in word.chars() {
+            self.chars.next_if_eq(&c)?;
+        }
+        Some(())
+    }
+
+    fn document(&mut self) -> Option<Json> {
+        let json = self.value()?;
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None => Some(json),
+            Some(_) => None,
+        }
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            'n' => self.expect_word("null").map(|_| Json::Null),
+            't' => self.expect_word("true").map(|_| Json::Bool(true)),
+            'f' => self.expect_word("false").map(|_| Json::Bool(false)),
+            '"' => self.string().map(Json::Str),
+            '[' => self.nested(Self::array),
+            '{' => self.nested(Self::object),
+            c if c == '-' || c.is_ascii_digit() => Some(self.number()),
+            _ => None,
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<Json>) -> Option<Json> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let json = parse(self);
+        self.depth -= 1;
+        json
+    }
+
+    fn number(&mut self) -> Json {
+        let mut text = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+            text.push(c);
+        }
+        Json::Number(text)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.chars.next_if_eq(&'"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(out),
+                '\\' => match self.chars.next()? {
+                    '"' => out.push('"'),
+                    '\\' => out.push('\\'),
+                    '/' => out.push('/'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'u' => out.push(self.unicode_escape()?),
+                    _ => return None,
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    // reads the hex digits after a `\u`, including the second half of a surrogate pair if there is one
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        self.expect_word("\\u")?;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let mut n = 0;
+        for _ in 0..4 {
+            n = n * 16 + self.chars.next()?.to_digit(16)?;
+        }
+        Some(n)
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.chars.next_if_eq(&'[')?;
+        let mut elems = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Some(Json::Arr(elems));
+        }
+        loop {
+            elems.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => continue,
+                ']' => return Some(Json::Arr(elems)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.chars.next_if_eq(&'{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Some(Json::Obj(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.chars.next_if_eq(&':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => continue,
+                '}' => return Some(Json::Obj(fields)),
+                _ => return None,
+            }
+        }
+    }
+}
+
+// converts parsed JSON to a value of the given type, or None if the JSON doesn't match the type
+fn convert(json: Json, typ: &Type) -> Option<ReturnValue> {
+    let value = match (json, typ) {
+        (Json::Number(n), Type::Int) => ReturnValue::Value(Value::from_i64(n.parse().ok()?)),
+        (Json::Number(n), Type::Float) => ReturnValue::Value(Value::from_f64(n.parse().ok()?)),
+        (Json::Bool(b), Type::Bool) => ReturnValue::Value(Value::from_bool(b)),
+        (Json::Str(s), Type::Str) => ReturnValue::HeapValue(HeapValue::String(Rc::new(s))),
+        (Json::Null, Type::Maybe(t)) => ReturnValue::HeapValue(
+            if t.is_heap() { HeapValue::MaybeHeap(None) } else { HeapValue::Maybe(None) }
+        ),
+        (json, Type::Maybe(t)) => ReturnValue::HeapValue(wrap_some(convert(json, t)?)),
+        (Json::Arr(elems), Type::Arr(t)) => {
+            let elems = elems.into_iter().map(|e| convert(e, t)).collect::<Option<Vec<_>>>()?;
+            ReturnValue::HeapValue(if t.is_heap() {
+                HeapValue::ArrayHeap(elems.into_iter().map(expect_heap).collect())
+            } else {
+                HeapValue::Array(elems.into_iter().map(expect_value).collect())
+            })
+        },
+        (Json::Obj(mut entries), Type::Object(name, field_types)) => {
+            let typedef = TypeDef::new(
+                name.clone(),
+                field_types.iter().map(|(n, t)| (n.clone(), t.is_heap())).collect()
+            );
+            let mut fields = FxHashMap::default();
+            let mut heap_fields = FxHashMap::default();
+            for (field, t) in field_types {
+                // extra keys are ignored, but every field of the object must be present
+                let idx = entries.iter().position(|(k, _)| k == field)?;
+                match convert(entries.swap_remove(idx).1, t)? {
+                    ReturnValue::Value(v) => { fields.insert(field.clone(), v); },
+                    ReturnValue::HeapValue(v) => { heap_fields.insert(field.clone(), v); },
+                }
+            }
+            ReturnValue::HeapValue(HeapValue::Object(Rc::new(
+                Object::new(Rc::new(typedef), fields, heap_fields)
+            )))
+        },
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn wrap_some(value: ReturnValue) -> HeapValue {
+    match value {
+        ReturnValue::Value(v) => HeapValue::Maybe(Some(v)),
+        ReturnValue::HeapValue(v) => HeapValue::MaybeHeap(Some(Box::new(v))),
+    }
+}
+
+fn expect_value(value: ReturnValue) -> Value {
+    match value {
+        ReturnValue::Value(v) => v,
+        ReturnValue::HeapValue(_) => unreachable!(),
+    }
+}
+
+fn expect_heap(value: ReturnValue) -> HeapValue {
+    match value {
+        ReturnValue::HeapValue(v) => v,
+        ReturnValue::Value(_) => unreachable!(),
+    }
+}
+
+/// Parses a JSON string into a `Maybe` of the given type, which is null if the string is not valid JSON
+/// or doesn't match the type.
+pub fn from_json(source: &str, typ: &Type) -> HeapValue {
+    match JsonParser::new(source).document().and_then(|json| convert(json, typ)) {
+        Some(value) => wrap_some(value),
+        None if typ.is_heap() => HeapValue::MaybeHeap(None),
+        None => HeapValue::Maybe(None),
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::rc::Rc;
 
@@ -5,7 +7,7 @@ use dyn_clone::DynClone;
 
 use crate::VM;
 
-use super::{Closure, HeapValue, NativeFunction, TypeDef, Value};
+use super::{Closure, HeapValue, NativeFunction, Object, TypeDef, Value};
 
 
 pub trait LazyIter<T: Clone>: DynClone + Debug {
@@ -17,6 +19,17 @@ pub trait LazyIter<T: Clone>: DynClone + Debug {
         }
         Rc::from(arr)
     }
+    // like into_array, but gives up and returns None if there are more than limit elements
+    fn collect_limited(&mut self, limit: usize) -> Option<Rc<[T]>> {
+        let mut arr = Vec::new();
+        while let Some(x) = self.next() {
+            if arr.len() == limit {
+                return None;
+            }
+            arr.push(x);
+        }
+        Some(Rc::from(arr))
+    }
 }
 dyn_clone::clone_trait_object!(<T> LazyIter<T>);
 
@@ -54,6 +67,13 @@ impl<T: Clone + Debug> LazyIter<T> for ArrayIter<T> {
     fn into_array(&mut self) -> Rc<[T]> {
         self.array.clone()
     }
+
+    fn collect_limited(&mut self, limit: usize) -> Option<Rc<[T]>> {
+        if self.array.len() > limit {
+            return None;
+        }
+        Some(self.array.clone())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -82,6 +102,27 @@ impl LazyIter<Value> for RangeIter {
     }
 }
 
+// Yields 0, 1, 2, ... without end; used by `with_index` to zip indices with another iterator,
+// so the indices are generated lazily and also work with iterators of unknown length
+#[derive(Clone, Debug, Default)]
+pub struct IndexZipIter {
+    current: i64,
+}
+
+impl IndexZipIter {
+    pub fn new() -> Self {
+        Self { current: 0 }
+    }
+}
+
+impl LazyIter<Value> for IndexZipIter {
+    fn next(&mut self) -> Option<Value> {
+        let value = self.current;
+        self.current += 1;
+        Some(Value::from_i64(value))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ReverseRangeIter {
     end: i64,
@@ -108,6 +149,263 @@ impl LazyIter<Value> for ReverseRangeIter {
     }
 }
 
+// Yields the values of an inner iterator, computing them only once across all clones of this iterator
+// This is eager: on the first call to next, the entire inner iterator is consumed and stored in the cache
+#[derive(Clone, Debug)]
+pub struct CacheIter<T: Clone + Debug> {
+    iter: Rc<RefCell<Option<Box<dyn LazyIter<T>>>>>,
+    cache: Rc<RefCell<Vec<T>>>,
+    idx: usize,
+}
+
+impl<T: Clone + Debug> CacheIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>) -> Self {
+        Self { iter: Rc::new(RefCell::new(Some(iter))), cache: Rc::new(RefCell::new(Vec::new())), idx: 0 }
+    }
+
+    fn fill_cache(&self) {
+        // take the inner iterator out first, so that it is consumed at most once
+        let iter = self.iter.borrow_mut().take();
+        if let Some(mut iter) = iter {
+            *self.cache.borrow_mut() = iter.into_array().to_vec();
+        }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for CacheIter<T> {
+    fn next(&mut self) -> Option<T> {
+        self.fill_cache();
+        let value = self.cache.borrow().get(self.idx).cloned();
+        if value.is_some() {
+            self.idx += 1;
+        }
+        value
+    }
+
+    fn into_array(&mut self) -> Rc<[T]> {
+        self.fill_cache();
+        let cache = self.cache.borrow();
+        let arr = Rc::from(&cache[self.idx.min(cache.len())..]);
+        self.idx = cache.len();
+        arr
+    }
+}
+
+// An iterator that yields the elements of an inner iterator with a separator between each consecutive pair
+#[derive(Clone, Debug)]
+pub struct IntersperseIter<T: Clone + Debug> {
+    iter: Box<dyn LazyIter<T>>,
+    sep: T,
+    // element that has already been taken from the inner iterator, to be yielded after the next separator
+    pending: Option<T>,
+    started: bool,
+}
+
+impl<T: Clone + Debug> IntersperseIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, sep: T) -> Self {
+        Self { iter, sep, pending: None, started: false }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for IntersperseIter<T> {
+    fn next(&mut self) -> Option<T> {
+        if let Some(x) = self.pending.take() {
+            return Some(x);
+        }
+        let x = self.iter.next()?;
+        if self.started {
+            self.pending = Some(x);
+            Some(self.sep.clone())
+        }
+        else {
+            self.started = true;
+            Some(x)
+        }
+    }
+}
+
+// An iterator that skips elements equal to the element yielded just before them
+#[derive(Clone, Debug)]
+pub struct DedupIter<T: Clone + Debug> {
+    iter: Box<dyn LazyIter<T>>,
+    last: Option<T>,
+    eq: fn(&T, &T) -> bool,
+}
+
+impl<T: Clone + Debug> DedupIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, eq: fn(&T, &T) -> bool) -> Self {
+        Self { iter, last: None, eq }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for DedupIter<T> {
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let x = self.iter.next()?;
+            if let Some(last) = &self.last {
+                if (self.eq)(last, &x) {
+                    continue;
+                }
+            }
+            self.last = Some(x.clone());
+            return Some(x);
+        }
+    }
+}
+
+// An iterator that yields at most the first `remaining` elements of an inner iterator
+#[derive(Clone, Debug)]
+pub struct TakeIter<T: Clone + Debug> {
+    iter: Box<dyn LazyIter<T>>,
+    remaining: usize,
+}
+
+impl<T: Clone + Debug> TakeIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, n: usize) -> Self {
+        Self { iter, remaining: n }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for TakeIter<T> {
+    fn next(&mut self) -> Option<T> {
+        // stop without touching the inner iterator, so infinite iterators can be taken from
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.iter.next()
+    }
+}
+
+// An iterator that skips the first `to_skip` elements of an inner iterator, then yields the rest
+// elements are skipped on the first call to next, not when the iterator is created
+#[derive(Clone, Debug)]
+pub struct DropIter<T: Clone + Debug> {
+    iter: Box<dyn LazyIter<T>>,
+    to_skip: usize,
+}
+
+impl<T: Clone + Debug> DropIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, n: usize) -> Self {
+        Self { iter, to_skip: n }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for DropIter<T> {
+    fn next(&mut self) -> Option<T> {
+        while self.to_skip > 0 {
+            self.to_skip -= 1;
+            self.iter.next()?;
+        }
+        self.iter.next()
+    }
+}
+
+// An iterator that yields the running result of combining the elements of an inner iterator with a fixed reducer
+#[derive(Clone, Debug)]
+pub struct ScanIter<T: Clone + Debug> {
+    iter: Box<dyn LazyIter<T>>,
+    acc: Option<T>,
+    reducer: fn(T, T) -> T,
+}
+
+impl<T: Clone + Debug> ScanIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, reducer: fn(T, T) -> T) -> Self {
+        Self { iter, acc: None, reducer }
+    }
+}
+
+impl<T: Clone + Debug> LazyIter<T> for ScanIter<T> {
+    fn next(&mut self) -> Option<T> {
+        let x = self.iter.next()?;
+        let acc = match self.acc.take() {
+            Some(acc) => (self.reducer)(acc, x),
+            None => x,
+        };
+        self.acc = Some(acc.clone());
+        Some(acc)
+    }
+}
+
+// Like ScanIter, but the reducer is a function called by the VM with the accumulator and each element
+// The accumulator is a Value
+#[derive(Clone, Debug)]
+pub struct FnScanIter<T: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    acc: Value,
+    f: HeapValue,
+    vm: *mut VM,
+}
+
+impl<T: Debug + Clone> FnScanIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, init: Value, f: HeapValue, vm: *mut VM) -> FnScanIter<T> {
+        Self { iter, acc: init, f, vm }
+    }
+}
+
+impl LazyIter<Value> for FnScanIter<Value> {
+    fn next(&mut self) -> Option<Value> {
+        let x = self.iter.next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.stack.push(self.acc);
+        vm.stack.push(x);
+        vm.call_value(&self.f).expect("Unrecoverable error in scan iterator");
+        self.acc = vm.stack.pop().expect("Expected result on stack");
+        Some(self.acc)
+    }
+}
+
+impl LazyIter<Value> for FnScanIter<HeapValue> {
+    fn next(&mut self) -> Option<Value> {
+        let x = self.iter.next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.stack.push(self.acc);
+        vm.heap_stack.push(x);
+        vm.call_value(&self.f).expect("Unrecoverable error in scan iterator");
+        self.acc = vm.stack.pop().expect("Expected result on stack");
+        Some(self.acc)
+    }
+}
+
+// Like FnScanIter, but the accumulator is a HeapValue
+#[derive(Clone, Debug)]
+pub struct FnScanIterHeap<T: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    acc: HeapValue,
+    f: HeapValue,
+    vm: *mut VM,
+}
+
+impl<T: Debug + Clone> FnScanIterHeap<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, init: HeapValue, f: HeapValue, vm: *mut VM) -> FnScanIterHeap<T> {
+        Self { iter, acc: init, f, vm }
+    }
+}
+
+impl LazyIter<HeapValue> for FnScanIterHeap<Value> {
+    fn next(&mut self) -> Option<HeapValue> {
+        let x = self.iter.next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.heap_stack.push(self.acc.clone());
+        vm.stack.push(x);
+        vm.call_value(&self.f).expect("Unrecoverable error in scan iterator");
+        self.acc = vm.heap_stack.pop().expect("Expected result on heap stack");
+        Some(self.acc.clone())
+    }
+}
+
+impl LazyIter<HeapValue> for FnScanIterHeap<HeapValue> {
+    fn next(&mut self) -> Option<HeapValue> {
+        let x = self.iter.next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.heap_stack.push(self.acc.clone());
+        vm.heap_stack.push(x);
+        vm.call_value(&self.f).expect("Unrecoverable error in scan iterator");
+        self.acc = vm.heap_stack.pop().expect("Expected result on heap stack");
+        Some(self.acc.clone())
+    }
+}
+
 // An iterator that iterates over some input iterator, calls a closure, and yields the closure's result
 // The closure must return a Value (have return_is_heap == false) 
 #[derive(Clone, Debug)]
@@ -340,6 +638,33 @@ impl LazyIter<HeapValue> for IndexIter<HeapValue> {
 }
 
 
+// An iterator that flattens an iterator of arrays, yielding the elements of each array in turn
+#[derive(Clone, Debug)]
+pub struct FlatMapIter<T: Debug + Clone> {
+    arrays: Box<dyn LazyIter<HeapValue>>,
+    current: Option<ArrayIter<T>>,
+    // converts an element of arrays into the array it holds
+    unwrap: fn(HeapValue) -> Rc<[T]>,
+}
+
+impl<T: Debug + Clone> FlatMapIter<T> {
+    pub fn new(arrays: Box<dyn LazyIter<HeapValue>>, unwrap: fn(HeapValue) -> Rc<[T]>) -> Self {
+        Self { arrays, current: None, unwrap }
+    }
+}
+
+impl<T: Debug + Clone> LazyIter<T> for FlatMapIter<T> {
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(x) = self.current.as_mut().and_then(|arr| arr.next()) {
+                return Some(x);
+            }
+            let arr = self.arrays.next()?;
+            self.current = Some(ArrayIter::new((self.unwrap)(arr)));
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FilterIter<T: Debug + Clone> {
     bool_iter: Box<dyn LazyIter<Value>>,
@@ -383,6 +708,185 @@ impl LazyIter<HeapValue> for FilterIter<HeapValue> {
 }
 
 
+// calls a function on each element before yielding it, discarding the function's result
+#[derive(Clone, Debug)]
+pub struct InspectIter<T: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    // should be a Closure or NativeFunction
+    callee: HeapValue,
+    result_is_heap: bool,
+    vm: *mut VM,
+}
+
+impl<T: Debug + Clone> InspectIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, callee: HeapValue, vm: *mut VM) -> Self {
+        let result_is_heap = match &callee {
+            HeapValue::Closure(c) => c.function.return_is_heap,
+            HeapValue::NativeFunction(f) => f.return_is_heap,
+            _ => unreachable!(),
+        };
+        Self { iter, callee, result_is_heap, vm }
+    }
+
+    // calls the function on arguments already pushed to the VM, then drops the result
+    fn call(&self) {
+        let vm = unsafe { &mut *self.vm };
+        vm.call_value(&self.callee).expect("Unrecoverable error in inspect iterator");
+        if self.result_is_heap {
+            vm.heap_stack.pop();
+        }
+        else {
+            vm.stack.pop();
+        }
+    }
+}
+
+impl LazyIter<Value> for InspectIter<Value> {
+    fn next(&mut self) -> Option<Value> {
+        let x = self.iter.next()?;
+        unsafe { (*self.vm).stack.push(x) };
+        self.call();
+        Some(x)
+    }
+}
+
+impl LazyIter<HeapValue> for InspectIter<HeapValue> {
+    fn next(&mut self) -> Option<HeapValue> {
+        let x = self.iter.next()?;
+        unsafe { (*self.vm).heap_stack.push(x.clone()) };
+        self.call();
+        Some(x)
+    }
+}
+
+
+// groups maximal runs of consecutive values that have equal keys into arrays
+// the key of each value is computed by calling f on it as it is pulled from the inner iterator
+#[derive(Clone, Debug)]
+pub struct ChunkByIter<T: Debug + Clone, K: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    f: HeapValue,
+    vm: *mut VM,
+    // first element of the next run, which was read while finishing the previous one
+    pending: Option<(T, K)>,
+    push: fn(&mut VM, T),
+    pop_key: fn(&mut VM) -> K,
+    same_key: fn(&K, &K) -> bool,
+    wrap: fn(Rc<[T]>) -> HeapValue,
+}
+
+impl<T: Debug + Clone, K: Debug + Clone> ChunkByIter<T, K> {
+    pub fn new(
+        iter: Box<dyn LazyIter<T>>,
+        f: HeapValue,
+        vm: *mut VM,
+        push: fn(&mut VM, T),
+        pop_key: fn(&mut VM) -> K,
+        same_key: fn(&K, &K) -> bool,
+        wrap: fn(Rc<[T]>) -> HeapValue,
+    ) -> Self {
+        Self { iter, f, vm, pending: None, push, pop_key, same_key, wrap }
+    }
+
+    // pulls the next value from the inner iterator, along with its key
+    fn next_keyed(&mut self) -> Option<(T, K)> {
+        let x = self.iter.next()?;
+        let vm = unsafe { &mut *self.vm };
+        (self.push)(vm, x.clone());
+        vm.call_value(&self.f).expect("Unrecoverable error in chunk_by iterator");
+        Some((x, (self.pop_key)(vm)))
+    }
+}
+
+impl<T: Debug + Clone + 'static, K: Debug + Clone + 'static> LazyIter<HeapValue> for ChunkByIter<T, K> {
+    fn next(&mut self) -> Option<HeapValue> {
+        let (first, key) = match self.pending.take() {
+            Some(p) => p,
+            None => self.next_keyed()?,
+        };
+        let mut run = vec![first];
+        while let Some((x, k)) = self.next_keyed() {
+            if (self.same_key)(&key, &k) {
+                run.push(x);
+            }
+            else {
+                self.pending = Some((x, k));
+                break;
+            }
+        }
+        Some((self.wrap)(Rc::from(run)))
+    }
+}
+
+// run-length encoding: yields an object `Run { value, count }` for each run of consecutive equal elements
+#[derive(Clone, Debug)]
+pub struct RunLengthIter<T: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    // the first element of the next run, already taken from the inner iterator while finding the end of the last run
+    pending: Option<T>,
+    typedef: Rc<TypeDef>,
+    eq: fn(&T, &T) -> bool,
+    set_value: fn(&mut Object, T),
+}
+
+impl<T: Debug + Clone> RunLengthIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, typedef: Rc<TypeDef>, eq: fn(&T, &T) -> bool, set_value: fn(&mut Object, T)) -> Self {
+        Self { iter, pending: None, typedef, eq, set_value }
+    }
+}
+
+impl<T: Debug + Clone + 'static> LazyIter<HeapValue> for RunLengthIter<T> {
+    fn next(&mut self) -> Option<HeapValue> {
+        let value = match self.pending.take() {
+            Some(x) => x,
+            None => self.iter.next()?,
+        };
+        let mut count = 1;
+        loop {
+            match self.iter.next() {
+                Some(x) if (self.eq)(&value, &x) => count += 1,
+                next => {
+                    self.pending = next;
+                    break;
+                }
+            }
+        }
+        let mut obj = Object::new(self.typedef.clone(), Default::default(), Default::default());
+        obj.fields.insert("count".to_string(), Value::from_i64(count));
+        (self.set_value)(&mut obj, value);
+        Some(HeapValue::Object(Rc::new(obj)))
+    }
+}
+
+// yields each run of `size` consecutive elements as an array, overlapping; yields nothing if there are fewer than `size` elements
+#[derive(Clone, Debug)]
+pub struct WindowIter<T: Debug + Clone> {
+    iter: Box<dyn LazyIter<T>>,
+    size: usize,
+    // the most recent elements, holding the last window yielded once the iterator has started
+    buffer: VecDeque<T>,
+    wrap: fn(Rc<[T]>) -> HeapValue,
+}
+
+impl<T: Debug + Clone> WindowIter<T> {
+    pub fn new(iter: Box<dyn LazyIter<T>>, size: usize, wrap: fn(Rc<[T]>) -> HeapValue) -> Self {
+        Self { iter, size, buffer: VecDeque::with_capacity(size), wrap }
+    }
+}
+
+impl<T: Debug + Clone + 'static> LazyIter<HeapValue> for WindowIter<T> {
+    fn next(&mut self) -> Option<HeapValue> {
+        if self.buffer.len() == self.size {
+            self.buffer.pop_front();
+        }
+        while self.buffer.len() < self.size {
+            self.buffer.push_back(self.iter.next()?);
+        }
+        Some((self.wrap)(self.buffer.iter().cloned().collect()))
+    }
+}
+
+
 #[derive(Clone, Debug)]
 pub struct ZipIter {
     iters: Vec<Box<dyn LazyIter<Value>>>,
@@ -440,6 +944,125 @@ impl LazyIter<HeapValue> for ZipIter {
 }
 
 
+// Like ZipIter, but continues until all of the inner iterators are exhausted
+// Once an inner iterator is exhausted, its default value is used in its place
+#[derive(Clone, Debug)]
+pub struct ZipLongestIter {
+    iters: Vec<(Box<dyn LazyIter<Value>>, Value)>,
+    heap_iters: Vec<(Box<dyn LazyIter<HeapValue>>, HeapValue)>,
+    // should be a Closure or NativeFunction
+    callee: HeapValue,
+    vm: *mut VM,
+}
+
+impl ZipLongestIter {
+    pub fn new(iters: Vec<(Box<dyn LazyIter<Value>>, Value)>, heap_iters: Vec<(Box<dyn LazyIter<HeapValue>>, HeapValue)>, callee: HeapValue, vm: *mut VM) -> Self {
+        Self { iters, heap_iters, callee, vm }
+    }
+
+    fn _next(&mut self) -> Option<()> {
+        let vm = unsafe { &mut *self.vm };
+        let mut any_remaining = false;
+        let mut stack_values = Vec::new();
+        let mut heap_stack_values = Vec::new();
+        for (iter, default) in self.iters.iter_mut() {
+            match iter.next() {
+                None => stack_values.push(*default),
+                Some(x) => {
+                    any_remaining = true;
+                    stack_values.push(x);
+                }
+            }
+        }
+        for (iter, default) in self.heap_iters.iter_mut() {
+            match iter.next() {
+                None => heap_stack_values.push(default.clone()),
+                Some(x) => {
+                    any_remaining = true;
+                    heap_stack_values.push(x);
+                }
+            }
+        }
+        if !any_remaining {
+            return None;
+        }
+        vm.stack.append(&mut stack_values);
+        vm.heap_stack.append(&mut heap_stack_values);
+        match &self.callee {
+            HeapValue::Closure(c) => vm.call_function(c.clone()).expect("Unrecoverable error in zip iterator"),
+            HeapValue::NativeFunction(f) => vm.call_native_function(f).expect("Unrecoverable error in zip iterator"),
+            _ => unreachable!(),
+        }
+        Some(())
+    }
+}
+
+impl LazyIter<Value> for ZipLongestIter {
+    fn next(&mut self) -> Option<Value> {
+        self._next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.stack.pop()
+    }
+}
+
+impl LazyIter<HeapValue> for ZipLongestIter {
+    fn next(&mut self) -> Option<HeapValue> {
+        self._next()?;
+        let vm = unsafe { &mut *self.vm };
+        vm.heap_stack.pop()
+    }
+}
+
+
+// one side of a ZipPairIter, which may yield either stack or heap values
+#[derive(Clone, Debug)]
+pub enum ZipPairSide {
+    Value(Box<dyn LazyIter<Value>>),
+    Heap(Box<dyn LazyIter<HeapValue>>),
+}
+
+impl ZipPairSide {
+    // advances this side and stores the result in the named field of obj
+    fn next_into(&mut self, obj: &mut Object, field: &str) -> Option<()> {
+        match self {
+            Self::Value(iter) => { obj.fields.insert(field.to_string(), iter.next()?); },
+            Self::Heap(iter) => { obj.heap_fields.insert(field.to_string(), iter.next()?); },
+        }
+        Some(())
+    }
+}
+
+// yields `Pair { first, second }` objects from two iterators, stopping when either is exhausted
+#[derive(Clone, Debug)]
+pub struct ZipPairIter {
+    first: ZipPairSide,
+    second: ZipPairSide,
+    typedef: Rc<TypeDef>,
+}
+
+impl ZipPairIter {
+    pub fn new(first: ZipPairSide, second: ZipPairSide) -> Self {
+        let typedef = TypeDef::new(
+            "Pair".to_string(),
+            vec![
+                ("first".to_string(), matches!(first, ZipPairSide::Heap(_))),
+                ("second".to_string(), matches!(second, ZipPairSide::Heap(_))),
+            ]
+        );
+        Self { first, second, typedef: Rc::new(typedef) }
+    }
+}
+
+impl LazyIter<HeapValue> for ZipPairIter {
+    fn next(&mut self) -> Option<HeapValue> {
+        let mut obj = Object::new(self.typedef.clone(), Default::default(), Default::default());
+        self.first.next_into(&mut obj, "first")?;
+        self.second.next_into(&mut obj, "second")?;
+        Some(HeapValue::Object(Rc::new(obj)))
+    }
+}
+
+
 #[derive(Clone, Debug)]
 pub struct ZipIterNative {
     iters: Vec<Box<dyn LazyIter<Value>>>,
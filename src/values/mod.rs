@@ -1,4 +1,5 @@
 mod heap_value;
+mod json;
 mod functions;
 mod lazy_iter;
 mod object;
@@ -11,6 +12,7 @@ pub use functions::*;
 pub use lazy_iter::*;
 pub use object::*;
 pub use tagged_value::TaggedValue;
+pub use json::from_json;
 
 #[derive(Debug)]
 pub enum ReturnValue {
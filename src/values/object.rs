@@ -39,6 +39,11 @@ impl TypeDef {
     pub fn new(name: String, fields: Vec<(String, bool)>) -> Self {
         Self { name, fields }
     }
+
+    // the names of the fields, in the order they were declared
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(name, _)| name.as_str())
+    }
 }
 
 pub struct Object {
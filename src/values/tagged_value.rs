@@ -7,6 +7,17 @@ use crate::ast;
 use super::{Closure, TypeDef, Value};
 
 
+/// A value returned from the VM, tagged with its type.
+///
+/// Values can be inspected with the `as_*` accessors, or converted into plain Rust types with `TryFrom`:
+///
+/// ```
+/// use henrylang::VM;
+///
+/// let result = VM::new().interpret("[1, 2, 3]").unwrap();
+/// let arr: Vec<i64> = result.try_into().unwrap();
+/// assert_eq!(arr, vec![1, 2, 3]);
+/// ```
 #[derive(Debug)]
 pub enum TaggedValue {
     Int(i64),
@@ -89,3 +100,230 @@ impl TaggedValue {
         })
     }
 }
+
+impl TaggedValue {
+    /// Serializes the value to a JSON string.
+    ///
+    /// Null `Maybe`s become `null` and other `Maybe`s are written as their contents.
+    /// Object fields are written in alphabetical order, and non-finite floats are written as `null`.
+    ///
+    /// ```
+    /// use henrylang::VM;
+    ///
+    /// let result = VM::new().interpret("[some(1.5), {}: Float]").unwrap();
+    /// assert_eq!(result.to_json(), "[1.5,null]");
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+    fn write_json(&self, out: &mut String) {
+        match self {
+            TaggedValue::Int(i) => out.push_str(&i.to_string()),
+            TaggedValue::Float(f) => if f.is_finite() {
+                out.push_str(&format!("{:?}", f))
+            } else {
+                out.push_str("null")
+            },
+            TaggedValue::Bool(b) => out.push_str(&b.to_string()),
+            TaggedValue::Str(s) => write_json_str(s, out),
+            TaggedValue::Arr(arr) => {
+                out.push('[');
+                for (i, v) in arr.iter().enumerate() {
+                    if i != 0 {
+                        out.push(',');
+                    }
+                    v.write_json(out);
+                }
+                out.push(']');
+            },
+            TaggedValue::Maybe(maybe) => match maybe {
+                Some(v) => v.write_json(out),
+                None => out.push_str("null"),
+            },
+            TaggedValue::Object(_, fields) => {
+                let mut fields = fields.iter().collect::<Vec<_>>();
+                fields.sort_by_key(|(k, _)| k.as_str());
+                out.push('{');
+                for (i, (k, v)) in fields.into_iter().enumerate() {
+                    if i != 0 {
+                        out.push(',');
+                    }
+                    write_json_str(k, out);
+                    out.push(':');
+                    v.write_json(out);
+                }
+                out.push('}');
+            },
+            // these are rejected at compile time by to_json, but can still show up when called from Rust
+            TaggedValue::Closure(_) | TaggedValue::TypeDef(_) => out.push_str("null"),
+        }
+    }
+}
+
+fn write_json_str(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl TaggedValue {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            TaggedValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            TaggedValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TaggedValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TaggedValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_arr(&self) -> Option<&[TaggedValue]> {
+        match self {
+            TaggedValue::Arr(arr) => Some(arr),
+            _ => None,
+        }
+    }
+    /// Returns `Some(None)` for a null Maybe, and `None` if this is not a Maybe at all.
+    pub fn as_maybe(&self) -> Option<Option<&TaggedValue>> {
+        match self {
+            TaggedValue::Maybe(maybe) => Some(maybe.as_deref()),
+            _ => None,
+        }
+    }
+    /// Gets a field of an object by name.
+    ///
+    /// ```
+    /// use henrylang::VM;
+    ///
+    /// let result = VM::new().interpret("Point := type { x: Int, y: Int } Point(1, 2)").unwrap();
+    /// assert_eq!(result.as_object_field("y").and_then(|y| y.as_int()), Some(2));
+    /// ```
+    pub fn as_object_field(&self, name: &str) -> Option<&TaggedValue> {
+        match self {
+            TaggedValue::Object(_, fields) => fields.get(name),
+            _ => None,
+        }
+    }
+    fn type_name(&self) -> &'static str {
+        match self {
+            TaggedValue::Int(_) => "Int",
+            TaggedValue::Float(_) => "Float",
+            TaggedValue::Bool(_) => "Bool",
+            TaggedValue::Str(_) => "Str",
+            TaggedValue::Arr(_) => "Arr",
+            TaggedValue::Maybe(_) => "Maybe",
+            TaggedValue::Closure(_) => "Func",
+            TaggedValue::TypeDef(_) => "TypeDef",
+            TaggedValue::Object(..) => "Object",
+        }
+    }
+}
+
+impl From<i64> for TaggedValue {
+    fn from(value: i64) -> Self {
+        TaggedValue::Int(value)
+    }
+}
+
+impl From<f64> for TaggedValue {
+    fn from(value: f64) -> Self {
+        TaggedValue::Float(value)
+    }
+}
+
+impl From<bool> for TaggedValue {
+    fn from(value: bool) -> Self {
+        TaggedValue::Bool(value)
+    }
+}
+
+impl From<String> for TaggedValue {
+    fn from(value: String) -> Self {
+        TaggedValue::Str(value)
+    }
+}
+
+impl From<&str> for TaggedValue {
+    fn from(value: &str) -> Self {
+        TaggedValue::Str(value.to_string())
+    }
+}
+
+impl<T: Into<TaggedValue>> From<Vec<T>> for TaggedValue {
+    fn from(value: Vec<T>) -> Self {
+        TaggedValue::Arr(value.into_iter().map(|x| x.into()).collect())
+    }
+}
+
+impl<T: Into<TaggedValue>> From<Option<T>> for TaggedValue {
+    fn from(value: Option<T>) -> Self {
+        TaggedValue::Maybe(value.map(|x| Box::new(x.into())))
+    }
+}
+
+macro_rules! impl_try_from_tagged_value {
+    ($typ:ty, $variant:ident) => {
+        impl TryFrom<TaggedValue> for $typ {
+            type Error = String;
+            fn try_from(value: TaggedValue) -> Result<Self, Self::Error> {
+                match value {
+                    TaggedValue::$variant(x) => Ok(x),
+                    x => Err(format!(
+                        "Expected a {}, but got a {}", stringify!($variant), x.type_name()
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_tagged_value!(i64, Int);
+impl_try_from_tagged_value!(f64, Float);
+impl_try_from_tagged_value!(bool, Bool);
+impl_try_from_tagged_value!(String, Str);
+
+impl<T: TryFrom<TaggedValue, Error = String>> TryFrom<TaggedValue> for Vec<T> {
+    type Error = String;
+    fn try_from(value: TaggedValue) -> Result<Self, Self::Error> {
+        match value {
+            TaggedValue::Arr(arr) => arr.into_iter().map(T::try_from).collect(),
+            x => Err(format!("Expected an Arr, but got a {}", x.type_name())),
+        }
+    }
+}
+
+impl<T: TryFrom<TaggedValue, Error = String>> TryFrom<TaggedValue> for Option<T> {
+    type Error = String;
+    fn try_from(value: TaggedValue) -> Result<Self, Self::Error> {
+        match value {
+            TaggedValue::Maybe(maybe) => maybe.map(|x| T::try_from(*x)).transpose(),
+            x => Err(format!("Expected a Maybe, but got a {}", x.type_name())),
+        }
+    }
+}
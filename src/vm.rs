@@ -1,25 +1,91 @@
 use std::cell::RefCell;
 use rustc_hash::FxHashMap;
 use std::ops::{Add, Mul, Sub, Div, Neg};
+use std::fmt::Debug;
+use std::path::Path;
 use std::rc::Rc;
 
 use crate::ast;
 use crate::builtins;
-use crate::chunk::{Chunk, OpCode};
+use crate::chunk::{Chunk, KeyType, OpCode};
 use crate::compiler;
-use crate::values::{ArrayIter, Closure, FilterIter, Function, HeapValue, IndexIter, LazyIter, MapIter, MapIterHeap, MapIterNative, MapIterNativeHeap, NativeFunction, Object, RangeIter, ReturnValue, ReverseRangeIter, TaggedValue, TypeDef, Value, ZipIter, ZipIterNative, ZipIterTypeDef};
+use crate::scanner;
+use crate::token::{Token, TokenType};
+use crate::values::{ArrayIter, CacheIter, ChunkByIter, Closure, FilterIter, FlatMapIter, FnScanIter, FnScanIterHeap, from_json, Function, HeapValue, IndexIter, IndexZipIter, InspectIter, LazyIter, MapIter, MapIterHeap, MapIterNative, MapIterNativeHeap, NativeFunction, Object, RangeIter, ReturnValue, ReverseRangeIter, TaggedValue, TypeDef, Value, ZipIter, ZipIterNative, ZipIterTypeDef, ZipLongestIter, ZipPairIter, ZipPairSide};
+
+// a key computed by MaxBy, MinBy, or SortByKey; all keys compared with each other have the same variant
+#[derive(PartialEq, PartialOrd)]
+enum SortKey {
+    Int(i64),
+    Float(f64),
+    Str(Rc<String>),
+}
+
+impl SortKey {
+    // an ordering of keys of the same variant, where NaN floats are greater than all other floats
+    fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (SortKey::Float(a), SortKey::Float(b)) => match (a.is_nan(), b.is_nan()) {
+                (false, false) => a.partial_cmp(b).unwrap(),
+                (a_nan, b_nan) => a_nan.cmp(&b_nan),
+            },
+            _ => self.partial_cmp(other).expect("Compared sort keys of different types"),
+        }
+    }
+}
+
+// broad category of an error, so that callers can tell failures apart without inspecting messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Parse,
+    UndefinedVariable,
+    TypeMismatch,
+    IndexOutOfBounds,
+    DivisionByZero,
+    Io,
+    Other,
+}
 
 #[derive(Debug, Clone)]
 pub enum InterpreterError {
-    CompileError(String),
-    RuntimeError(String),
+    CompileError(ErrorKind, String),
+    // the last field is the function call trace at the time of the error, outermost call first
+    RuntimeError(ErrorKind, String, Vec<String>),
+}
+
+impl InterpreterError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            InterpreterError::CompileError(kind, _) | InterpreterError::RuntimeError(kind, _, _) => *kind,
+        }
+    }
+    pub fn message(&self) -> &str {
+        match self {
+            InterpreterError::CompileError(_, msg) | InterpreterError::RuntimeError(_, msg, _) => msg,
+        }
+    }
+    // the function call trace for a runtime error, or an empty slice for a compile error
+    pub fn trace(&self) -> &[String] {
+        match self {
+            InterpreterError::CompileError(..) => &[],
+            InterpreterError::RuntimeError(_, _, trace) => trace,
+        }
+    }
 }
 
 impl std::fmt::Display for InterpreterError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            InterpreterError::CompileError(msg) => write!(f, "Compile error: {}", msg),
-            InterpreterError::RuntimeError(msg) => write!(f, "Runtime error: {}", msg),
+            InterpreterError::CompileError(_, msg) => write!(f, "Compile error: {}", msg),
+            InterpreterError::RuntimeError(_, msg, trace) => {
+                if !trace.is_empty() {
+                    writeln!(f, "Function call trace:")?;
+                    for line in trace {
+                        writeln!(f, " | {}", line)?;
+                    }
+                }
+                write!(f, "Runtime error: {}", msg)
+            },
         }
     }
 }
@@ -44,6 +110,15 @@ pub struct VM {
     pub heap_globals: FxHashMap<String, HeapValue>,
     pub frames: Vec<CallFrame>,
     pub typecontext: compiler::TypeContext,
+    // maximum number of elements that can be collected from an iterator into an array, if any
+    collect_limit: Option<usize>,
+    // maximum number of nested function calls, if any; deep recursion otherwise overflows the native stack
+    max_call_depth: Option<usize>,
+    // whether to print the function call trace to stdout when a runtime error occurs
+    // off by default, since the trace is also part of the error, for the caller to report as it likes
+    pub print_trace: bool,
+    // state of the xorshift generator used by `rand_int` and `rand_float`; never zero
+    rng_state: u64,
 }
 
 impl VM {
@@ -55,7 +130,90 @@ impl VM {
             heap_globals: builtins::heap_builtins(),
             frames: Vec::new(),
             typecontext: Rc::new(RefCell::new(builtins::builtin_types())),
+            collect_limit: None,
+            max_call_depth: None,
+            print_trace: false,
+            rng_state: seed_state(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64)
+            ),
+        }
+    }
+
+    // get the names of all globals and keywords, without the types used to distinguish overloaded functions
+    pub fn global_names(&self) -> Vec<String> {
+        let typecontext = self.typecontext.borrow();
+        let mut names = self.globals.keys()
+            .chain(self.heap_globals.keys())
+            .chain(typecontext.keys())
+            .map(|name| name.split('[').next().unwrap().to_string())
+            .filter(|name| !name.starts_with('<'))
+            // builtins handled by the parser, like `filter`, are keywords rather than globals
+            .chain(TokenType::keywords().map(String::from))
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // get the names and types of all globals, including builtins, sorted by name
+    // overloaded functions appear once per overload, with their argument types in the name, e.g. `sum[Arr(Int)]`
+    pub fn globals_list(&self) -> Vec<(String, ast::Type)> {
+        let mut globals = self.typecontext.borrow().iter()
+            .filter(|(name, _)| !name.starts_with('<'))
+            .map(|(name, typ)| (name.clone(), typ.clone()))
+            .collect::<Vec<_>>();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        globals
+    }
+
+    // set the maximum number of elements that can be collected into an array, or None for no limit
+    pub fn set_collect_limit(&mut self, limit: Option<usize>) {
+        self.collect_limit = limit;
+    }
+
+    // set the maximum depth of nested function calls, or None for no limit
+    pub fn set_max_call_depth(&mut self, depth: Option<usize>) {
+        self.max_call_depth = depth;
+    }
+
+    // set whether the function call trace is printed to stdout when a runtime error occurs
+    pub fn set_print_trace(&mut self, print_trace: bool) {
+        self.print_trace = print_trace;
+    }
+
+    // error for exceeding the maximum call depth, with a hint on how to avoid recursion if that is the cause
+    fn call_depth_err(&self, function: &Function, depth: usize) -> InterpreterError {
+        let mut msg = format!("Maximum call depth of {} exceeded when calling {}", depth, function.name);
+        let is_recursive = self.frames.last().is_some_and(|f| f.closure.function.name == function.name);
+        if is_recursive && function.is_tail_recursive {
+            msg.push_str(
+                "; its recursive call is in tail position, so it can be rewritten as a loop, e.g. with reduce over a range"
+            );
+        }
+        else if is_recursive {
+            msg.push_str(
+                "; consider rewriting it in tail-call form, with an accumulator passed to the recursive call, \
+                which can then be turned into a loop, e.g. with reduce over a range"
+            );
         }
+        self.runtime_err(msg)
+    }
+
+    // seed the random number generator, so that `rand_int` and `rand_float` give a reproducible sequence
+    pub fn seed(&mut self, seed: u64) {
+        self.rng_state = seed_state(seed);
+    }
+
+    // advance the random number generator (xorshift64*), returning the next 64 random bits
+    pub fn next_random(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng_state = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
     }
 
     fn init(&mut self, function: Rc<Function>) {
@@ -96,13 +254,20 @@ impl VM {
     }
 
     pub fn runtime_err(&self, e: String) -> InterpreterError {
-        // print track
-        println!("Function call trace:");
-        for frame in self.frames.iter() {
-            println!(" | Line {}, in {}...", frame.closure.function.chunk.line_num(frame.ip), frame.closure.function);
+        self.runtime_err_of_kind(ErrorKind::Other, e)
+    }
+
+    pub fn runtime_err_of_kind(&self, kind: ErrorKind, e: String) -> InterpreterError {
+        let trace = self.frames.iter().map(|frame| format!(
+            "Line {}, in {}...", frame.closure.function.chunk.line_num(frame.ip), frame.closure.function
+        )).collect::<Vec<_>>();
+        if self.print_trace {
+            println!("Function call trace:");
+            for line in &trace {
+                println!(" | {}", line);
+            }
         }
-        // return error
-        InterpreterError::RuntimeError(e.to_string())
+        InterpreterError::RuntimeError(kind, e, trace)
     }
 
     fn binary_bool_op(&mut self, op: fn(bool, bool) -> bool) {
@@ -154,6 +319,12 @@ impl VM {
     }
 
     pub fn call_function(&mut self, closure: Box<Closure>) -> Result<(), InterpreterError> {
+        if let Some(depth) = self.max_call_depth {
+            // the outermost frame is the top-level script, not a function call
+            if self.frames.len() > depth {
+                return Err(self.call_depth_err(&closure.function, depth));
+            }
+        }
         let n_args = closure.function.arity as usize;
         let n_heap_args = closure.function.heap_arity as usize;
         let is_heap = closure.function.return_is_heap;
@@ -180,6 +351,10 @@ impl VM {
     }
 
     pub fn call_native_function(&mut self, function: &'static NativeFunction) -> Result<(), InterpreterError> {
+        // arity should already be checked at compile time, but avoid panicking if something slipped through
+        if self.stack.len() < function.arity as usize || self.heap_stack.len() < function.heap_arity as usize {
+            return Err(self.runtime_err("Internal: native call arity underflow".to_string()));
+        }
         let args = self.stack.split_off(self.stack.len() - function.arity as usize);
         let heap_args = self.heap_stack.split_off(self.heap_stack.len() - function.heap_arity as usize);
         (function.function)(self, &args, &heap_args)
@@ -192,7 +367,8 @@ impl VM {
             idx = arr_len as i64 + idx;
         }
         if idx < 0 || idx >= arr_len as i64 {
-            return Err(self.runtime_err(
+            return Err(self.runtime_err_of_kind(
+                ErrorKind::IndexOutOfBounds,
                 format!("Index {} out of bounds for array of length {}", idx, arr_len)
             ));
         }
@@ -249,6 +425,245 @@ impl VM {
         Ok(())
     }
 
+    // creates a copy of an object with one field replaced
+    // the names of fields that have the same type as the new value, and that type's name, are on top of the heap stack
+    fn set_field(&mut self, is_heap: bool) -> Result<(), InterpreterError> {
+        let value_type = match self.heap_stack.pop().expect("Expected type name on heap stack") {
+            HeapValue::String(s) => s,
+            _ => unreachable!(),
+        };
+        let matching_fields = match self.heap_stack.pop().expect("Expected field names on heap stack") {
+            HeapValue::ArrayHeap(a) => a,
+            _ => unreachable!(),
+        };
+        let (value, heap_value) = if is_heap {
+            (None, Some(self.heap_stack.pop().expect("Expected value on heap stack")))
+        }
+        else {
+            (Some(self.stack.pop().expect("Expected value on stack")), None)
+        };
+        let fieldname = match self.heap_stack.pop().expect("Expected field name on heap stack") {
+            HeapValue::String(s) => s,
+            _ => unreachable!(),
+        };
+        let obj = match self.heap_stack.pop().expect("Expected object on heap stack") {
+            HeapValue::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        if !obj.typedef.fields.iter().any(|(name, _)| name == fieldname.as_ref()) {
+            return Err(self.runtime_err(format!("Object {} has no field `{}`", obj.typedef, fieldname)));
+        }
+        if !matching_fields.iter().any(|name| matches!(name, HeapValue::String(name) if name == &fieldname)) {
+            return Err(self.runtime_err(format!("Field `{}` cannot be set to a value of type {}", fieldname, value_type)));
+        }
+        let mut fields = obj.fields.clone();
+        let mut heap_fields = obj.heap_fields.clone();
+        if let Some(value) = value {
+            fields.insert(fieldname.to_string(), value);
+        }
+        if let Some(heap_value) = heap_value {
+            heap_fields.insert(fieldname.to_string(), heap_value);
+        }
+        let obj = Object::new(obj.typedef.clone(), fields, heap_fields);
+        self.heap_stack.push(HeapValue::Object(Rc::new(obj)));
+        Ok(())
+    }
+
+    // maps through the function for a flat_map, returning an iterator over the resulting arrays
+    fn flat_map_arrays(&mut self) -> Result<Box<dyn LazyIter<HeapValue>>, InterpreterError> {
+        self.map()?;
+        match self.heap_stack.pop().expect("Expected array iterator on stack after mapping through flat_map function") {
+            HeapValue::LazyIterHeap(a) => Ok(a),
+            _ => unreachable!(),
+        }
+    }
+
+    // pops two float arrays and compares them elementwise, so that 0.0 = -0.0 and NaN != NaN
+    fn float_arrays_equal(&mut self) -> bool {
+        let r = self.heap_stack.pop().expect("Expected array on heap stack");
+        let l = self.heap_stack.pop().expect("Expected array on stack");
+        match (l, r) {
+            (HeapValue::Array(l), HeapValue::Array(r)) => {
+                l.len() == r.len() && l.iter().zip(r.iter()).all(|(x, y)| unsafe { x.f == y.f })
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // folds like reduce, then replaces the accumulator with a `Counted { result, count }` object,
+    // where count is the number of elements folded over
+    fn reduce_count(&mut self, acc_is_heap: bool) -> Result<(), InterpreterError> {
+        let f = self.heap_stack.pop().expect("Expected function on heap stack");
+        let arr = self.heap_stack.pop().expect("Expected array on heap stack");
+        let mut count = 0;
+        match arr {
+            HeapValue::LazyIter(iter) => for x in iter.into_iter() {
+                self.stack.push(x);
+                self.call_value(&f)?;
+                count += 1;
+            },
+            HeapValue::LazyIterHeap(iter) => for x in iter.into_iter() {
+                self.heap_stack.push(x);
+                self.call_value(&f)?;
+                count += 1;
+            },
+            HeapValue::Array(a) => for x in a.iter() {
+                self.stack.push(*x);
+                self.call_value(&f)?;
+                count += 1;
+            },
+            HeapValue::ArrayHeap(a) => for x in a.iter() {
+                self.heap_stack.push(x.clone());
+                self.call_value(&f)?;
+                count += 1;
+            },
+            _ => unreachable!(),
+        }
+        let typedef = TypeDef::new(
+            "Counted".to_string(),
+            vec![("result".to_string(), acc_is_heap), ("count".to_string(), false)]
+        );
+        let mut obj = Object::new(Rc::new(typedef), FxHashMap::default(), FxHashMap::default());
+        obj.fields.insert("count".to_string(), Value::from_i64(count));
+        if acc_is_heap {
+            let acc = self.heap_stack.pop().expect("Expected accumulator on heap stack");
+            obj.heap_fields.insert("result".to_string(), acc);
+        }
+        else {
+            let acc = self.stack.pop().expect("Expected accumulator on stack");
+            obj.fields.insert("result".to_string(), acc);
+        }
+        self.heap_stack.push(HeapValue::Object(Rc::new(obj)));
+        Ok(())
+    }
+
+    // folds like reduce, stopping when the function returns null
+    // the accumulator is copied before each call, so that the last one is still there if the function returns null
+    fn reduce_while(&mut self, acc_is_heap: bool) -> Result<(), InterpreterError> {
+        let f = self.heap_stack.pop().expect("Expected function on heap stack");
+        let arr = self.heap_stack.pop().expect("Expected array on heap stack");
+        match arr {
+            HeapValue::LazyIter(iter) => for x in iter.into_iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.stack.push(x);
+                if !self.reduce_while_step(&f, acc_is_heap)? { break; }
+            },
+            HeapValue::LazyIterHeap(iter) => for x in iter.into_iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.heap_stack.push(x);
+                if !self.reduce_while_step(&f, acc_is_heap)? { break; }
+            },
+            HeapValue::Array(a) => for x in a.iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.stack.push(*x);
+                if !self.reduce_while_step(&f, acc_is_heap)? { break; }
+            },
+            HeapValue::ArrayHeap(a) => for x in a.iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.heap_stack.push(x.clone());
+                if !self.reduce_while_step(&f, acc_is_heap)? { break; }
+            },
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    // folds like reduce, but keeps each accumulator value, pushing an iterator over them
+    // like map, this is lazy over iterators and eager over arrays
+    fn scan(&mut self, acc_is_heap: bool) -> Result<(), InterpreterError> {
+        let f = self.heap_stack.pop().expect("Expected function on heap stack");
+        let arr = self.heap_stack.pop().expect("Expected array on heap stack");
+        let mut n_steps = 0;
+        match arr {
+            HeapValue::LazyIter(iter) => {
+                let iter = self.scan_iter(iter, f, acc_is_heap);
+                self.heap_stack.push(iter);
+                return Ok(());
+            },
+            HeapValue::LazyIterHeap(iter) => {
+                let iter = self.scan_iter(iter, f, acc_is_heap);
+                self.heap_stack.push(iter);
+                return Ok(());
+            },
+            HeapValue::Array(a) => for x in a.iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.stack.push(*x);
+                self.call_value(&f)?;
+                n_steps += 1;
+            },
+            HeapValue::ArrayHeap(a) => for x in a.iter() {
+                self.copy_accumulator(acc_is_heap);
+                self.heap_stack.push(x.clone());
+                self.call_value(&f)?;
+                n_steps += 1;
+            },
+            _ => unreachable!(),
+        }
+        // the initial value is below the accumulated values on the stack
+        self.push_map_result(n_steps, acc_is_heap);
+        let iter = self.heap_stack.pop().expect("Expected scan result on heap stack");
+        if acc_is_heap {
+            self.heap_stack.pop().expect("Expected initial value on heap stack");
+        }
+        else {
+            self.stack.pop().expect("Expected initial value on stack");
+        }
+        self.heap_stack.push(iter);
+        Ok(())
+    }
+
+    // takes the initial value off the stack, and makes an iterator that calls f as its elements are consumed
+    fn scan_iter<T: Debug + Clone + 'static>(&mut self, iter: Box<dyn LazyIter<T>>, f: HeapValue, acc_is_heap: bool) -> HeapValue
+    where FnScanIter<T>: LazyIter<Value>, FnScanIterHeap<T>: LazyIter<HeapValue> {
+        if acc_is_heap {
+            let init = self.heap_stack.pop().expect("Expected initial value on heap stack");
+            HeapValue::LazyIterHeap(Box::new(FnScanIterHeap::new(iter, init, f, self)))
+        }
+        else {
+            let init = self.stack.pop().expect("Expected initial value on stack");
+            HeapValue::LazyIter(Box::new(FnScanIter::new(iter, init, f, self)))
+        }
+    }
+
+    pub fn call_value(&mut self, f: &HeapValue) -> Result<(), InterpreterError> {
+        match f {
+            HeapValue::Closure(f) => self.call_function(f.clone()),
+            HeapValue::NativeFunction(f) => self.call_native_function(f),
+            _ => unreachable!(),
+        }
+    }
+
+    fn copy_accumulator(&mut self, acc_is_heap: bool) {
+        if acc_is_heap {
+            let acc = self.heap_stack.last().expect("Expected accumulator on heap stack").clone();
+            self.heap_stack.push(acc);
+        }
+        else {
+            let acc = *self.stack.last().expect("Expected accumulator on stack");
+            self.stack.push(acc);
+        }
+    }
+
+    // calls the reduce_while function, replacing the old accumulator if the result is not null
+    // returns whether the fold should continue
+    fn reduce_while_step(&mut self, f: &HeapValue, acc_is_heap: bool) -> Result<bool, InterpreterError> {
+        self.call_value(f)?;
+        match self.heap_stack.pop().expect("Expected Maybe on heap stack after calling reduce_while function") {
+            HeapValue::Maybe(Some(acc)) if !acc_is_heap => {
+                self.stack.pop();
+                self.stack.push(acc);
+                Ok(true)
+            },
+            HeapValue::MaybeHeap(Some(acc)) if acc_is_heap => {
+                self.heap_stack.pop();
+                self.heap_stack.push(*acc);
+                Ok(true)
+            },
+            HeapValue::Maybe(None) | HeapValue::MaybeHeap(None) => Ok(false),
+            _ => unreachable!(),
+        }
+    }
+
     fn push_map_result(&mut self, len: usize, is_heap: bool) {
         if is_heap {
             let lazy_iter = Box::new(
@@ -268,6 +683,264 @@ impl VM {
         }
     }
 
+    // finds the element with the largest (or smallest) key, where keys come from calling a function on each element
+    // ties are broken in favor of the earliest element; result is wrapped in a Maybe, which is null if there are no elements
+    fn extremum_by(&mut self, key_type: KeyType, is_max: bool) -> Result<(), InterpreterError> {
+        let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+        let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
+
+        let result = match arg {
+            HeapValue::Array(a) => HeapValue::Maybe(
+                self.best_by_key(a.iter().copied(), &callee, key_type, is_max, |vm, x| vm.stack.push(x))?
+            ),
+            HeapValue::LazyIter(iter) => HeapValue::Maybe(
+                self.best_by_key(iter.into_iter(), &callee, key_type, is_max, |vm, x| vm.stack.push(x))?
+            ),
+            HeapValue::ArrayHeap(a) => HeapValue::MaybeHeap(
+                self.best_by_key(a.iter().cloned(), &callee, key_type, is_max, |vm, x| vm.heap_stack.push(x))?.map(Box::new)
+            ),
+            HeapValue::LazyIterHeap(iter) => HeapValue::MaybeHeap(
+                self.best_by_key(iter.into_iter(), &callee, key_type, is_max, |vm, x| vm.heap_stack.push(x))?.map(Box::new)
+            ),
+            _ => unreachable!(),
+        };
+        self.heap_stack.push(result);
+        Ok(())
+    }
+
+    // calls a predicate on each element until it returns true, pushing a Maybe with that element's index
+    fn find_index(&mut self) -> Result<(), InterpreterError> {
+        let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
+        let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+
+        let index = match arg {
+            HeapValue::Array(a) => self.first_index_where(a.iter().copied(), &callee, |vm, x| vm.stack.push(x))?,
+            HeapValue::LazyIter(iter) => self.first_index_where(iter.into_iter(), &callee, |vm, x| vm.stack.push(x))?,
+            HeapValue::ArrayHeap(a) => self.first_index_where(a.iter().cloned(), &callee, |vm, x| vm.heap_stack.push(x))?,
+            HeapValue::LazyIterHeap(iter) => self.first_index_where(iter.into_iter(), &callee, |vm, x| vm.heap_stack.push(x))?,
+            _ => unreachable!(),
+        };
+        self.heap_stack.push(HeapValue::Maybe(index.map(|i| Value::from_i64(i as i64))));
+        Ok(())
+    }
+
+    fn first_index_where<T>(
+        &mut self,
+        elems: impl Iterator<Item = T>,
+        callee: &HeapValue,
+        push: fn(&mut VM, T),
+    ) -> Result<Option<usize>, InterpreterError> {
+        for (i, x) in elems.enumerate() {
+            push(self, x);
+            self.call_value(callee)?;
+            if unsafe { self.stack.pop().expect("Expected bool on stack").b } {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    // calls a function on each element and sums the results as they are produced
+    fn sum_by(&mut self, key_type: KeyType) -> Result<(), InterpreterError> {
+        let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+        let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
+
+        let sum = match arg {
+            HeapValue::Array(a) => self.sum_keys(a.iter().copied(), &callee, key_type, |vm, x| vm.stack.push(x))?,
+            HeapValue::LazyIter(iter) => self.sum_keys(iter.into_iter(), &callee, key_type, |vm, x| vm.stack.push(x))?,
+            HeapValue::ArrayHeap(a) => self.sum_keys(a.iter().cloned(), &callee, key_type, |vm, x| vm.heap_stack.push(x))?,
+            HeapValue::LazyIterHeap(iter) => self.sum_keys(iter.into_iter(), &callee, key_type, |vm, x| vm.heap_stack.push(x))?,
+            _ => unreachable!(),
+        };
+        self.stack.push(sum);
+        Ok(())
+    }
+
+    fn sum_keys<T>(
+        &mut self,
+        elems: impl Iterator<Item = T>,
+        callee: &HeapValue,
+        key_type: KeyType,
+        push: fn(&mut VM, T),
+    ) -> Result<Value, InterpreterError> {
+        let mut sum = match key_type {
+            KeyType::Int => Value::from_i64(0),
+            _ => Value::from_f64(0.0),
+        };
+        for x in elems {
+            push(self, x);
+            self.call_value(callee)?;
+            let key = self.stack.pop().expect("Expected key on stack");
+            sum = match key_type {
+                KeyType::Int => unsafe { Value::from_i64(sum.i.wrapping_add(key.i)) },
+                _ => unsafe { Value::from_f64(sum.f + key.f) },
+            };
+        }
+        Ok(sum)
+    }
+
+    fn best_by_key<T: Clone>(
+        &mut self,
+        elems: impl Iterator<Item = T>,
+        callee: &HeapValue,
+        key_type: KeyType,
+        is_max: bool,
+        push: fn(&mut VM, T),
+    ) -> Result<Option<T>, InterpreterError> {
+        let mut best: Option<(SortKey, T)> = None;
+        for x in elems {
+            push(self, x.clone());
+            self.call_value(callee)?;
+            let key = self.pop_sort_key(key_type);
+            // NaN doesn't compare with anything, so elements with NaN keys are never selected
+            if matches!(key, SortKey::Float(f) if f.is_nan()) {
+                continue;
+            }
+            let is_better = match &best {
+                None => true,
+                Some((best_key, _)) => if is_max { key > *best_key } else { key < *best_key },
+            };
+            if is_better {
+                best = Some((key, x));
+            }
+        }
+        Ok(best.map(|(_, x)| x))
+    }
+
+    // pops the key a function just returned
+    fn pop_sort_key(&mut self, key_type: KeyType) -> SortKey {
+        match key_type {
+            KeyType::Int => SortKey::Int(unsafe { self.stack.pop().expect("Expected key on stack").i }),
+            KeyType::Float => SortKey::Float(unsafe { self.stack.pop().expect("Expected key on stack").f }),
+            KeyType::Bool => SortKey::Int(unsafe { self.stack.pop().expect("Expected key on stack").b } as i64),
+            KeyType::Str => match self.heap_stack.pop().expect("Expected key on heap stack") {
+                HeapValue::String(s) => SortKey::Str(s),
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn sort_by_key(&mut self, key_type: KeyType) -> Result<(), InterpreterError> {
+        let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+        let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
+
+        let result = match arg {
+            HeapValue::Array(a) => HeapValue::Array(
+                self.sorted_by_key(a.iter().copied(), &callee, key_type, |vm, x| vm.stack.push(x))?.into()
+            ),
+            HeapValue::LazyIter(iter) => HeapValue::Array(
+                self.sorted_by_key(iter.into_iter(), &callee, key_type, |vm, x| vm.stack.push(x))?.into()
+            ),
+            HeapValue::ArrayHeap(a) => HeapValue::ArrayHeap(
+                self.sorted_by_key(a.iter().cloned(), &callee, key_type, |vm, x| vm.heap_stack.push(x))?.into()
+            ),
+            HeapValue::LazyIterHeap(iter) => HeapValue::ArrayHeap(
+                self.sorted_by_key(iter.into_iter(), &callee, key_type, |vm, x| vm.heap_stack.push(x))?.into()
+            ),
+            _ => unreachable!(),
+        };
+        self.heap_stack.push(result);
+        Ok(())
+    }
+
+    fn sorted_by_key<T: Clone>(
+        &mut self,
+        elems: impl Iterator<Item = T>,
+        callee: &HeapValue,
+        key_type: KeyType,
+        push: fn(&mut VM, T),
+    ) -> Result<Vec<T>, InterpreterError> {
+        // compute every key up front, so the function is called once per element
+        let mut keyed = Vec::new();
+        for x in elems {
+            push(self, x.clone());
+            self.call_value(callee)?;
+            keyed.push((self.pop_sort_key(key_type), x));
+        }
+        // sort_by is stable, so elements with equal keys keep their order
+        keyed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Ok(keyed.into_iter().map(|(_, x)| x).collect())
+    }
+
+    fn sort_by(&mut self) -> Result<(), InterpreterError> {
+        let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+        let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
+
+        let result = match arg {
+            HeapValue::Array(a) => HeapValue::Array(
+                self.merge_sort_by(a.to_vec(), &callee, |vm, x| vm.stack.push(x))?.into()
+            ),
+            HeapValue::LazyIter(mut iter) => HeapValue::Array(
+                self.merge_sort_by(iter.into_array().to_vec(), &callee, |vm, x| vm.stack.push(x))?.into()
+            ),
+            HeapValue::ArrayHeap(a) => HeapValue::ArrayHeap(
+                self.merge_sort_by(a.to_vec(), &callee, |vm, x| vm.heap_stack.push(x))?.into()
+            ),
+            HeapValue::LazyIterHeap(mut iter) => HeapValue::ArrayHeap(
+                self.merge_sort_by(iter.into_array().to_vec(), &callee, |vm, x| vm.heap_stack.push(x))?.into()
+            ),
+            _ => unreachable!(),
+        };
+        self.heap_stack.push(result);
+        Ok(())
+    }
+
+    // a stable merge sort using a comparison function that returns a negative, zero, or positive Int.
+    // slice::sort_by isn't used since the comparison function can fail, and may not be a consistent ordering
+    fn merge_sort_by<T: Clone>(
+        &mut self,
+        mut elems: Vec<T>,
+        callee: &HeapValue,
+        push: fn(&mut VM, T),
+    ) -> Result<Vec<T>, InterpreterError> {
+        if elems.len() <= 1 {
+            return Ok(elems);
+        }
+        let right = elems.split_off(elems.len() / 2);
+        let mut left = self.merge_sort_by(elems, callee, push)?.into_iter().peekable();
+        let mut right = self.merge_sort_by(right, callee, push)?.into_iter().peekable();
+        let mut merged = Vec::with_capacity(left.len() + right.len());
+        while let (Some(l), Some(r)) = (left.peek().cloned(), right.peek().cloned()) {
+            push(self, l);
+            push(self, r);
+            self.call_value(callee)?;
+            let cmp = unsafe { self.stack.pop().expect("Expected comparison result on stack").i };
+            // taking from the left when elements compare equal keeps the sort stable
+            if cmp <= 0 {
+                merged.extend(left.next());
+            }
+            else {
+                merged.extend(right.next());
+            }
+        }
+        merged.extend(left);
+        merged.extend(right);
+        Ok(merged)
+    }
+
+    // groups runs of consecutive elements with equal keys, where keys come from calling the function on each element
+    fn chunk_by(&mut self, key_type: KeyType) -> Result<(), InterpreterError> {
+        let arr = self.heap_stack.pop().expect("Expected array on heap stack");
+        let f = self.heap_stack.pop().expect("Expected function on heap stack");
+        let iter: Box<dyn LazyIter<HeapValue>> = match arr {
+            HeapValue::Array(a) => chunk_by_iter(
+                Box::new(ArrayIter::new(a)), f, self, key_type, |vm, x| vm.stack.push(x), HeapValue::Array
+            ),
+            HeapValue::ArrayHeap(a) => chunk_by_iter(
+                Box::new(ArrayIter::new(a)), f, self, key_type, |vm, x| vm.heap_stack.push(x), HeapValue::ArrayHeap
+            ),
+            HeapValue::LazyIter(iter) => chunk_by_iter(
+                iter, f, self, key_type, |vm, x| vm.stack.push(x), HeapValue::Array
+            ),
+            HeapValue::LazyIterHeap(iter) => chunk_by_iter(
+                iter, f, self, key_type, |vm, x| vm.heap_stack.push(x), HeapValue::ArrayHeap
+            ),
+            _ => unreachable!(),
+        };
+        self.heap_stack.push(HeapValue::LazyIterHeap(iter));
+        Ok(())
+    }
+
     fn map(&mut self) -> Result<(), InterpreterError> {
         let arg = self.heap_stack.pop().expect("Expected argument array on heap stack");
         let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
@@ -295,6 +968,8 @@ impl VM {
                     self.heap_stack.push(HeapValue::LazyIter(map_iter));
                 }
             },
+            // mapping over an array is eager: the function is called on every element here, so a runtime error in it
+            // is reported as an error instead of a panic inside a lazy iterator. `lazy(arr)` gives the lazy behavior
             // Closure -> Array
             (HeapValue::Closure(f), HeapValue::Array(a)) => {
                 let n_calls = a.len();
@@ -439,7 +1114,18 @@ impl VM {
                 OpCode::IntAdd => self.binary_int_op(i64::add),
                 OpCode::IntSubtract => self.binary_int_op(i64::sub),
                 OpCode::IntMultiply => self.binary_int_op(i64::mul),
-                OpCode::IntDivide => self.binary_int_op(i64::div),
+                OpCode::IntDivide => {
+                    if unsafe { self.stack.last().expect("Expected divisor on stack").i } == 0 {
+                        return Err(self.runtime_err_of_kind(ErrorKind::DivisionByZero, "Integer division by zero".to_string()));
+                    }
+                    self.binary_int_op(i64::div)
+                },
+                OpCode::IntMod => {
+                    if unsafe { self.stack.last().expect("Expected divisor on stack").i } == 0 {
+                        return Err(self.runtime_err_of_kind(ErrorKind::DivisionByZero, "Modulo by zero".to_string()));
+                    }
+                    self.binary_int_op(i64::rem_euclid)
+                },
                 OpCode::IntNegate => self.unary_int_op(i64::neg),
                 OpCode::To => {
                     let r = self.stack.pop().expect("Expected int on stack");
@@ -480,6 +1166,14 @@ impl VM {
                     let l = self.heap_stack.pop().expect("Expected array on stack");
                     self.stack.push(Value::from_bool(l != r));
                 },
+                OpCode::FloatArrEqual => {
+                    let equal = self.float_arrays_equal();
+                    self.stack.push(Value::from_bool(equal));
+                },
+                OpCode::FloatArrNotEqual => {
+                    let equal = self.float_arrays_equal();
+                    self.stack.push(Value::from_bool(!equal));
+                },
                 OpCode::Concat => {
                     let r = self.heap_stack.pop().expect("Expected array on heap stack");
                     let l = self.heap_stack.pop().expect("Expected array on stack");
@@ -561,17 +1255,29 @@ impl VM {
                     let arr = Rc::from(self.heap_stack.split_off(self.heap_stack.len() - n_elems as usize));
                     self.heap_stack.push(HeapValue::ArrayHeap(arr));
                 },
+                OpCode::ArrayRepeat => {
+                    let n_elems = self.read_u16();
+                    let value = self.stack.pop().expect("Attempted to repeat value with empty stack");
+                    self.heap_stack.push(HeapValue::Array(Rc::from(vec![value; n_elems as usize])));
+                },
+                OpCode::ArrayHeapRepeat => {
+                    let n_elems = self.read_u16();
+                    let value = self.heap_stack.pop().expect("Attempted to repeat value with empty stack");
+                    self.heap_stack.push(HeapValue::ArrayHeap(Rc::from(vec![value; n_elems as usize])));
+                },
 
                 OpCode::Collect => {
                     let mut iter = self.heap_stack.pop().expect("Attempted to collect with empty stack");
-                    match &mut iter {
-                        HeapValue::LazyIter(iter) => {
-                            self.heap_stack.push(HeapValue::Array(iter.into_array()));
-                        },
-                        HeapValue::LazyIterHeap(iter) => {
-                            self.heap_stack.push(HeapValue::ArrayHeap(iter.into_array()));
-                        },
+                    let arr = match (&mut iter, self.collect_limit) {
+                        (HeapValue::LazyIter(iter), None) => Some(HeapValue::Array(iter.into_array())),
+                        (HeapValue::LazyIter(iter), Some(limit)) => iter.collect_limited(limit).map(HeapValue::Array),
+                        (HeapValue::LazyIterHeap(iter), None) => Some(HeapValue::ArrayHeap(iter.into_array())),
+                        (HeapValue::LazyIterHeap(iter), Some(limit)) => iter.collect_limited(limit).map(HeapValue::ArrayHeap),
                         _ => unreachable!(),
+                    };
+                    match arr {
+                        Some(arr) => self.heap_stack.push(arr),
+                        None => return Err(self.runtime_err("Collected array exceeds size limit".to_string())),
                     }
                 }
                 
@@ -807,6 +1513,13 @@ impl VM {
                     }
                 },
 
+                OpCode::Scan => self.scan(false)?,
+                OpCode::ScanHeap => self.scan(true)?,
+                OpCode::ReduceWhile => self.reduce_while(false)?,
+                OpCode::ReduceWhileHeap => self.reduce_while(true)?,
+                OpCode::ReduceCount => self.reduce_count(false)?,
+                OpCode::ReduceCountHeap => self.reduce_count(true)?,
+
                 OpCode::Filter => {
                     let arr = self.heap_stack.last().expect("Expected array on top of stack").clone();
                     self.map()?;
@@ -843,6 +1556,45 @@ impl VM {
                     }
                 },
 
+                OpCode::CountWhere => {
+                    self.map()?;
+                    let bool_iter = match self.heap_stack.pop().expect("Expected bool iterator on stack after mapping through count_where function") {
+                        HeapValue::LazyIter(a) => a,
+                        _ => unreachable!(),
+                    };
+                    let count = bool_iter.into_iter().filter(|b| unsafe { b.b }).count();
+                    self.stack.push(Value::from_i64(count as i64));
+                },
+                OpCode::FindIndex => self.find_index()?,
+                OpCode::Inspect => {
+                    let iter_over = self.heap_stack.pop().expect("Expected array on heap stack");
+                    let callee = self.heap_stack.pop().expect("Expected callable on heap stack");
+                    let iter = match iter_over {
+                        HeapValue::LazyIter(i) => HeapValue::LazyIter(Box::new(InspectIter::new(i, callee, self))),
+                        HeapValue::Array(a) => HeapValue::LazyIter(Box::new(InspectIter::new(Box::new(ArrayIter::new(a)), callee, self))),
+                        HeapValue::LazyIterHeap(i) => HeapValue::LazyIterHeap(Box::new(InspectIter::new(i, callee, self))),
+                        HeapValue::ArrayHeap(a) => HeapValue::LazyIterHeap(Box::new(InspectIter::new(Box::new(ArrayIter::new(a)), callee, self))),
+                        _ => unreachable!(),
+                    };
+                    self.heap_stack.push(iter);
+                },
+                OpCode::FlatMap => {
+                    let arrays = self.flat_map_arrays()?;
+                    let iter = FlatMapIter::new(arrays, |arr| match arr {
+                        HeapValue::Array(a) => a,
+                        _ => unreachable!(),
+                    });
+                    self.heap_stack.push(HeapValue::LazyIter(Box::new(iter)));
+                },
+                OpCode::FlatMapHeap => {
+                    let arrays = self.flat_map_arrays()?;
+                    let iter = FlatMapIter::new(arrays, |arr| match arr {
+                        HeapValue::ArrayHeap(a) => a,
+                        _ => unreachable!(),
+                    });
+                    self.heap_stack.push(HeapValue::LazyIterHeap(Box::new(iter)));
+                },
+
                 OpCode::ZipMap => {
                     let f = self.heap_stack.pop().expect("Expected function on heap stack");
                     let n_iters = unsafe { self.stack.pop().expect("Expected number of arrays on stack").i };
@@ -886,14 +1638,174 @@ impl VM {
                     };
                     self.heap_stack.push(zip_iter);
                 },
+                OpCode::SetField => self.set_field(false)?,
+                OpCode::SetHeapField => self.set_field(true)?,
+                OpCode::FieldNames => {
+                    let names = match self.heap_stack.pop().expect("Expected object on heap stack") {
+                        HeapValue::Object(obj) => obj.typedef.field_names()
+                            .map(|name| HeapValue::String(Rc::new(name.to_string())))
+                            .collect(),
+                        _ => unreachable!(),
+                    };
+                    self.heap_stack.push(HeapValue::ArrayHeap(names));
+                },
+                OpCode::ZipLongest => {
+                    let f = self.heap_stack.pop().expect("Expected function on heap stack");
+                    let b = self.heap_stack.pop().expect("Expected array on heap stack");
+                    let a = self.heap_stack.pop().expect("Expected array on heap stack");
+                    // defaults are below the arrays, with the default for b on top
+                    // which stack each default is on depends on the type of its array
+                    let mut iters = Vec::new();
+                    let mut heap_iters = Vec::new();
+                    for hv in [b, a] {
+                        match hv {
+                            HeapValue::LazyIter(i) => iters.push((i, self.stack.pop().expect("Expected default on stack"))),
+                            HeapValue::Array(a) => iters.push((Box::new(ArrayIter::new(a)), self.stack.pop().expect("Expected default on stack"))),
+                            HeapValue::LazyIterHeap(i) => heap_iters.push((i, self.heap_stack.pop().expect("Expected default on heap stack"))),
+                            HeapValue::ArrayHeap(a) => heap_iters.push((Box::new(ArrayIter::new(a)), self.heap_stack.pop().expect("Expected default on heap stack"))),
+                            _ => unreachable!(),
+                        }
+                    }
+                    iters.reverse();
+                    heap_iters.reverse();
+                    let is_heap = match &f {
+                        HeapValue::Closure(c) => c.function.return_is_heap,
+                        HeapValue::NativeFunction(f) => f.return_is_heap,
+                        _ => unreachable!(),
+                    };
+                    let iter = Box::new(ZipLongestIter::new(iters, heap_iters, f, self));
+                    self.heap_stack.push(
+                        if is_heap {
+                            HeapValue::LazyIterHeap(iter)
+                        }
+                        else {
+                            HeapValue::LazyIter(iter)
+                        }
+                    );
+                },
+                OpCode::ZipPair => {
+                    let mut sides = [
+                        self.heap_stack.pop().expect("Expected array on heap stack"),
+                        self.heap_stack.pop().expect("Expected array on heap stack"),
+                    ].map(|hv| match hv {
+                        HeapValue::LazyIter(i) => ZipPairSide::Value(i),
+                        HeapValue::Array(a) => ZipPairSide::Value(Box::new(ArrayIter::new(a))),
+                        HeapValue::LazyIterHeap(i) => ZipPairSide::Heap(i),
+                        HeapValue::ArrayHeap(a) => ZipPairSide::Heap(Box::new(ArrayIter::new(a))),
+                        _ => unreachable!(),
+                    });
+                    sides.reverse();
+                    let [first, second] = sides;
+                    self.heap_stack.push(HeapValue::LazyIterHeap(Box::new(ZipPairIter::new(first, second))));
+                },
+                OpCode::Cache => {
+                    let iter = match self.heap_stack.pop().expect("Expected iterator on heap stack") {
+                        HeapValue::LazyIter(iter) => HeapValue::LazyIter(Box::new(CacheIter::new(iter))),
+                        HeapValue::LazyIterHeap(iter) => HeapValue::LazyIterHeap(Box::new(CacheIter::new(iter))),
+                        _ => unreachable!(),
+                    };
+                    self.heap_stack.push(iter);
+                },
+                OpCode::IndexIter => {
+                    self.heap_stack.push(HeapValue::LazyIter(Box::new(IndexZipIter::new())));
+                },
+                OpCode::MaxBy => {
+                    let key_type = KeyType::from(self.read_u8());
+                    self.extremum_by(key_type, true)?;
+                },
+                OpCode::MinBy => {
+                    let key_type = KeyType::from(self.read_u8());
+                    self.extremum_by(key_type, false)?;
+                },
+                OpCode::ChunkBy => {
+                    let key_type = KeyType::from(self.read_u8());
+                    self.chunk_by(key_type)?;
+                },
+                OpCode::ToJson => {
+                    let ip = unsafe { &mut (*self.frame_ptr()).ip };
+                    let typ = self.chunk().read_type(ip).clone();
+                    let value = if typ.is_heap() {
+                        ReturnValue::HeapValue(self.heap_stack.pop().expect("Expected value on heap stack"))
+                    }
+                    else {
+                        ReturnValue::Value(self.stack.pop().expect("Expected value on stack"))
+                    };
+                    let json = unpack_result(value, &typ).map_err(|e| self.runtime_err(e))?.to_json();
+                    self.heap_stack.push(HeapValue::String(Rc::new(json)));
+                },
+                OpCode::FromJson => {
+                    let ip = unsafe { &mut (*self.frame_ptr()).ip };
+                    let typ = self.chunk().read_type(ip).clone();
+                    let source = match self.heap_stack.pop() {
+                        Some(HeapValue::String(s)) => s,
+                        x => return Err(self.runtime_err(format!("Expected string on heap stack, got {:?}", x))),
+                    };
+                    self.heap_stack.push(from_json(&source, &typ));
+                },
+                OpCode::SumBy => {
+                    let key_type = KeyType::from(self.read_u8());
+                    self.sum_by(key_type)?;
+                },
+                OpCode::SortByKey => {
+                    let key_type = KeyType::from(self.read_u8());
+                    self.sort_by_key(key_type)?;
+                },
+                OpCode::SortBy => {
+                    self.sort_by()?;
+                },
             }
         }
     }
 
     pub fn interpret(&mut self, source: &str) -> Result<TaggedValue, InterpreterError> {
+        self.interpret_source(scanner::scan(source), None).map(|(value, _)| value)
+    }
+
+    // like `interpret`, but also returns the static type of the result
+    pub fn interpret_typed(&mut self, source: &str) -> Result<(TaggedValue, ast::Type), InterpreterError> {
+        self.interpret_source(scanner::scan(source), None)
+    }
+
+    // like `interpret`, but imports are resolved relative to the file's directory
+    pub fn interpret_file(&mut self, path: &str) -> Result<TaggedValue, InterpreterError> {
+        let source = std::fs::read_to_string(path).map_err(
+            |_| InterpreterError::CompileError(ErrorKind::Io, format!("Could not read file `{}`", path))
+        )?;
+        self.interpret_source(scanner::scan(&source), Some(Path::new(path))).map(|(value, _)| value)
+    }
+
+    // type-check a file without running it, returning the name and type (or type error) of each top-level binding
+    pub fn check_file(&self, path: &str) -> Result<compiler::BindingTypes, InterpreterError> {
+        let source = std::fs::read_to_string(path).map_err(
+            |_| InterpreterError::CompileError(ErrorKind::Io, format!("Could not read file `{}`", path))
+        )?;
+        compiler::check(&source, Some(Path::new(path)), self.typecontext.clone())
+            .map_err(|(kind, e)| InterpreterError::CompileError(kind, e))
+    }
+
+    // compile a program without running it, and get the name and the expanded name (e.g., `sum` and `sum[Iter(Int)]`)
+    // of each reference to an overloaded or templated function, to see which overload was selected
+    pub fn resolved_names(&self, source: &str) -> Result<Vec<(String, String)>, InterpreterError> {
+        compiler::resolved_names(source, None, self.typecontext.clone())
+            .map_err(|(kind, e)| InterpreterError::CompileError(kind, e))
+    }
+
+    // compile a program without running it, and get a description of each instruction in the compiled top-level code,
+    // e.g. `0000 Constant 0x3`, to see what the compiler emits
+    pub fn instructions(&self, source: &str) -> Result<Vec<String>, InterpreterError> {
+        compiler::instructions(source, None, self.typecontext.clone())
+            .map_err(|(kind, e)| InterpreterError::CompileError(kind, e))
+    }
+
+    // like `interpret`, but skips the scanner, for front-ends that generate tokens directly
+    pub fn interpret_tokens(&mut self, tokens: Vec<Token>) -> Result<TaggedValue, InterpreterError> {
+        self.interpret_source(tokens, None).map(|(value, _)| value)
+    }
+
+    fn interpret_source(&mut self, tokens: Vec<Token>, path: Option<&Path>) -> Result<(TaggedValue, ast::Type), InterpreterError> {
         let (function, return_type) = 
-            compiler::compile(source, self.typecontext.clone())
-            .map_err(|e| InterpreterError::CompileError(e))?
+            compiler::compile(tokens, path, self.typecontext.clone())
+            .map_err(|(kind, e)| InterpreterError::CompileError(kind, e))?
             ;
         let function = Rc::new(function);
         self.init(function);
@@ -909,7 +1821,31 @@ impl VM {
         else {
             ReturnValue::Value(self.stack.pop().unwrap())
         };
-        unpack_result(result, &return_type).map_err(|e| InterpreterError::RuntimeError(e))
+        let value = unpack_result(result, &return_type).map_err(|e| InterpreterError::RuntimeError(ErrorKind::Other, e, vec![]))?;
+        Ok((value, return_type))
+    }
+}
+
+fn chunk_by_iter<T: std::fmt::Debug + Clone + 'static>(
+    iter: Box<dyn LazyIter<T>>,
+    f: HeapValue,
+    vm: *mut VM,
+    key_type: KeyType,
+    push: fn(&mut VM, T),
+    wrap: fn(Rc<[T]>) -> HeapValue,
+) -> Box<dyn LazyIter<HeapValue>> {
+    let pop_value = |vm: &mut VM| vm.stack.pop().expect("Expected key on stack");
+    match key_type {
+        KeyType::Int => Box::new(ChunkByIter::new(
+            iter, f, vm, push, pop_value, |a, b| unsafe { a.i == b.i }, wrap
+        )),
+        KeyType::Bool => Box::new(ChunkByIter::new(
+            iter, f, vm, push, pop_value, |a, b| unsafe { a.b == b.b }, wrap
+        )),
+        KeyType::Str => Box::new(ChunkByIter::new(
+            iter, f, vm, push, |vm| vm.heap_stack.pop().expect("Expected key on heap stack"), |a, b| a == b, wrap
+        )),
+        _ => unreachable!(),
     }
 }
 
@@ -1004,4 +1940,14 @@ fn unpack_heapvalue(hvalue: HeapValue, return_type: &ast::Type) -> Result<Tagged
             )
         ),
     }
-}
\ No newline at end of file
+}
+
+// scramble a seed (with splitmix64) into a state for the random number generator,
+// so that similar seeds give unrelated sequences and the state is never zero
+fn seed_state(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+    if z == 0 { 1 } else { z }
+}
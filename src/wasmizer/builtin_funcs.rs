@@ -133,6 +133,24 @@ impl BuiltinFunc {
         self.write_opcode(Opcode::I64Add);
     }
 
+    // computes the absolute value of the i32 x, using mask as scratch space
+    // there is no I32Abs opcode. Instead we'll calculate mask = x >> 31, then (x ^ mask) - mask
+    pub fn write_abs_i32(&mut self, x_name: &str, mask_name: &str) {
+        self.write_opcode(Opcode::LocalGet);
+        self.write_var(x_name);
+        self.write_opcode(Opcode::I32Const);
+        self.write_byte(31);
+        self.write_opcode(Opcode::I32ShrS);
+        self.write_opcode(Opcode::LocalTee);
+        self.write_var(mask_name);
+        self.write_opcode(Opcode::LocalGet);
+        self.write_var(x_name);
+        self.write_opcode(Opcode::I32Xor);
+        self.write_opcode(Opcode::LocalGet);
+        self.write_var(mask_name);
+        self.write_opcode(Opcode::I32Sub);
+    }
+
     // copy size bytes from offset to memptr
     // sets offset to value of memptr, then increments memptr by size
     pub fn copy_mem(&mut self, offset_name: &str, size_name: &str) {
@@ -205,6 +223,19 @@ impl BuiltinFunc {
         self.write_byte(0x00); // table index
     }
 
+    // Copies the current value of the inner iterator to the current value of the outer iterator
+    // inner_offset should already be set, e.g. by iter_call_advance_on_inner
+    pub fn iter_copy_inner_current(&mut self, offset_name: &str, inner_offset_name: &str, inner_type: Numtype) {
+        self.write_opcode(Opcode::LocalGet);
+        self.write_var(offset_name);
+        self.write_opcode(Opcode::LocalGet);
+        self.write_var(inner_offset_name);
+        self.write_opcode(inner_type.load_op());
+        self.write_slice(&[0x02, 0x00]);
+        self.write_opcode(inner_type.store_op());
+        self.write_slice(&[0x02, 0x00]);
+    }
+
     // Used to call the advance function on an iterator
     pub fn iter_call_advance(
         &mut self,
@@ -300,7 +331,14 @@ lazy_static! {
             func.write_opcode(Opcode::I32DivU);
             func.write_opcode(Opcode::MemoryGrow);
             func.write_byte(0x00);
-            func.write_opcode(Opcode::Drop);  // TODO: Handle when grow fails (= -1)
+            // trap if grow fails (= -1)
+            func.write_opcode(Opcode::I32Const);
+            func.write_slice(&signed_leb128(-1));
+            func.write_opcode(Opcode::I32Eq);
+            func.write_opcode(Opcode::If);
+            func.write_byte(Numtype::Void as u8);
+            func.write_opcode(Opcode::Unreachable);
+            func.write_opcode(Opcode::End);  // end if
             func.write_opcode(Opcode::End);  // end if
 
             // return start of the new memory chunk
@@ -377,102 +415,7 @@ lazy_static! {
         };
         map.insert("concat_heap_objs".to_string(), concat_heap_objs);
 
-        let heap_objs_equal = {
-            let mut func = BuiltinFunc::new(
-                FuncTypeSignature::new(vec![Numtype::I64, Numtype::I64], Some(Numtype::I32)),
-                vec!["fatptr1".to_string(), "fatptr2".to_string()]
-            );
-
-            func.add_local("offset1", Numtype::I32);
-            func.add_local("size1", Numtype::I32);
-            func.add_local("offset2", Numtype::I32);
-            func.add_local("size2", Numtype::I32);
-
-            func.set_offset_and_size("fatptr1", "offset1", "size1");
-            func.set_offset_and_size("fatptr2", "offset2", "size2");
-
-            // check if sizes are equal
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("size1");
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("size2");
-            func.write_opcode(Opcode::I32Eq);
-            func.write_opcode(Opcode::If);
-            func.write_byte(Numtype::I32 as u8);  // will be 1 if equal, 0 if not
-
-            // case if sizes are equal
-            // loop through all values and check if they are equal
-            // <inner_offset> is used to store the index of the current value within the loop
-            func.add_local("inner_offset", Numtype::I32);
-            func.write_opcode(Opcode::I32Const);
-            func.write_byte(0x00);
-            func.write_opcode(Opcode::LocalSet);
-            func.write_var("inner_offset");
-            // <equal> is used to store whether the values are equal, initialized to 1
-            func.add_local("equal", Numtype::I32);
-            func.write_opcode(Opcode::I32Const);
-            func.write_byte(0x01);
-            func.write_opcode(Opcode::LocalSet);
-            func.write_var("equal");
-            func.write_opcode(Opcode::Loop);
-            func.write_byte(Numtype::Void as u8);
-            // read value from memory at offset1 + inner_offset
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("inner_offset");
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("offset1");
-            func.write_opcode(Opcode::I32Add);
-            func.write_opcode(Opcode::I32Load);
-            func.write_byte(0x02);  // alignment
-            func.write_byte(0x00);  // load offset
-            // read value from memory at offset2 + inner_offset
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("inner_offset");
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("offset2");
-            func.write_opcode(Opcode::I32Add);
-            func.write_opcode(Opcode::I32Load);
-            func.write_byte(0x02);  // alignment
-            func.write_byte(0x00);  // load offset
-            // compare values, update equal, keep that value on stack
-            func.write_opcode(Opcode::I32Eq);
-            func.write_opcode(Opcode::LocalTee);
-            func.write_var("equal");
-            // add 4 to inner_offset
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("inner_offset");
-            func.write_opcode(Opcode::I32Const);
-            func.write_byte(0x04);
-            func.write_opcode(Opcode::I32Add);
-            func.write_opcode(Opcode::LocalTee);
-            func.write_var("inner_offset");
-            // check if inner_offset < size1
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("size1");
-            func.write_opcode(Opcode::I32LtU);
-            // continue if inner_offset < size1 AND equal == 1
-            func.write_opcode(Opcode::I32And);
-            func.write_opcode(Opcode::BrIf);
-            func.write_byte(0x00);  // break depth
-            func.write_opcode(Opcode::End); // end loop
-            // return <equal>
-            func.write_opcode(Opcode::LocalGet);
-            func.write_var("equal");
-
-
-            func.write_opcode(Opcode::Else);
-            // case if sizes are not equal
-            func.write_opcode(Opcode::I32Const);
-            func.write_byte(0);
-
-
-            func.write_opcode(Opcode::End); // end if
-
-            func.write_opcode(Opcode::End); // end function
-
-            func
-        };
-        map.insert("heap_objs_equal".to_string(), heap_objs_equal);
+        map.insert("heap_objs_equal".to_string(), define_builtin_heap_objs_equal(Numtype::I32));
 
         let get_i32_field = {
             let mut func = BuiltinFunc::new(
@@ -574,33 +517,91 @@ pub fn define_builtin_abs_int() -> BuiltinFunc {
         vec!["x".to_string()],
     );
     func.add_local("mask", Numtype::I32);
+    func.write_abs_i32("x", "mask");
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_abs_float() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::F32)),
+        vec!["x".to_string()],
+    );
     func.write_opcode(Opcode::LocalGet);
     func.write_var("x");
-    // there is no I32Abs opcode. Instead we'll calculate mask = x >> 31, then (x ^ mask) - mask
-    func.write_opcode(Opcode::I32Const);
-    func.write_byte(31);
-    func.write_opcode(Opcode::I32ShrS);
-    func.write_opcode(Opcode::LocalTee);
-    func.write_var("mask");
+    func.write_opcode(Opcode::F32Abs);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_abs_diff_int() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32, Numtype::I32], Some(Numtype::I32)),
+        vec!["a".to_string(), "b".to_string()],
+    );
+    func.add_local("diff", Numtype::I32);
+    func.add_local("mask", Numtype::I32);
     func.write_opcode(Opcode::LocalGet);
-    func.write_var("x");
-    func.write_opcode(Opcode::I32Xor);
+    func.write_var("a");
     func.write_opcode(Opcode::LocalGet);
-    func.write_var("mask");
+    func.write_var("b");
     func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("diff");
+    func.write_abs_i32("diff", "mask");
     func.write_opcode(Opcode::End);
 
     func
 }
 
-pub fn define_builtin_abs_float() -> BuiltinFunc {
+pub fn define_builtin_abs_diff_float() -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
-        FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::F32)),
+        FuncTypeSignature::new(vec![Numtype::F32, Numtype::F32], Some(Numtype::F32)),
+        vec!["a".to_string(), "b".to_string()],
+    );
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("a");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("b");
+    func.write_opcode(Opcode::F32Sub);
+    func.write_opcode(Opcode::F32Abs);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_is_finite() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::I32)),
+        vec!["x".to_string()],
+    );
+    // x - x is 0 for finite x, and NaN for infinite or NaN x
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::F32Sub);
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&0f32.to_le_bytes());
+    func.write_opcode(Opcode::F32Eq);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_is_inf() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::I32)),
         vec!["x".to_string()],
     );
     func.write_opcode(Opcode::LocalGet);
     func.write_var("x");
     func.write_opcode(Opcode::F32Abs);
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&f32::INFINITY.to_le_bytes());
+    func.write_opcode(Opcode::F32Eq);
     func.write_opcode(Opcode::End);
 
     func
@@ -632,6 +633,35 @@ pub fn define_builtin_ftoi() -> BuiltinFunc {
     func
 }
 
+// Bool and Int are both represented as I32, so this just passes the value through
+pub fn define_builtin_btoi() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32], Some(Numtype::I32)),
+        vec!["x".to_string()],
+    );
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_itob() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32], Some(Numtype::I32)),
+        vec!["x".to_string()],
+    );
+    // normalize to 0 or 1
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32Ne);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
 pub fn define_builtin_sqrt_float() -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
         FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::F32)),
@@ -645,66 +675,279 @@ pub fn define_builtin_sqrt_float() -> BuiltinFunc {
     func
 }
 
+pub fn define_builtin_clamp01() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::F32], Some(Numtype::F32)),
+        vec!["x".to_string()],
+    );
+    // max(min(x, 1.0), 0.0), which keeps NaN as NaN
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&1f32.to_le_bytes());
+    func.write_opcode(Opcode::F32Min);
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&0f32.to_le_bytes());
+    func.write_opcode(Opcode::F32Max);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_lerp() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::F32, Numtype::F32, Numtype::F32], Some(Numtype::F32)),
+        vec!["a".to_string(), "b".to_string(), "t".to_string()],
+    );
+    // a + (b - a) * t
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("a");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("b");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("a");
+    func.write_opcode(Opcode::F32Sub);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("t");
+    func.write_opcode(Opcode::F32Mul);
+    func.write_opcode(Opcode::F32Add);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_popcount() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32], Some(Numtype::I32)),
+        vec!["x".to_string()],
+    );
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::I32Popcnt);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+// for bit operations that map directly to a single i32 instruction, e.g. I32Shl or I32And
+pub fn define_builtin_int_bitop(op: Opcode) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32, Numtype::I32], Some(Numtype::I32)),
+        vec!["x".to_string(), "y".to_string()],
+    );
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("x");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("y");
+    func.write_opcode(op);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
 pub fn define_builtin_mod() -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
         FuncTypeSignature::new(vec![Numtype::I32, Numtype::I32], Some(Numtype::I32)),
         vec!["x".to_string(), "y".to_string()],
     );
-    // Do a few extra steps, since we want the answer to always be positive
-    // mod(x, y) = ((x % y) + y) % y
+    func.add_local("r", Numtype::I32);
+    func.add_local("mask", Numtype::I32);
+    // Do a few extra steps, since we want the answer to always be positive, even for negative y (like i64::rem_euclid)
+    // r = x % y, then mod(x, y) = r + (abs(y) if r < 0 else 0)
     func.write_opcode(Opcode::LocalGet);
     func.write_var("x");
     func.write_opcode(Opcode::LocalGet);
     func.write_var("y");
     func.write_opcode(Opcode::I32RemS);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("r");
+    // abs(y) = (y ^ mask) - mask, where mask = y >> 31
     func.write_opcode(Opcode::LocalGet);
     func.write_var("y");
-    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(31);
+    func.write_opcode(Opcode::I32ShrS);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("mask");
     func.write_opcode(Opcode::LocalGet);
     func.write_var("y");
-    func.write_opcode(Opcode::I32RemS);
+    func.write_opcode(Opcode::I32Xor);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("mask");
+    func.write_opcode(Opcode::I32Sub);
+    // r >> 31 is all ones if r < 0, and zero otherwise
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("r");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(31);
+    func.write_opcode(Opcode::I32ShrS);
+    func.write_opcode(Opcode::I32And);
+    func.write_opcode(Opcode::I32Add);
     func.write_opcode(Opcode::End);
 
     func
 }
 
-pub fn define_builtin_reduce_iter(
-    numtype: Numtype,
-    operation: &str,
-    advance_fn_type_idx: u32,
-) -> BuiltinFunc {
+pub fn define_builtin_fpow_int() -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
-        FuncTypeSignature::new(vec![Numtype::I64], Some(numtype)),
-        vec!["iter_fatptr".to_string()],
+        FuncTypeSignature::new(vec![Numtype::F32, Numtype::I32], Some(Numtype::F32)),
+        vec!["base".to_string(), "exp".to_string()],
     );
-    func.add_local("iter_offset", Numtype::I32);
-    func.add_local("total", numtype);
+    func.add_local("result", Numtype::F32);
+    func.add_local("n", Numtype::I32);
 
-    // if this is a product, we need to initialize the total to 1
-    if operation == "prod" || operation == "all" {
-        func.write_opcode(numtype.const_op());
-        match numtype {
-            Numtype::F32 => func.write_slice(&[0x00, 0x00, 0x80, 0x3F]),
-            _ => func.write_byte(1),
-        }
-        func.write_opcode(Opcode::LocalSet);
-        func.write_var("total");
-    }
+    // exponentiation by squaring, so we don't need to import powf from the host
+    // result = 1.0
+    // n = abs(exp)
+    // loop:
+    //   if n == 0: break
+    //   if n & 1: result *= base
+    //   base *= base
+    //   n >>= 1
+    //   branch to loop
+    // if exp < 0: result = 1.0 / result
 
-    // iter_offset = iter_fatpr >> 32
-    func.write_opcode(Opcode::LocalGet);
-    func.write_var("iter_fatptr");
-    func.write_opcode(Opcode::I64Const);
-    func.write_byte(0x20);
-    func.write_opcode(Opcode::I64ShrU);
-    func.write_opcode(Opcode::I32WrapI64);
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&1f32.to_le_bytes());
     func.write_opcode(Opcode::LocalSet);
-    func.write_var("iter_offset");
+    func.write_var("result");
 
-    func.write_opcode(Opcode::Loop);
-    func.write_byte(Numtype::Void as u8);
-
-    // call iterator->advance
+    // n = if exp < 0 { -exp } else { exp }
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("exp");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32LtS);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("exp");
+    func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::Else);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("exp");
+    func.write_opcode(Opcode::End);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("n");
+
+    func.write_opcode(Opcode::Block);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    // if n == 0: break
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32Eqz);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01);
+
+    // if n & 1: result *= base
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::I32And);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("result");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("base");
+    func.write_opcode(Opcode::F32Mul);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("result");
+    func.write_opcode(Opcode::End);
+
+    // base *= base
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("base");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("base");
+    func.write_opcode(Opcode::F32Mul);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("base");
+
+    // n >>= 1
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::I32ShrU);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("n");
+
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x00); // branch to loop
+
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end block
+
+    // if exp < 0: return 1.0 / result
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("exp");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32LtS);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::F32 as u8);
+    func.write_opcode(Opcode::F32Const);
+    func.write_slice(&1f32.to_le_bytes());
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("result");
+    func.write_opcode(Opcode::F32Div);
+    func.write_opcode(Opcode::Else);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("result");
+    func.write_opcode(Opcode::End);
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+// Int sums are accumulated in an I64, like in the VM, then narrowed back to an Int (I32) at the end;
+// a total that doesn't fit in an Int traps with an integer overflow instead of silently wrapping
+pub fn define_builtin_reduce_iter(
+    numtype: Numtype,
+    operation: &str,
+    advance_fn_type_idx: u32,
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I64], Some(numtype)),
+        vec!["iter_fatptr".to_string()],
+    );
+    let wide_sum = numtype == Numtype::I32 && operation == "sum";
+    func.add_local("iter_offset", Numtype::I32);
+    func.add_local("total", if wide_sum { Numtype::I64 } else { numtype });
+
+    // if this is a product, we need to initialize the total to 1
+    if operation == "prod" || operation == "all" {
+        func.write_opcode(numtype.const_op());
+        match numtype {
+            Numtype::F32 => func.write_slice(&[0x00, 0x00, 0x80, 0x3F]),
+            _ => func.write_byte(1),
+        }
+        func.write_opcode(Opcode::LocalSet);
+        func.write_var("total");
+    }
+
+    // iter_offset = iter_fatpr >> 32
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("iter_fatptr");
+    func.write_opcode(Opcode::I64Const);
+    func.write_byte(0x20);
+    func.write_opcode(Opcode::I64ShrU);
+    func.write_opcode(Opcode::I32WrapI64);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("iter_offset");
+
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    // call iterator->advance
     let advance_fn_delta = numtype.size();
     func.iter_call_advance("iter_offset", advance_fn_delta, advance_fn_type_idx);
 
@@ -718,10 +961,13 @@ pub fn define_builtin_reduce_iter(
     func.write_opcode(numtype.load_op());
     func.write_byte(0x02); // alignment
     func.write_byte(0x00); // load offset
+    if wide_sum {
+        func.write_opcode(Opcode::I64ExtendI32S);
+    }
     func.write_opcode(Opcode::LocalGet);
     func.write_var("total");
     func.write_opcode(match (numtype, operation) {
-        (Numtype::I32, "sum") => Opcode::I32Add,
+        (Numtype::I32, "sum") => Opcode::I64Add,
         (Numtype::F32, "sum") => Opcode::F32Add,
         (Numtype::I32, "prod") => Opcode::I32Mul,
         (Numtype::F32, "prod") => Opcode::F32Mul,
@@ -762,9 +1008,138 @@ pub fn define_builtin_reduce_iter(
 
     func.write_opcode(Opcode::End); // end loop
 
+    if wide_sum {
+        // if total != i64(i32(total)), trap by dividing i32::MIN by -1, which overflows
+        func.write_opcode(Opcode::LocalGet);
+        func.write_var("total");
+        func.write_opcode(Opcode::I32WrapI64);
+        func.write_opcode(Opcode::I64ExtendI32S);
+        func.write_opcode(Opcode::LocalGet);
+        func.write_var("total");
+        func.write_opcode(Opcode::I64Ne);
+        func.write_opcode(Opcode::If);
+        func.write_byte(Numtype::Void as u8);
+        func.write_opcode(Opcode::I32Const);
+        func.write_slice(&signed_leb128(i32::MIN));
+        func.write_opcode(Opcode::I32Const);
+        func.write_slice(&signed_leb128(-1));
+        func.write_opcode(Opcode::I32DivS);
+        func.write_opcode(Opcode::Drop);
+        func.write_opcode(Opcode::End); // end if
+    }
+
     // return total
     func.write_opcode(Opcode::LocalGet);
     func.write_var("total");
+    if wide_sum {
+        func.write_opcode(Opcode::I32WrapI64);
+    }
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+// compares two heap objects (e.g. arrays, strings) four bytes at a time
+// with an I32 elem_type the comparison is bitwise, which works for everything except floats,
+// which need F32Eq so that 0.0 = -0.0 and NaN != NaN
+pub fn define_builtin_heap_objs_equal(elem_type: Numtype) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I64, Numtype::I64], Some(Numtype::I32)),
+        vec!["fatptr1".to_string(), "fatptr2".to_string()]
+    );
+
+    func.add_local("offset1", Numtype::I32);
+    func.add_local("size1", Numtype::I32);
+    func.add_local("offset2", Numtype::I32);
+    func.add_local("size2", Numtype::I32);
+
+    func.set_offset_and_size("fatptr1", "offset1", "size1");
+    func.set_offset_and_size("fatptr2", "offset2", "size2");
+
+    // check if sizes are equal
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("size1");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("size2");
+    func.write_opcode(Opcode::I32Eq);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);  // will be 1 if equal, 0 if not
+
+    // case if sizes are equal
+    // loop through all values and check if they are equal
+    // <inner_offset> is used to store the index of the current value within the loop
+    func.add_local("inner_offset", Numtype::I32);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("inner_offset");
+    // <equal> is used to store whether the values are equal, initialized to 1
+    func.add_local("equal", Numtype::I32);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("equal");
+    // empty objects are equal, and have nothing to read
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("size1");
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+    // read value from memory at offset1 + inner_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset1");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(elem_type.load_op());
+    func.write_byte(0x02);  // alignment
+    func.write_byte(0x00);  // load offset
+    // read value from memory at offset2 + inner_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset2");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(elem_type.load_op());
+    func.write_byte(0x02);  // alignment
+    func.write_byte(0x00);  // load offset
+    // compare values, update equal, keep that value on stack
+    func.write_opcode(match elem_type {
+        Numtype::F32 => Opcode::F32Eq,
+        _ => Opcode::I32Eq,
+    });
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("equal");
+    // add 4 to inner_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x04);
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("inner_offset");
+    // check if inner_offset < size1
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("size1");
+    func.write_opcode(Opcode::I32LtU);
+    // continue if inner_offset < size1 AND equal == 1
+    func.write_opcode(Opcode::I32And);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x00);  // break depth
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end if
+    // return <equal>
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("equal");
+
+    func.write_opcode(Opcode::Else);
+    // case if sizes are not equal
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0);
+
+    func.write_opcode(Opcode::End); // end if
 
     func.write_opcode(Opcode::End); // end function
 
@@ -971,6 +1346,20 @@ pub fn define_builtin_str_len() -> BuiltinFunc {
     func
 }
 
+pub fn define_builtin_byte_len() -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I64], Some(Numtype::I32)),
+        vec!["str_fatptr".to_string()],
+    );
+    // the size in the low 32 bits of a string's fatptr is already its length in bytes
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("str_fatptr");
+    func.write_opcode(Opcode::I32WrapI64);
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
 pub fn define_builtin_unwrap(numtype: Numtype) -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
         FuncTypeSignature::new(vec![Numtype::I64, numtype], Some(numtype)),
@@ -1194,6 +1583,7 @@ pub fn define_builtin_range_iter_factory(
     );
     func.add_local("step", Numtype::I32);
     // step = 1 if stop > start else -1
+    // when start == stop, the direction doesn't matter: the one value is yielded before current reaches stop + step
     func.write_opcode(Opcode::LocalGet);
     func.write_var("start");
     func.write_opcode(Opcode::LocalGet);
@@ -1234,64 +1624,300 @@ pub fn define_builtin_range_iter_factory(
     func
 }
 
-pub fn define_builtin_map_iter_advance(
-    in_type: Numtype,
-    out_type: Numtype,
+// advance function for `take`: reports done once `count` elements have been yielded, without advancing the inner iterator further
+pub fn define_builtin_take_iter_advance(
+    inner_type: Numtype,
+    count_delta: u32,
     inner_offset_delta: u32,
-    map_fn_delta: u32,
     advance_fn_type_idx: u32,
-    map_fn_type_idx: u32,
 ) -> BuiltinFunc {
-    // function will take the offset of the start of a range struct, update the "current" field, and return a bool (1 if the iterator is done)
     let mut func = BuiltinFunc::advance_fn_template();
-    func.add_local("inner_offset", Numtype::I32); // will store the offset of the inner iterator in memory
-    func.add_local("current", out_type); // will store the current value of the map iterator
+    func.add_local("inner_offset", Numtype::I32);
+    func.add_local("count", Numtype::I32);
 
-    // call `advance` on the inner iterator
+    // if count == 0, return 1 (done)
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(count_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Load);
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Eqz);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+
+    func.write_opcode(Opcode::Else);
+
+    // count -= 1
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(count_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::I32Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    // otherwise, this is done when the inner iterator is done
     func.iter_call_advance_on_inner(
         "offset",
         "inner_offset",
         inner_offset_delta,
-        in_type,
+        inner_type,
         advance_fn_type_idx,
     );
-
-    // if done, we can just return that here
     func.write_opcode(Opcode::If);
     func.write_byte(Numtype::I32 as u8);
 
     func.write_opcode(Opcode::I32Const);
-    func.write_byte(1); // done = 1
+    func.write_byte(0x01);
 
-    // otherwise, we need to get the current value from the inner iterator
-    // and map through function
     func.write_opcode(Opcode::Else);
+    func.iter_copy_inner_current("offset", "inner_offset", inner_type);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::End); // end inner if
 
-    let load_op = in_type.load_op();
-    // now get inner_current
-    func.write_opcode(Opcode::LocalGet);
-    func.write_var("inner_offset");
-    func.write_opcode(load_op);
-    func.write_slice(&[0x02, 0x00]);
-
-    // pass inner_current to mapping fn
-    func.iter_call_map_fn("offset", map_fn_delta, map_fn_type_idx);
+    func.write_opcode(Opcode::End); // end outer if
 
-    // // When the current value is an I64 (heap object), we need to also copy the memory for that value
-    // if in_type == Numtype::I64 {
-    //     func.write_opcode(Opcode::Call);
-    //     let copy_heap_obj_idx = self.builtins.get("copy_heap_obj").unwrap();
-    //     func.write_slice(&unsigned_leb128(*copy_heap_obj_idx));
-    // }
+    func.write_opcode(Opcode::End); // end function
 
-    // set as new current value
-    func.write_opcode(Opcode::LocalSet);
-    func.write_var("current");
+    func
+}
 
-    func.write_opcode(Opcode::LocalGet);
-    func.write_var("offset");
-    func.write_opcode(Opcode::LocalGet);
-    func.write_var("current");
+// advance function for `drop`: on the first call, advances the inner iterator `count` extra times to skip its first elements
+pub fn define_builtin_drop_iter_advance(
+    inner_type: Numtype,
+    count_delta: u32,
+    inner_offset_delta: u32,
+    advance_fn_type_idx: u32,
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::advance_fn_template();
+    func.add_local("inner_offset", Numtype::I32);
+    func.add_local("count", Numtype::I32);
+
+    // loop:
+    // if count == 0: break
+    // count -= 1
+    // if advance(inner) is done: return 1
+    // branch to loop
+
+    func.write_opcode(Opcode::Block);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(count_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Load);
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Eqz);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01); // break out of block
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(count_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::I32Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        inner_type,
+        advance_fn_type_idx,
+    );
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+    func.write_opcode(Opcode::Return);
+    func.write_opcode(Opcode::End);
+
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x00); // branch to loop
+
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end block
+
+    // with everything skipped, this is done when the inner iterator is done
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        inner_type,
+        advance_fn_type_idx,
+    );
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+
+    func.write_opcode(Opcode::Else);
+    func.iter_copy_inner_current("offset", "inner_offset", inner_type);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::End); // end if
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+// builds a take or drop iterator from an iterator and a count, trapping if the count is negative
+pub fn define_builtin_counted_iter_factory(
+    out_type: Numtype,
+    constructor_idx: u32,
+    advance_fn_table_idx: u32,
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I64, Numtype::I32], Some(Numtype::I64)),
+        vec!["iter_over".to_string(), "count".to_string()],
+    );
+
+    // trap if count < 0
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32LtS);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Unreachable);
+    func.write_opcode(Opcode::End);
+
+    // pass values to constructor
+    // current can just be set to an arbitrary value, since its initial state doesn't matter
+    func.write_opcode(out_type.const_op());
+    match out_type {
+        Numtype::F32 => func.write_slice(&[0x00, 0x00, 0x00, 0x00]),
+        _ => func.write_byte(0x00),
+    };
+    // advance_fn
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(advance_fn_table_idx));
+    // count
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    // iter_offset = iter_over >> 32
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("iter_over");
+    func.write_opcode(Opcode::I64Const);
+    func.write_byte(0x20);
+    func.write_opcode(Opcode::I64ShrU);
+    func.write_opcode(Opcode::I32WrapI64);
+
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(constructor_idx));
+
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+// take or drop over an array: converts the array to an iterator, then passes it to the take or drop factory
+pub fn define_builtin_counted_array_iter(array_iter_factory_idx: u32, counted_iter_factory_idx: u32) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I64, Numtype::I32], Some(Numtype::I64)),
+        vec!["arr".to_string(), "count".to_string()],
+    );
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("arr");
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(array_iter_factory_idx));
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(counted_iter_factory_idx));
+
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
+pub fn define_builtin_map_iter_advance(
+    in_type: Numtype,
+    out_type: Numtype,
+    inner_offset_delta: u32,
+    map_fn_delta: u32,
+    advance_fn_type_idx: u32,
+    map_fn_type_idx: u32,
+) -> BuiltinFunc {
+    // function will take the offset of the start of a range struct, update the "current" field, and return a bool (1 if the iterator is done)
+    let mut func = BuiltinFunc::advance_fn_template();
+    func.add_local("inner_offset", Numtype::I32); // will store the offset of the inner iterator in memory
+    func.add_local("current", out_type); // will store the current value of the map iterator
+
+    // call `advance` on the inner iterator
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        in_type,
+        advance_fn_type_idx,
+    );
+
+    // if done, we can just return that here
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(1); // done = 1
+
+    // otherwise, we need to get the current value from the inner iterator
+    // and map through function
+    func.write_opcode(Opcode::Else);
+
+    let load_op = in_type.load_op();
+    // now get inner_current
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(load_op);
+    func.write_slice(&[0x02, 0x00]);
+
+    // pass inner_current to mapping fn
+    func.iter_call_map_fn("offset", map_fn_delta, map_fn_type_idx);
+
+    // // When the current value is an I64 (heap object), we need to also copy the memory for that value
+    // if in_type == Numtype::I64 {
+    //     func.write_opcode(Opcode::Call);
+    //     let copy_heap_obj_idx = self.builtins.get("copy_heap_obj").unwrap();
+    //     func.write_slice(&unsigned_leb128(*copy_heap_obj_idx));
+    // }
+
+    // set as new current value
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("current");
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("current");
     func.write_opcode(out_type.store_op());
     func.write_slice(&[0x02, 0x00]);
 
@@ -1345,6 +1971,251 @@ pub fn define_builtin_map_iter_factory(
     func
 }
 
+// like the map iterator's advance function, but computes the mapped value with the given inlined function body,
+// which expects its argument in local 1
+pub fn define_builtin_inline_map_iter_advance(
+    in_type: Numtype,
+    out_type: Numtype,
+    inner_offset_delta: u32,
+    advance_fn_type_idx: u32,
+    body: &[u8],
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::advance_fn_template();
+    func.add_local("inner_current", in_type); // must be the first local, since the inlined body refers to it
+    func.add_local("inner_offset", Numtype::I32);
+    func.add_local("current", out_type);
+
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        in_type,
+        advance_fn_type_idx,
+    );
+
+    // if done, we can just return that here
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(1); // done = 1
+
+    func.write_opcode(Opcode::Else);
+
+    // get inner_current and evaluate the inlined body on it
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(in_type.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("inner_current");
+    func.write_slice(body);
+
+    // set as new current value
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("current");
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("current");
+    func.write_opcode(out_type.store_op());
+    func.write_slice(&[0x02, 0x00]);
+
+    // return 0 (for not done)
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0);
+
+    func.write_opcode(Opcode::End); // end if
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn define_builtin_flat_map_iter_advance(
+    in_type: Numtype,
+    out_type: Numtype,
+    inner_offset_delta: u32,
+    map_fn_delta: u32,
+    array_offset_delta: u32,
+    array_end_delta: u32,
+    advance_fn_type_idx: u32,
+    map_fn_type_idx: u32,
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::advance_fn_template();
+    func.add_local("inner_offset", Numtype::I32);
+    func.add_local("array_offset", Numtype::I32);
+    func.add_local("array_size", Numtype::I32);
+    func.add_local("array_fatptr", Numtype::I64);
+
+    // loop until we find an array element to yield, or the inner iterator runs out
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::I32 as u8);
+
+    // check if there are elements left in the current array
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(array_offset_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Load);
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(array_end_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Load);
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::I32LtU);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    // if so, set current to the next element
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_offset");
+    func.write_opcode(out_type.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(out_type.store_op());
+    func.write_slice(&[0x02, 0x00]);
+
+    // and move array_offset to the following element
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(array_offset_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(out_type.size()));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    // return 0 (not done)
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+
+    // otherwise, get the next array from the inner iterator
+    func.write_opcode(Opcode::Else);
+
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        in_type,
+        advance_fn_type_idx,
+    );
+
+    // if the inner iterator is done, so is this one
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+
+    func.write_opcode(Opcode::Else);
+
+    // pass inner current to function to get the next array
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(in_type.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.iter_call_map_fn("offset", map_fn_delta, map_fn_type_idx);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("array_fatptr");
+    func.set_offset_and_size("array_fatptr", "array_offset", "array_size");
+
+    // store the bounds of the new array
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(array_offset_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::I32Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(array_end_delta));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    // go back to start of loop to yield from the new array (which may be empty)
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x02); // break depth is 2 since we need to get out of both if statements
+
+    func.write_opcode(Opcode::End); // end inner if
+
+    func.write_opcode(Opcode::End); // end outer if
+
+    func.write_opcode(Opcode::End); // end loop
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+pub fn define_builtin_flat_map_iter_factory(
+    out_type: Numtype,
+    constructor_idx: u32,
+    advance_fn_table_idx: u32,
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        FuncTypeSignature::new(vec![Numtype::I32, Numtype::I64], Some(Numtype::I64)),
+        vec!["map_fn".to_string(), "iter_over".to_string()],
+    );
+
+    // pass values to constructor
+    // current can just be set to an arbitrary value, since its initial state doesn't matter
+    func.write_opcode(out_type.const_op());
+    match out_type {
+        Numtype::F32 => func.write_slice(&[0x00, 0x00, 0x00, 0x00]),
+        _ => func.write_byte(0x00),
+    };
+    // advance_fn
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(advance_fn_table_idx));
+    // map_fn
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("map_fn");
+    // inner_offset = iter_over >> 32
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("iter_over");
+    func.write_opcode(Opcode::I64Const);
+    func.write_byte(0x20);
+    func.write_opcode(Opcode::I64ShrU);
+    func.write_opcode(Opcode::I32WrapI64);
+    // array_offset and array_end start equal, since there is no current array yet
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(constructor_idx));
+
+    func.write_opcode(Opcode::End);
+
+    func
+}
+
 pub fn define_builtin_scan_iter_advance(
     acc_type: Numtype,
     x_type: Numtype,
@@ -1497,11 +2368,84 @@ pub fn define_builtin_filter_iter_advance(
     func.write_var("inner_offset");
     func.write_opcode(inner_type.load_op());
     func.write_slice(&[0x02, 0x00]);
-    func.write_opcode(Opcode::LocalTee);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("inner_current");
+
+    // pass to filter fn
+    func.iter_call_map_fn("offset", filter_fn_delta, filter_fn_type_idx);
+
+    // if result is false (0), branch to start of loop
+    func.write_opcode(Opcode::I32Eqz);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01); // break depth is 1 since we need to get out of the if statement
+
+    // otherwise, this value is not filtered out, so set it as the new current value
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_current");
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("current");
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("current");
+    func.write_opcode(inner_type.store_op());
+    func.write_slice(&[0x02, 0x00]);
+
+    // return 0 (not done)
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+
+    func.write_opcode(Opcode::End); // end if
+
+    func.write_opcode(Opcode::End); // end loop
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+// like the filter iterator's advance function, but evaluates the given inlined function body as the filter condition,
+// which expects its argument in local 1
+pub fn define_builtin_inline_filter_iter_advance(
+    inner_type: Numtype,
+    inner_offset_delta: u32,
+    advance_fn_type_idx: u32,
+    body: &[u8],
+) -> BuiltinFunc {
+    let mut func = BuiltinFunc::advance_fn_template();
+    func.add_local("inner_current", inner_type); // must be the first local, since the inlined body refers to it
+    func.add_local("inner_offset", Numtype::I32);
+
+    // keep going until we run out of values or get a value that passes the filter
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.iter_call_advance_on_inner(
+        "offset",
+        "inner_offset",
+        inner_offset_delta,
+        inner_type,
+        advance_fn_type_idx,
+    );
+
+    // if done, return 1 here
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x01);
+
+    func.write_opcode(Opcode::Else);
+
+    // get inner current and evaluate the inlined body on it
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("inner_offset");
+    func.write_opcode(inner_type.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::LocalSet);
     func.write_var("inner_current");
-
-    // pass to filter fn
-    func.iter_call_map_fn("offset", filter_fn_delta, filter_fn_type_idx);
+    func.write_slice(body);
 
     // if result is false (0), branch to start of loop
     func.write_opcode(Opcode::I32Eqz);
@@ -1509,15 +2453,10 @@ pub fn define_builtin_filter_iter_advance(
     func.write_byte(0x01); // break depth is 1 since we need to get out of the if statement
 
     // otherwise, this value is not filtered out, so set it as the new current value
-    func.write_opcode(Opcode::LocalGet);
-    func.write_var("inner_current");
-    func.write_opcode(Opcode::LocalSet);
-    func.write_var("current");
-
     func.write_opcode(Opcode::LocalGet);
     func.write_var("offset");
     func.write_opcode(Opcode::LocalGet);
-    func.write_var("current");
+    func.write_var("inner_current");
     func.write_opcode(inner_type.store_op());
     func.write_slice(&[0x02, 0x00]);
 
@@ -2002,6 +2941,88 @@ pub fn define_builtin_iter_collect(
     func
 }
 
+pub fn define_builtin_array_repeat(numtype: Numtype, alloc_idx: u32) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        // takes a value and a count and returns an Arr with the value repeated count times
+        FuncTypeSignature::new(vec![numtype, Numtype::I32], Some(Numtype::I64)),
+        vec!["value".to_string(), "count".to_string()],
+    );
+    func.add_local("array_offset", Numtype::I32);
+    func.add_local("element_offset", Numtype::I32);
+    func.add_local("array_size", Numtype::I32);
+
+    let memsize = numtype.size();
+
+    // array_size = count * memsize
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("count");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32Mul);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("array_size");
+
+    // array_offset = element_offset = alloc(array_size)
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(alloc_idx));
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("element_offset");
+
+    // loop:
+    // if element_offset >= array_offset + array_size: break
+    // *element_offset = value
+    // element_offset += memsize
+    // branch to loop
+
+    func.write_opcode(Opcode::Block);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("element_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::I32GeU);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01); // break out of block
+
+    // store value at element_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("element_offset");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("value");
+    func.write_opcode(numtype.store_op());
+    func.write_slice(&[0x02, 0x00]);
+
+    // add memsize to element_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("element_offset");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("element_offset");
+
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x00); // branch to loop
+
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end block
+
+    // return [array_offset, array_size]
+    func.create_fatptr("array_offset", "array_size");
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
 pub fn define_builtin_iter_last(iter_type: Numtype, advance_fn_type_idx: u32) -> BuiltinFunc {
     let mut func = BuiltinFunc::new(
         // takes an Iter([type]) and returns a value of type [type]
@@ -2060,3 +3081,305 @@ pub fn define_builtin_iter_last(iter_type: Numtype, advance_fn_type_idx: u32) ->
 
     func
 }
+
+// advances an iterator once, returning its first value, or the default if the iterator is already done
+// (so, unlike iter_last, this never reads the current value of an empty iterator)
+pub fn define_builtin_iter_first_or(iter_type: Numtype, advance_fn_type_idx: u32) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        // takes an Iter([type]) and a default [type], and returns a value of type [type]
+        FuncTypeSignature::new(vec![Numtype::I64, iter_type], Some(iter_type)),
+        vec!["iter_fatptr".to_string(), "default".to_string()],
+    );
+    func.add_local("iter_offset", Numtype::I32);
+
+    // iter_offset = iter_fatptr >> 32
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("iter_fatptr");
+    func.write_opcode(Opcode::I64Const);
+    func.write_byte(0x20);
+    func.write_opcode(Opcode::I64ShrU);
+    func.write_opcode(Opcode::I32WrapI64);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("iter_offset");
+
+    // call iterator->advance
+    func.iter_call_advance("iter_offset", iter_type.size(), advance_fn_type_idx);
+
+    // if !iterator->done, return iterator->current, else return default
+    func.write_opcode(Opcode::I32Eqz);
+    func.write_opcode(Opcode::If);
+    func.write_byte(iter_type as u8);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("iter_offset");
+    func.write_opcode(iter_type.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(Opcode::Else);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("default");
+    func.write_opcode(Opcode::End); // end if
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+pub fn define_builtin_array_reverse(numtype: Numtype, alloc_idx: u32) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        // takes an Arr and returns a new Arr with the elements in reverse order
+        FuncTypeSignature::new(vec![Numtype::I64], Some(Numtype::I64)),
+        vec!["array".to_string()],
+    );
+    func.add_local("src_offset", Numtype::I32);
+    func.add_local("array_size", Numtype::I32);
+    func.add_local("dst_offset", Numtype::I32);
+    func.add_local("src_ptr", Numtype::I32);
+    func.add_local("dst_ptr", Numtype::I32);
+
+    let memsize = numtype.size();
+
+    func.set_offset_and_size("array", "src_offset", "array_size");
+
+    // dst_offset = alloc(array_size)
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(alloc_idx));
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("dst_offset");
+
+    // dst_ptr = dst_offset + array_size, src_ptr = src_offset
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("dst_ptr");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_offset");
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("src_ptr");
+
+    // loop:
+    // if dst_ptr <= dst_offset: break
+    // dst_ptr -= memsize
+    // *dst_ptr = *src_ptr
+    // src_ptr += memsize
+    // branch to loop
+
+    func.write_opcode(Opcode::Block);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("dst_ptr");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("dst_offset");
+    func.write_opcode(Opcode::I32LeU);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01); // break out of block
+
+    // subtract memsize from dst_ptr
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("dst_ptr");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("dst_ptr");
+
+    // copy value from src_ptr to dst_ptr
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_ptr");
+    func.write_opcode(numtype.load_op());
+    func.write_slice(&[0x02, 0x00]);
+    func.write_opcode(numtype.store_op());
+    func.write_slice(&[0x02, 0x00]);
+
+    // add memsize to src_ptr
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_ptr");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("src_ptr");
+
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x00); // branch to loop
+
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end block
+
+    // return [dst_offset, array_size]
+    func.create_fatptr("dst_offset", "array_size");
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
+
+pub fn define_builtin_array_chunk(numtype: Numtype, alloc_idx: u32) -> BuiltinFunc {
+    let mut func = BuiltinFunc::new(
+        // takes an Arr and a chunk size n, and returns an Arr of Arrs of n elements each (the last may be shorter)
+        // the chunks are views into the memory of the original array rather than copies;
+        // this is safe since arrays are never mutated in place, but means the chunks keep pointing at the original's memory
+        FuncTypeSignature::new(vec![Numtype::I64, Numtype::I32], Some(Numtype::I64)),
+        vec!["array".to_string(), "n".to_string()],
+    );
+    func.add_local("src_offset", Numtype::I32);
+    func.add_local("array_size", Numtype::I32);
+    func.add_local("n_elems", Numtype::I32);
+    func.add_local("out_size", Numtype::I32);
+    func.add_local("dst_offset", Numtype::I32);
+    func.add_local("dst_ptr", Numtype::I32);
+    func.add_local("src_ptr", Numtype::I32);
+    func.add_local("src_end", Numtype::I32);
+    func.add_local("len", Numtype::I32);
+
+    let memsize = numtype.size();
+
+    func.set_offset_and_size("array", "src_offset", "array_size");
+
+    // trap if n <= 0
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32LeS);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Unreachable);
+    func.write_opcode(Opcode::End);
+
+    // number of chunks = n_elems / n + (n_elems % n != 0), which can't overflow like (n_elems + n - 1) / n
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32DivU);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("n_elems");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32DivU);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n_elems");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32RemU);
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x00);
+    func.write_opcode(Opcode::I32Ne);
+    func.write_opcode(Opcode::I32Add);
+
+    // dst_offset = alloc(number of chunks * size of fatptr)
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x08);
+    func.write_opcode(Opcode::I32Mul);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("out_size");
+    func.write_opcode(Opcode::Call);
+    func.write_slice(&unsigned_leb128(alloc_idx));
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("dst_offset");
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("dst_ptr");
+
+    // src_ptr = src_offset, src_end = src_offset + array_size
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_offset");
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("src_ptr");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("array_size");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("src_end");
+
+    // loop:
+    // if src_ptr >= src_end: break
+    // len = min(src_end - src_ptr, n * memsize)
+    // *dst_ptr = [src_ptr, len]
+    // dst_ptr += 8
+    // src_ptr += len
+    // branch to loop
+
+    func.write_opcode(Opcode::Block);
+    func.write_byte(Numtype::Void as u8);
+    func.write_opcode(Opcode::Loop);
+    func.write_byte(Numtype::Void as u8);
+
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_ptr");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_end");
+    func.write_opcode(Opcode::I32GeU);
+    func.write_opcode(Opcode::BrIf);
+    func.write_byte(0x01); // break out of block
+
+    // compare in elements rather than bytes, so n * memsize is only computed when it fits in the array
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_end");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_ptr");
+    func.write_opcode(Opcode::I32Sub);
+    func.write_opcode(Opcode::LocalTee);
+    func.write_var("len");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32DivU);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32LtU);
+    func.write_opcode(Opcode::If);
+    func.write_byte(Numtype::I32 as u8);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("len");
+    func.write_opcode(Opcode::Else);
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("n");
+    func.write_opcode(Opcode::I32Const);
+    func.write_slice(&unsigned_leb128(memsize));
+    func.write_opcode(Opcode::I32Mul);
+    func.write_opcode(Opcode::End);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("len");
+
+    // store fatptr to chunk
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("dst_ptr");
+    func.create_fatptr("src_ptr", "len");
+    func.write_opcode(Opcode::I64Store);
+    func.write_slice(&[0x02, 0x00]);
+
+    // dst_ptr += 8
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("dst_ptr");
+    func.write_opcode(Opcode::I32Const);
+    func.write_byte(0x08);
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("dst_ptr");
+
+    // src_ptr += len
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("src_ptr");
+    func.write_opcode(Opcode::LocalGet);
+    func.write_var("len");
+    func.write_opcode(Opcode::I32Add);
+    func.write_opcode(Opcode::LocalSet);
+    func.write_var("src_ptr");
+
+    func.write_opcode(Opcode::Br);
+    func.write_byte(0x00); // branch to loop
+
+    func.write_opcode(Opcode::End); // end loop
+    func.write_opcode(Opcode::End); // end block
+
+    // return [dst_offset, out_size]
+    func.create_fatptr("dst_offset", "out_size");
+
+    func.write_opcode(Opcode::End); // end function
+
+    func
+}
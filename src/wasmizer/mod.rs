@@ -4,4 +4,4 @@ pub mod structs;
 mod wasmizer;
 pub mod wasmtypes;
 
-pub use wasmizer::{wasmize, Wasmizer};
+pub use wasmizer::{wasmize, wasmize_file, Wasmizer};
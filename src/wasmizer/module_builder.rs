@@ -90,16 +90,20 @@ pub struct ModuleBuilder {
     globals: Vec<Global>,
     // bytecode for each import
     pub imports: Vec<Vec<u8>>,
+    // debug names for functions (including imports), by function index
+    func_names: Vec<(u32, String)>,
 }
 
 impl Default for ModuleBuilder {
     fn default() -> Self {
         let mem_export = Export::new("memory".to_string(), 0, ExportType::Memory);
+        // memptr is exported so the host can tell when a trap was caused by running out of memory
+        let memptr_export = Export::new("memptr".to_string(), 0, ExportType::Global);
         Self {
             functypes: Vec::new(),
             funcs: vec![],
             func_bodies: vec![],
-            exports: vec![mem_export],
+            exports: vec![mem_export, memptr_export],
             data_segments: vec![],
             globals: vec![
                 // memptr
@@ -116,6 +120,7 @@ impl Default for ModuleBuilder {
                 ),
             ],
             imports: vec![],
+            func_names: vec![],
         }
     }
 }
@@ -184,6 +189,14 @@ impl ModuleBuilder {
         )
     }
 
+    // records a name for the function with the given index, to be written in the name section
+    pub fn name_function(&mut self, idx: u32, name: String) {
+        match self.func_names.iter_mut().find(|(i, _)| *i == idx) {
+            Some((_, n)) => *n = name,
+            None => self.func_names.push((idx, name)),
+        }
+    }
+
     pub fn get_functype_idx(&mut self, ftype: &FuncTypeSignature) -> u32 {
         match self
             .functypes
@@ -299,6 +312,23 @@ impl ModuleBuilder {
         bytes
     }
 
+    // custom section giving debug names to functions, so tools can show them instead of bare indices
+    fn name_section(&self) -> Vec<u8> {
+        let mut names = self.func_names.iter().collect::<Vec<_>>();
+        names.sort_by_key(|(idx, _)| *idx);
+        let mut name_map = unsigned_leb128(names.len() as u32);
+        for (idx, name) in names {
+            name_map.append(&mut unsigned_leb128(*idx));
+            name_map.append(&mut encode_string(name));
+        }
+        let mut data = encode_string("name");
+        data.push(0x01); // function names subsection
+        data.append(&mut vector(name_map));
+        let mut bytes = vec![SectionType::Custom as u8];
+        bytes.append(&mut vector(data));
+        bytes
+    }
+
     pub fn program(&self) -> Vec<u8> {
         [
             &MAGIC,
@@ -314,6 +344,7 @@ impl ModuleBuilder {
             self.data_count_section().as_slice(), // this needs to come before the code section even though SectionType::DataCount  > SectionType::Code
             self.code_section().as_slice(),
             self.data_section().as_slice(),
+            self.name_section().as_slice(),
         ]
         .concat()
     }
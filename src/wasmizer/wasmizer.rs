@@ -1,3 +1,6 @@
+use std::num::IntErrorKind;
+use std::path::Path;
+
 use rustc_hash::FxHashMap;
 
 use super::module_builder::{Global, ModuleBuilder};
@@ -347,8 +350,10 @@ impl Wasmizer {
             Some(func) => func,
             None => return Err("Tried to pop function when frames is empty".to_string()),
         };
-        self.builder
-            .add_function(&func.signature, func.locals.types, func.bytes, export_name)
+        let idx = self.builder
+            .add_function(&func.signature, func.locals.types, func.bytes, export_name)?;
+        self.builder.name_function(idx, func.name);
+        Ok(idx)
     }
     pub fn write_last_func_index(&mut self) {
         let idx = self.builder.funcs.len() as i32 - 1;
@@ -359,7 +364,13 @@ impl Wasmizer {
     pub fn write_const(&mut self, value: &str, typ: &ast::Type) -> Result<(), String> {
         match typ {
             ast::Type::Int => {
-                let value = value.parse::<i32>().map_err(|e| e.to_string())?;
+                // wasm Ints are 32 bits wide, so literals that fit in the VM's i64 may not fit here
+                let value = value.parse::<i32>().map_err(|e| match e.kind() {
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => format!(
+                        "Integer literal exceeds 32-bit range for wasm backend: {}", value
+                    ),
+                    _ => e.to_string(),
+                })?;
                 self.write_opcode(Opcode::I32Const);
                 self.bytes_mut().append(&mut signed_leb128(value));
             }
@@ -419,8 +430,11 @@ impl Wasmizer {
             ast::Type::Float => {
                 self.write_opcode(Opcode::F32Eq);
             }
-            ast::Type::Str | ast::Type::Arr(_) => {
-                self.arrays_equal()?;
+            ast::Type::Str => {
+                self.arrays_equal(Numtype::I32)?;
+            }
+            ast::Type::Arr(t) => {
+                self.arrays_equal(Numtype::from_ast_type(t)?)?;
             }
             _ => {
                 return Err(format!("Cannot compare values of type {:?}", typ));
@@ -436,6 +450,10 @@ impl Wasmizer {
             ast::Type::Float => {
                 self.write_opcode(Opcode::F32Ne);
             }
+            ast::Type::Str | ast::Type::Arr(_) => {
+                self.write_equal(typ)?;
+                self.write_opcode(Opcode::I32Eqz);
+            }
             _ => {
                 return Err(format!("Cannot compare values of type {:?}", typ));
             }
@@ -558,6 +576,20 @@ impl Wasmizer {
         }
         Ok(())
     }
+    pub fn write_mod(&mut self, typ: &ast::Type) -> Result<(), String> {
+        match typ {
+            ast::Type::Int => {
+                // same as the mod builtin, so the result is always positive
+                let fn_idx = unsigned_leb128(self.get_callable_builtin("mod[Int, Int]")?);
+                self.write_opcode(Opcode::Call);
+                self.write_slice(&fn_idx);
+            }
+            _ => {
+                return Err(format!("Cannot take modulus of values of type {:?}", typ));
+            }
+        }
+        Ok(())
+    }
     pub fn write_and(&mut self, typ: &ast::Type) -> Result<(), String> {
         match typ {
             ast::Type::Bool => {
@@ -580,6 +612,17 @@ impl Wasmizer {
         }
         Ok(())
     }
+    pub fn write_xor(&mut self, typ: &ast::Type) -> Result<(), String> {
+        match typ {
+            ast::Type::Bool => {
+                self.write_opcode(Opcode::I32Xor);
+            }
+            _ => {
+                return Err(format!("Cannot XOR values of type {:?}", typ));
+            }
+        }
+        Ok(())
+    }
 
     pub fn write_range(&mut self, typ: &ast::Type) -> Result<(), String> {
         if typ != &ast::Type::Int {
@@ -771,16 +814,23 @@ impl Wasmizer {
         Ok(())
     }
 
+    // wasmize an expression that is iterated over, converting it into an array iterator if it is an array,
+    // so ops that consume iterators don't need to handle arrays themselves
+    pub fn write_as_iter(&mut self, expr: &dyn ast::Expression) -> Result<(), String> {
+        expr.wasmize(self)?;
+        if let ast::Type::Arr(inner_type) = expr.get_type()? {
+            self.make_array_iter(&inner_type)?;
+        }
+        Ok(())
+    }
+
+    // the write_* functions for iterator-consuming ops expect the iterator on top of the stack,
+    // as left by write_as_iter
     pub fn write_map(
         &mut self,
         result_inner_type: &ast::Type,
         input_inner_type: &ast::Type,
-        input_is_array: bool,
     ) -> Result<(), String> {
-        if input_is_array {
-            self.make_array_iter(input_inner_type)?;
-        }
-
         let result_inner_type = Numtype::from_ast_type(result_inner_type)?;
         let input_inner_type = Numtype::from_ast_type(input_inner_type)?;
 
@@ -792,25 +842,57 @@ impl Wasmizer {
         Ok(())
     }
 
-    pub fn write_reduce(
+    // like write_map, but with the body of the mapping function written directly into the iterator's advance function
+    pub fn write_inline_map(
+        &mut self,
+        param_name: &str,
+        body: &dyn ast::Expression,
+        iter_over: &dyn ast::Expression,
+        input_inner_type: &ast::Type,
+    ) -> Result<(), String> {
+        // the iterator factory still expects a function index, but it won't be used
+        self.write_opcode(Opcode::I32Const);
+        self.write_byte(0x00);
+        self.write_as_iter(iter_over)?;
+
+        let input_inner_type = Numtype::from_ast_type(input_inner_type)?;
+        let result_inner_type = Numtype::from_ast_type(&body.get_type()?)?;
+        let body = self.inline_body(param_name, input_inner_type, body)?;
+
+        let factory = unsigned_leb128(
+            self.get_inline_map_iter_factory(input_inner_type, result_inner_type, &body)?
+        );
+        self.write_opcode(Opcode::Call);
+        self.write_slice(&factory);
+
+        Ok(())
+    }
+
+    // leaves an iterator over the successive accumulator values on the stack
+    pub fn write_scan(
         &mut self,
         acc_type: &ast::Type,
         x_type: &ast::Type,
-        use_array_iter: bool,
     ) -> Result<(), String> {
-        if use_array_iter {
-            // convert the Array into an ArrayIter first
-            self.make_array_iter(x_type)?;
-        }
-
         let acc_type = Numtype::from_ast_type(acc_type)?;
         let x_type = Numtype::from_ast_type(x_type)?;
 
-        // we implement this by creating a scan iterator, then getting the last element of that iterator
         let factory = unsigned_leb128(self.get_scan_iter_factory(acc_type, x_type)?);
         self.write_opcode(Opcode::Call);
         self.write_slice(&factory);
 
+        Ok(())
+    }
+
+    pub fn write_reduce(
+        &mut self,
+        acc_type: &ast::Type,
+        x_type: &ast::Type,
+    ) -> Result<(), String> {
+        // we implement this by creating a scan iterator, then getting the last element of that iterator
+        self.write_scan(acc_type, x_type)?;
+
+        let acc_type = Numtype::from_ast_type(acc_type)?;
         let last_fn_idx = unsigned_leb128(self.init_last(acc_type)?);
         self.write_opcode(Opcode::Call);
         self.write_slice(&last_fn_idx);
@@ -818,12 +900,7 @@ impl Wasmizer {
         Ok(())
     }
 
-    pub fn write_filter(&mut self, typ: &ast::Type, use_array_iter: bool) -> Result<(), String> {
-        if use_array_iter {
-            // convert the Array into an ArrayIter first
-            self.make_array_iter(typ)?;
-        }
-
+    pub fn write_filter(&mut self, typ: &ast::Type) -> Result<(), String> {
         let numtype = Numtype::from_ast_type(typ)?;
         let factory = unsigned_leb128(self.get_filter_iter_factory(numtype)?);
         self.write_opcode(Opcode::Call);
@@ -832,6 +909,78 @@ impl Wasmizer {
         Ok(())
     }
 
+    // like write_filter, but with the body of the filter function written directly into the iterator's advance function
+    pub fn write_inline_filter(
+        &mut self,
+        param_name: &str,
+        body: &dyn ast::Expression,
+        iter_over: &dyn ast::Expression,
+        typ: &ast::Type,
+    ) -> Result<(), String> {
+        // the iterator factory still expects a function index, but it won't be used
+        self.write_opcode(Opcode::I32Const);
+        self.write_byte(0x00);
+        self.write_as_iter(iter_over)?;
+
+        let numtype = Numtype::from_ast_type(typ)?;
+        let body = self.inline_body(param_name, numtype, body)?;
+        let factory = unsigned_leb128(self.get_inline_filter_iter_factory(numtype, &body)?);
+        self.write_opcode(Opcode::Call);
+        self.write_slice(&factory);
+
+        Ok(())
+    }
+
+    // compile the body of a single-argument function so it can be spliced into an iterator's advance function,
+    // which takes the iterator's offset as its parameter and stores the function argument in its first local
+    fn inline_body(
+        &mut self,
+        param_name: &str,
+        param_type: Numtype,
+        body: &dyn ast::Expression,
+    ) -> Result<Vec<u8>, String> {
+        let ret = Numtype::from_ast_type(&body.get_type()?)?;
+        let signature = FuncTypeSignature::new(vec![Numtype::I32, param_type], Some(ret));
+        self.frames.push(WasmFunc::new("<inline>".to_string(), signature, false));
+        self.add_param_name("<offset>".to_string());
+        self.add_param_name(param_name.to_string());
+        let result = body.wasmize(self);
+        let func = match self.frames.pop() {
+            Some(func) => func,
+            None => return Err("Tried to pop function when frames is empty".to_string()),
+        };
+        result?;
+        if func.locals.n_locals != 0 {
+            return Err("Cannot inline a function that declares local variables".to_string());
+        }
+        Ok(func.bytes)
+    }
+
+    pub fn write_flat_map(
+        &mut self,
+        result_inner_type: &ast::Type,
+        input_inner_type: &ast::Type,
+    ) -> Result<(), String> {
+        let result_inner_type = Numtype::from_ast_type(result_inner_type)?;
+        let input_inner_type = Numtype::from_ast_type(input_inner_type)?;
+        let factory = unsigned_leb128(
+            self.get_flat_map_iter_factory(input_inner_type, result_inner_type)?
+        );
+        self.write_opcode(Opcode::Call);
+        self.write_slice(&factory);
+
+        Ok(())
+    }
+
+    // create an iterator over 0, 1, 2, ... that is long enough to zip with any other iterator
+    pub fn write_index_iter(&mut self) -> Result<(), String> {
+        self.write_opcode(Opcode::I32Const);
+        self.write_byte(0x00);
+        self.write_opcode(Opcode::I32Const);
+        self.write_slice(&signed_leb128(i32::MAX));
+        self.write_range(&ast::Type::Int)
+    }
+
     pub fn write_zipmap(
         &mut self,
         func_ret_type: &ast::Type,
@@ -864,8 +1013,23 @@ impl Wasmizer {
 
     // check if two arrays or strings are equal
     // expects two previous values on the stack to be both i64 fatptrs
-    fn arrays_equal(&mut self) -> Result<(), String> {
-        self.call_builtin("heap_objs_equal")
+    // elem_type is the type of the array elements; float elements need their own comparison
+    fn arrays_equal(&mut self, elem_type: Numtype) -> Result<(), String> {
+        if elem_type != Numtype::F32 {
+            return self.call_builtin("heap_objs_equal");
+        }
+        let fn_idx = match self.builtins.get("float_arrays_equal") {
+            Some(idx) => *idx,
+            None => {
+                let func = builtin_funcs::define_builtin_heap_objs_equal(Numtype::F32);
+                let idx = self.builder.add_builtin(&func)?;
+                self.builtins.insert("float_arrays_equal".to_string(), idx);
+                idx
+            }
+        };
+        self.write_opcode(Opcode::Call);
+        self.write_slice(&unsigned_leb128(fn_idx));
+        Ok(())
     }
 
     pub fn write_array(&mut self, len: u16, typ: &ast::Type) -> Result<(), String> {
@@ -962,6 +1126,19 @@ impl Wasmizer {
         Ok(())
     }
 
+    pub fn write_array_repeat(&mut self, len: u16, typ: &ast::Type) -> Result<(), String> {
+        let numtype = Numtype::from_ast_type(typ)?;
+        // value to repeat is already on the stack
+        self.write_opcode(Opcode::I32Const);
+        self.write_slice(&signed_leb128(len as i32));
+
+        let repeat_fn_idx = unsigned_leb128(self.init_array_repeat(numtype)?);
+        self.write_opcode(Opcode::Call);
+        self.write_slice(&repeat_fn_idx);
+
+        Ok(())
+    }
+
     fn write_to_memory(
         &mut self,
         memptr_idx: &[u8],
@@ -1138,10 +1315,59 @@ impl Wasmizer {
         let func = match name {
             "abs[Int]" => builtin_funcs::define_builtin_abs_int(),
             "abs[Float]" => builtin_funcs::define_builtin_abs_float(),
+            "abs_diff[Int, Int]" => builtin_funcs::define_builtin_abs_diff_int(),
+            "abs_diff[Float, Float]" => builtin_funcs::define_builtin_abs_diff_float(),
+            "is_finite[Float]" => builtin_funcs::define_builtin_is_finite(),
+            "is_inf[Float]" => builtin_funcs::define_builtin_is_inf(),
+            "byte_len[Str]" => builtin_funcs::define_builtin_byte_len(),
             "float[Int]" => builtin_funcs::define_builtin_itof(),
             "int[Float]" => builtin_funcs::define_builtin_ftoi(),
+            "int[Bool]" => builtin_funcs::define_builtin_btoi(),
+            "bool[Int]" => builtin_funcs::define_builtin_itob(),
             "sqrt[Float]" => builtin_funcs::define_builtin_sqrt_float(),
+            "clamp01[Float]" => builtin_funcs::define_builtin_clamp01(),
+            "lerp[Float, Float, Float]" => builtin_funcs::define_builtin_lerp(),
             "mod[Int, Int]" => builtin_funcs::define_builtin_mod(),
+            "popcount[Int]" => builtin_funcs::define_builtin_popcount(),
+            "shl[Int, Int]" => builtin_funcs::define_builtin_int_bitop(Opcode::I32Shl),
+            "shr[Int, Int]" => builtin_funcs::define_builtin_int_bitop(Opcode::I32ShrS),
+            "band[Int, Int]" => builtin_funcs::define_builtin_int_bitop(Opcode::I32And),
+            "bor[Int, Int]" => builtin_funcs::define_builtin_int_bitop(Opcode::I32Or),
+            "bxor[Int, Int]" => builtin_funcs::define_builtin_int_bitop(Opcode::I32Xor),
+            "pow[Float, Int]" => builtin_funcs::define_builtin_fpow_int(),
+            "reverse[Arr(Int)]" => builtin_funcs::define_builtin_array_reverse(
+                Numtype::I32,
+                *self.builtins.get("alloc").unwrap(),
+            ),
+            "reverse[Arr(Float)]" => builtin_funcs::define_builtin_array_reverse(
+                Numtype::F32,
+                *self.builtins.get("alloc").unwrap(),
+            ),
+            "chunk[Arr(Int), Int]" | "chunk[Arr(Bool), Int]" => builtin_funcs::define_builtin_array_chunk(
+                Numtype::I32,
+                *self.builtins.get("alloc").unwrap(),
+            ),
+            "chunk[Arr(Float), Int]" => builtin_funcs::define_builtin_array_chunk(
+                Numtype::F32,
+                *self.builtins.get("alloc").unwrap(),
+            ),
+            "chunk[Arr(Str), Int]" => builtin_funcs::define_builtin_array_chunk(
+                Numtype::I64,
+                *self.builtins.get("alloc").unwrap(),
+            ),
+            // take and drop are cached as iterator factories, so they don't need to go through add_builtin here
+            "take[Iter(Int), Int]" | "take[Iter(Bool), Int]" => {
+                return self.get_counted_iter_factory("TakeIter", Numtype::I32)
+            }
+            "take[Iter(Float), Int]" => return self.get_counted_iter_factory("TakeIter", Numtype::F32),
+            "drop[Iter(Int), Int]" | "drop[Iter(Bool), Int]" => {
+                return self.get_counted_iter_factory("DropIter", Numtype::I32)
+            }
+            "drop[Iter(Float), Int]" => return self.get_counted_iter_factory("DropIter", Numtype::F32),
+            "take[Arr(Int), Int]" | "take[Arr(Bool), Int]" => self.define_counted_array_iter("TakeIter", Numtype::I32)?,
+            "take[Arr(Float), Int]" => self.define_counted_array_iter("TakeIter", Numtype::F32)?,
+            "drop[Arr(Int), Int]" | "drop[Arr(Bool), Int]" => self.define_counted_array_iter("DropIter", Numtype::I32)?,
+            "drop[Arr(Float), Int]" => self.define_counted_array_iter("DropIter", Numtype::F32)?,
             "sum[Iter(Int)]" => builtin_funcs::define_builtin_reduce_iter(
                 Numtype::I32,
                 "sum",
@@ -1172,6 +1398,18 @@ impl Wasmizer {
                 "any",
                 self.get_advance_fn_type_idx(),
             ),
+            "first_or[Iter(Int), Int]" | "first_or[Iter(Bool), Bool]" => builtin_funcs::define_builtin_iter_first_or(
+                Numtype::I32,
+                self.get_advance_fn_type_idx(),
+            ),
+            "first_or[Iter(Float), Float]" => builtin_funcs::define_builtin_iter_first_or(
+                Numtype::F32,
+                self.get_advance_fn_type_idx(),
+            ),
+            "first_or[Iter(Str), Str]" => builtin_funcs::define_builtin_iter_first_or(
+                Numtype::I64,
+                self.get_advance_fn_type_idx(),
+            ),
             _ => return Err(format!("variable {} not found", name)),
         };
         let fn_idx = self.builder.add_builtin(&func)?;
@@ -1240,19 +1478,32 @@ impl Wasmizer {
     // adds struct to Wasmizer's list of struct definitions
     // optionally adds struct constructor idx to stack
     // returns the index of the struct's constructor function
+    // structs with identical field layouts share a single constructor
     pub fn create_struct(
         &mut self,
         struct_name: String,
         struct_def: Struct,
         write_constructor: bool,
     ) -> Result<u32, String> {
-        let func = builtin_funcs::define_builtin_struct_constructor(
-            &struct_def,
-            *self.builtins.get("alloc").unwrap(),
-        );
-
-        // add to list of builtin functions
-        let idx = self.builder.add_builtin(&func)?;
+        let layout = struct_def
+            .fields
+            .iter()
+            .map(|(_, field)| field.nt)
+            .collect::<Vec<_>>();
+        let layout_name = format!("<Constructor{:?}>", layout);
+        let idx = match self.builtins.get(&layout_name) {
+            Some(idx) => *idx,
+            None => {
+                let func = builtin_funcs::define_builtin_struct_constructor(
+                    &struct_def,
+                    *self.builtins.get("alloc").unwrap(),
+                );
+                // add to list of builtin functions
+                let idx = self.builder.add_builtin(&func)?;
+                self.builtins.insert(layout_name, idx);
+                idx
+            }
+        };
         self.builtins.insert(struct_name.clone(), idx);
 
         // add to definitions
@@ -1260,20 +1511,36 @@ impl Wasmizer {
 
         if write_constructor {
             // add constructor to stack
-            self.write_last_func_index();
+            let table_idx = idx - self.builder.imports.len() as u32;
+            self.write_opcode(Opcode::I32Const);
+            self.write_slice(&signed_leb128(table_idx as i32));
         }
         Ok(idx)
     }
 
+    // name for a struct that is identified by its fields as well as its name, e.g., `Pair[first: I64, second: F32]`,
+    // for structs like the pairs made by zip, where the same name can have many layouts
+    pub fn layout_struct_name(struct_name: &str, fields: &[(String, ast::Type)]) -> Result<String, String> {
+        let fields = fields
+            .iter()
+            .map(|(name, t)| Ok(format!("{}: {}", name, Numtype::from_ast_type(t)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(format!("{}[{}]", struct_name, fields.join(", ")))
+    }
+
     // get the field of a struct
     // fatptr to struct should be on top of stack when calling this
     pub fn get_field(&mut self, object_type: ast::Type, field_name: &str) -> Result<(), String> {
         // first, figure out which struct type this is for
-        let (struct_name, _) = match object_type {
+        let (struct_name, fields) = match object_type {
             ast::Type::Object(name, fields) => (name, fields),
             _ => unreachable!(),
         };
-        let struct_def = self.structs.get(&struct_name).unwrap();
+        let layout_name = Self::layout_struct_name(&struct_name, &fields)?;
+        let struct_def = match self.structs.get(&layout_name) {
+            Some(struct_def) => struct_def,
+            None => self.structs.get(&struct_name).unwrap(),
+        };
         let field = struct_def
             .fields
             .iter()
@@ -1393,6 +1660,53 @@ impl Wasmizer {
         Ok(factory_idx)
     }
 
+    // create a map iterator factory whose advance function computes the mapped value inline
+    // these are shared between call sites with identical function bodies
+    fn get_inline_map_iter_factory(
+        &mut self,
+        in_type: Numtype,
+        out_type: Numtype,
+        body: &[u8],
+    ) -> Result<u32, String> {
+        let factory_name = format!("<InlineMapIter[{}->{}]{:?}Factory>", in_type, out_type, body);
+        if let Some(idx) = self.builtins.get(&factory_name) {
+            return Ok(*idx);
+        }
+
+        // same layout as a regular map iterator; the map_fn field is unused
+        let struct_def = Struct::new(vec![
+            ("current".to_string(), out_type),
+            ("advance_fn".to_string(), Numtype::I32),
+            ("map_fn".to_string(), Numtype::I32),
+            ("inner_offset".to_string(), Numtype::I32),
+        ]);
+        let inner_offset_delta = struct_def.get_field("inner_offset").unwrap().offset;
+
+        let struct_name = format!("<MapIter[{}->{}]>", in_type, out_type);
+        let constructor_idx = self.create_struct(struct_name, struct_def, false)?;
+
+        let advance_fn_type_idx = self.get_advance_fn_type_idx();
+        let func = builtin_funcs::define_builtin_inline_map_iter_advance(
+            in_type,
+            out_type,
+            inner_offset_delta,
+            advance_fn_type_idx,
+            body,
+        );
+        let advance_fn_idx = self.builder.add_builtin(&func)?;
+
+        // the regular map factory can be reused, since only the advance function differs
+        let func = builtin_funcs::define_builtin_map_iter_factory(
+            out_type,
+            constructor_idx,
+            advance_fn_idx - self.builder.imports.len() as u32,
+        );
+        let factory_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(factory_name, factory_idx);
+
+        Ok(factory_idx)
+    }
+
     fn get_scan_iter_factory(&mut self, acc_type: Numtype, x_type: Numtype) -> Result<u32, String> {
         let factory_name = format!("<ScanIter[{},{}]Factory>", acc_type, x_type);
         if let Some(idx) = self.builtins.get(&factory_name) {
@@ -1446,6 +1760,64 @@ impl Wasmizer {
         Ok(factory_idx)
     }
 
+    // kind is either "TakeIter" or "DropIter"; both share a layout and a factory, and differ only in their advance function
+    fn get_counted_iter_factory(&mut self, kind: &str, inner_type: Numtype) -> Result<u32, String> {
+        let factory_name = format!("<{}[{}]Factory>", kind, inner_type);
+        if let Some(idx) = self.builtins.get(&factory_name) {
+            return Ok(*idx);
+        }
+
+        let struct_def = Struct::new(vec![
+            ("current".to_string(), inner_type),
+            ("advance_fn".to_string(), Numtype::I32),
+            ("count".to_string(), Numtype::I32), // the number of elements left to take or skip
+            ("inner_offset".to_string(), Numtype::I32), // the memory offset of the wrapped iterator
+        ]);
+
+        let count_delta = struct_def.get_field("count").unwrap().offset;
+        let inner_offset_delta = struct_def.get_field("inner_offset").unwrap().offset;
+
+        let struct_name = format!("<{}[{}]>", kind, inner_type);
+        let constructor_idx = self.create_struct(struct_name, struct_def, false)?;
+
+        // initialize advance fn
+        let advance_fn_type_idx = self.get_advance_fn_type_idx();
+        let func = if kind == "TakeIter" {
+            builtin_funcs::define_builtin_take_iter_advance(
+                inner_type,
+                count_delta,
+                inner_offset_delta,
+                advance_fn_type_idx,
+            )
+        } else {
+            builtin_funcs::define_builtin_drop_iter_advance(
+                inner_type,
+                count_delta,
+                inner_offset_delta,
+                advance_fn_type_idx,
+            )
+        };
+        let advance_fn_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(format!("<{}[{}]Advance>", kind, inner_type), advance_fn_idx);
+
+        let func = builtin_funcs::define_builtin_counted_iter_factory(
+            inner_type,
+            constructor_idx,
+            advance_fn_idx - self.builder.imports.len() as u32,
+        );
+        let factory_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(factory_name, factory_idx);
+
+        Ok(factory_idx)
+    }
+
+    // take or drop over an array, which goes through the same array iterator factory as make_array_iter
+    fn define_counted_array_iter(&mut self, kind: &str, inner_type: Numtype) -> Result<builtin_funcs::BuiltinFunc, String> {
+        let array_iter_factory = self.get_array_iter_factory(inner_type)?;
+        let counted_iter_factory = self.get_counted_iter_factory(kind, inner_type)?;
+        Ok(builtin_funcs::define_builtin_counted_array_iter(array_iter_factory, counted_iter_factory))
+    }
+
     fn get_filter_iter_factory(&mut self, inner_type: Numtype) -> Result<u32, String> {
         let factory_name = format!("<FilterIter[{}]Factory>", inner_type);
         if let Some(idx) = self.builtins.get(&factory_name) {
@@ -1498,6 +1870,104 @@ impl Wasmizer {
         Ok(factory_idx)
     }
 
+    // create a filter iterator factory whose advance function evaluates the filter condition inline
+    fn get_inline_filter_iter_factory(&mut self, inner_type: Numtype, body: &[u8]) -> Result<u32, String> {
+        let factory_name = format!("<InlineFilterIter[{}]{:?}Factory>", inner_type, body);
+        if let Some(idx) = self.builtins.get(&factory_name) {
+            return Ok(*idx);
+        }
+
+        // same layout as a regular filter iterator; the filter_fn field is unused
+        let struct_def = Struct::new(vec![
+            ("current".to_string(), inner_type),
+            ("advance_fn".to_string(), Numtype::I32),
+            ("filter_fn".to_string(), Numtype::I32),
+            ("inner_offset".to_string(), Numtype::I32),
+        ]);
+        let inner_offset_delta = struct_def.get_field("inner_offset").unwrap().offset;
+
+        let struct_name = format!("<FilterIter[{}]>", inner_type);
+        let constructor_idx = self.create_struct(struct_name, struct_def, false)?;
+
+        let advance_fn_type_idx = self.get_advance_fn_type_idx();
+        let func = builtin_funcs::define_builtin_inline_filter_iter_advance(
+            inner_type,
+            inner_offset_delta,
+            advance_fn_type_idx,
+            body,
+        );
+        let advance_fn_idx = self.builder.add_builtin(&func)?;
+
+        let func = builtin_funcs::define_builtin_filter_iter_factory(
+            inner_type,
+            advance_fn_idx - self.builder.imports.len() as u32,
+            constructor_idx,
+        );
+        let factory_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(factory_name, factory_idx);
+
+        Ok(factory_idx)
+    }
+
+    // create a struct type that is used to store flat map iterators
+    fn get_flat_map_iter_factory(&mut self, in_type: Numtype, out_type: Numtype) -> Result<u32, String> {
+        let factory_name = format!("<FlatMapIter[{}->{}]Factory>", in_type, out_type);
+        if let Some(idx) = self.builtins.get(&factory_name) {
+            return Ok(*idx);
+        }
+
+        let struct_def = Struct::new(vec![
+            ("current".to_string(), out_type),
+            ("advance_fn".to_string(), Numtype::I32),
+            ("map_fn".to_string(), Numtype::I32), // the table index of the function producing arrays
+            ("inner_offset".to_string(), Numtype::I32), // the memory offset of the iterator being mapped from
+            ("array_offset".to_string(), Numtype::I32), // the memory offset of the next element to yield from the current array
+            ("array_end".to_string(), Numtype::I32), // the memory offset of the end of the current array
+        ]);
+
+        let map_fn_delta = struct_def.get_field("map_fn").unwrap().offset;
+        let inner_offset_delta = struct_def.get_field("inner_offset").unwrap().offset;
+        let array_offset_delta = struct_def.get_field("array_offset").unwrap().offset;
+        let array_end_delta = struct_def.get_field("array_end").unwrap().offset;
+
+        let struct_name = format!("<FlatMapIter[{}->{}]>", in_type, out_type);
+        let constructor_idx = self.create_struct(struct_name, struct_def, false)?;
+
+        // initialize advance fn
+        let advance_fn_type_idx = self.get_advance_fn_type_idx();
+        let map_fn_type_idx = self
+            .builder
+            .get_functype_idx(&FuncTypeSignature::new(vec![in_type], Some(Numtype::I64)));
+        let func = builtin_funcs::define_builtin_flat_map_iter_advance(
+            in_type,
+            out_type,
+            inner_offset_delta,
+            map_fn_delta,
+            array_offset_delta,
+            array_end_delta,
+            advance_fn_type_idx,
+            map_fn_type_idx,
+        );
+
+        let advance_fn_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(
+            format!("<FlatMapIter[{}->{}]Advance>", in_type, out_type),
+            advance_fn_idx,
+        );
+
+        // create factory function (for creating from function, iter_over syntax)
+        let func = builtin_funcs::define_builtin_flat_map_iter_factory(
+            out_type,
+            constructor_idx,
+            advance_fn_idx - self.builder.imports.len() as u32,
+        );
+
+        let factory_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(factory_name, factory_idx);
+
+        Ok(factory_idx)
+    }
+
     fn get_zipmap_iter_factory(
         &mut self,
         out_type: Numtype,
@@ -1631,6 +2101,23 @@ impl Wasmizer {
         Ok(fn_idx)
     }
 
+    // create the `array_repeat_[type]` builtin (used for [x; n] array literals)
+    fn init_array_repeat(&mut self, numtype: Numtype) -> Result<u32, String> {
+        // check if already initialized, return index if so
+        let fn_name = format!("array_repeat_{}", numtype);
+        if let Some(idx) = self.builtins.get(&fn_name) {
+            return Ok(*idx);
+        }
+
+        let alloc_idx = *self.builtins.get("alloc").unwrap();
+        let func = builtin_funcs::define_builtin_array_repeat(numtype, alloc_idx);
+
+        let fn_idx = self.builder.add_builtin(&func)?;
+        self.builtins.insert(fn_name, fn_idx);
+
+        Ok(fn_idx)
+    }
+
     // initialize the last_[type] builtin, used to get the last element in an iterator
     fn init_last(&mut self, iter_type: Numtype) -> Result<u32, String> {
         // check if already initialized, return index if so
@@ -1648,22 +2135,36 @@ impl Wasmizer {
         Ok(fn_idx)
     }
 
-    fn to_bytes(&self) -> Vec<u8> {
+    fn finish(&mut self) -> Vec<u8> {
+        // imports and builtins are named by the keys they are looked up by
+        for (name, idx) in self.builtins.iter() {
+            self.builder.name_function(*idx, name.clone());
+        }
         self.builder.program()
     }
 }
 
 pub fn wasmize(source: &str, global_env: env::Env) -> Result<(Vec<u8>, ast::Type), String> {
+    wasmize_source(source, None, global_env)
+}
+
+// like `wasmize`, but imports are resolved relative to the file's directory
+pub fn wasmize_file(path: &str, global_env: env::Env) -> Result<(Vec<u8>, ast::Type), String> {
+    let source = std::fs::read_to_string(path).map_err(|_| format!("Could not read file `{}`", path))?;
+    wasmize_source(&source, Some(Path::new(path)), global_env)
+}
+
+fn wasmize_source(source: &str, path: Option<&Path>, global_env: env::Env) -> Result<(Vec<u8>, ast::Type), String> {
     let tokens = scanner::scan(source);
-    let ast = parser::parse(tokens, global_env.global_types.clone())
-        .map_err(|_| "Compilation halted due to parsing error.")?;
+    let ast = parser::parse(tokens, global_env.global_types.clone(), path)
+        .map_err(|(_, e)| e)?;
     #[cfg(feature = "debug")]
     println!("{:?}", ast);
     let mut wasmizer = Wasmizer::new(global_env)?;
     ast.wasmize(&mut wasmizer)?;
     let return_type = ast.get_type()?;
 
-    let bytes = wasmizer.to_bytes();
+    let bytes = wasmizer.finish();
     #[cfg(feature = "debug")]
     {
         // print out bytes in form similar to x                                                                                                                          xd -g 1
@@ -3,6 +3,7 @@ use crate::ast;
 const FUNCTYPE: u8 = 0x60;
 
 pub enum SectionType {
+    Custom = 0x00,
     Type = 0x01,
     Import = 0x02,
     Function = 0x03,
@@ -28,6 +29,7 @@ impl Numtype {
     pub fn from_ast_type(typ: &ast::Type) -> Result<Self, String> {
         match typ {
             ast::Type::Int => Ok(Self::I32),
+            // Bools are not packed; each takes a full 4 bytes, including as an array element
             ast::Type::Bool => Ok(Self::I32),
             ast::Type::Float => Ok(Self::F32),
             ast::Type::Func(..) => Ok(Self::I32), // functions are referred to by their table indices
@@ -94,10 +96,12 @@ impl std::fmt::Display for Numtype {
 pub enum ExportType {
     Func = 0x00,
     Memory = 0x02,
+    Global = 0x03,
 }
 
 #[derive(Clone, Copy)]
 pub enum Opcode {
+    Unreachable = 0x00,
     Block = 0x02,
     Loop = 0x03,
     If = 0x04,
@@ -137,12 +141,14 @@ pub enum Opcode {
     I32LeU = 0x4d,
     I32GeS = 0x4e,
     I32GeU = 0x4f,
+    I64Ne = 0x52,
     F32Eq = 0x5b,
     F32Ne = 0x5c,
     F32Lt = 0x5d,
     F32Gt = 0x5e,
     F32Le = 0x5f,
     F32Ge = 0x60,
+    I32Popcnt = 0x69,
     I32Add = 0x6a,
     I32Sub = 0x6b,
     I32Mul = 0x6c,
@@ -153,6 +159,7 @@ pub enum Opcode {
     I32And = 0x71,
     I32Or = 0x72,
     I32Xor = 0x73,
+    I32Shl = 0x74,
     I32ShrS = 0x75,
     I32ShrU = 0x76,
     I64Add = 0x7c,
@@ -165,8 +172,11 @@ pub enum Opcode {
     F32Sub = 0x93,
     F32Mul = 0x94,
     F32Div = 0x95,
+    F32Min = 0x96,
+    F32Max = 0x97,
     I32WrapI64 = 0xa7,
     I32TruncF32S = 0xa8,
+    I64ExtendI32S = 0xac,
     I64ExtendI32U = 0xad,
     F32ConvertI32S = 0xb2,
 }
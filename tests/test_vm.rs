@@ -12,6 +12,24 @@ macro_rules! run_expect_value {
     }
 }
 
+// a file in the temp directory, named after the process and the test so that parallel or repeated runs don't collide,
+// which is removed when the test ends
+struct TempFile(std::path::PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("henry_{}_{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
 #[test]
 fn test_fib() {
     let source = "fib_helper := |n: Int, x: Int, y: Int|: Int {
@@ -276,6 +294,52 @@ fn test_zipmap() {
     assert!(result);
 }
 
+#[test]
+fn test_type_ascription() {
+    assert_eq!(run_expect_value!("x := [1, 2] : Arr(Int) len(x)", Int), 2);
+    assert_eq!(run_expect_value!("1 + 2 : Int", Int), 3);
+    assert!(VM::new().interpret("[1, 2] : Arr(Float)").is_err());
+
+    let source = "
+    x := ([]): Arr(Str)
+    len(x + [\"henry\"])
+    ";
+    assert_eq!(run_expect_value!(source, Int), 1);
+    // without the parentheses, the annotation is the type of the elements
+    assert_eq!(run_expect_value!("x := []: Arr(Int) len(x + [[1, 2]])", Int), 1);
+    assert!(VM::new().interpret("x := []: Arr(Int) x + [1]").is_err());
+    assert!(VM::new().interpret("x := [] len(x)").is_err());
+}
+
+#[test]
+fn test_with_index() {
+    let source = "
+    x := with_index(|i: Int, x: Int| { i * x }, [5, 6, 7])
+    @x = [0, 6, 14]
+    ";
+    assert!(run_expect_value!(source, Bool));
+
+    let source = "
+    x := with_index(|i: Int, x: Int| { i + x }, 10 to 12)
+    @x = [10, 12, 14]
+    ";
+    assert!(run_expect_value!(source, Bool));
+
+    let source = "
+    f := |i: Int, s: Str| { len(s) + i }
+    sum(with_index(f, [\"henry\", \"lenry\", \"!\"]))
+    ";
+    assert_eq!(run_expect_value!(source, Int), 14);
+
+    // zipmap_indexed stops at the shortest input
+    let source = "
+    x := zipmap_indexed(|i: Int, a: Int, b: Str| { 100 * i + a + len(b) }, [1, 2, 3], [\"a\", \"bb\"])
+    @x = [2, 104]
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert!(VM::new().interpret("zipmap_indexed(|a: Int, b: Int| { a }, [1], [2])").is_err());
+}
+
 #[test]
 fn test_len() {
     assert_eq!(run_expect_value!("len(\"hello\")", Int), 5);
@@ -287,3 +351,1172 @@ fn test_len() {
     assert_eq!(run_expect_value!("len([]: Int)", Int), 0);
     assert_eq!(run_expect_value!("len(|x:Int|{x} -> []:Int)", Int), 0);
 }
+
+#[test]
+fn test_array_repeat() {
+    assert!(run_expect_value!("[0; 5] = [0, 0, 0, 0, 0]", Bool));
+    assert!(run_expect_value!("[1.5; 2] = [1.5, 1.5]", Bool));
+    assert!(run_expect_value!("[\"hi\"; 3] = [\"hi\", \"hi\", \"hi\"]", Bool));
+    assert_eq!(run_expect_value!("len([7; 100])", Int), 100);
+    assert_eq!(run_expect_value!("len([]: Int + [1; 0])", Int), 0);
+    assert!(VM::new().interpret("n := 3 [0; n]").is_err());
+}
+
+#[test]
+fn test_float_int_pow() {
+    assert_relative_eq!(run_expect_value!("pow(2.0, 0)", Float), 1.0);
+    assert_relative_eq!(run_expect_value!("pow(2.0, 1)", Float), 2.0);
+    assert_relative_eq!(run_expect_value!("pow(1.5, 5)", Float), 7.59375);
+    assert_relative_eq!(run_expect_value!("pow(2.0, -2)", Float), 0.25);
+    // exponents outside the i32 range are not truncated
+    assert!(run_expect_value!("is_inf(pow(2.0, 4294967297))", Bool));
+    assert_relative_eq!(run_expect_value!("pow(-1.0, 4294967296)", Float), 1.0);
+    assert_relative_eq!(run_expect_value!("pow(0.5, 4294967297)", Float), 0.0);
+}
+
+#[test]
+fn test_sub() {
+    assert!(run_expect_value!("sub([1, 2, 3, 4], 1, 3) = [2, 3]", Bool));
+    assert!(run_expect_value!("sub([1, 2, 3, 4], -2, 4) = [3, 4]", Bool));
+    assert!(run_expect_value!("sub([1, 2, 3, 4], 2, 100) = [3, 4]", Bool));
+    assert_eq!(run_expect_value!("len(sub([1, 2, 3, 4], 3, 1))", Int), 0);
+    assert!(run_expect_value!("sub([\"a\", \"b\", \"c\"], -10, -1) = [\"a\", \"b\"]", Bool));
+    assert_eq!(run_expect_value!("sub(\"héllo\", 1, 3)", Str), "él");
+    assert_eq!(run_expect_value!("sub(\"héllo\", -3, 10)", Str), "llo");
+    assert_eq!(run_expect_value!("sub(\"héllo\", 4, 2)", Str), "");
+}
+
+#[test]
+fn test_chars_bytes() {
+    assert_eq!(run_expect_value!("len(chars(\"Χένρι😀\"))", Int), 6);
+    assert_eq!(run_expect_value!("len(bytes(\"Χένρι😀\"))", Int), 14);
+    assert!(run_expect_value!("chars(\"hé\") = [\"h\", \"é\"]", Bool));
+    assert!(run_expect_value!("bytes(\"hé\") = [104, 195, 169]", Bool));
+    let source = "
+    @filter(|c: Str| { c != \"l\" }, chars(\"hello\")) = [\"h\", \"e\", \"o\"]
+    ";
+    assert!(run_expect_value!(source, Bool));
+}
+
+#[test]
+fn test_native_arity_underflow() {
+    static ADD: values::NativeFunction = values::NativeFunction {
+        name: "add",
+        arity: 2,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            vm.stack.push(values::Value::from_i64(unsafe { args[0].i + args[1].i }));
+            Ok(())
+        },
+    };
+    let mut vm = VM::new();
+    vm.stack.push(values::Value::from_i64(1));
+    assert!(vm.call_native_function(&ADD).is_err());
+    // stack is left untouched
+    assert_eq!(vm.stack.len(), 1);
+
+    vm.stack.push(values::Value::from_i64(2));
+    assert!(vm.call_native_function(&ADD).is_ok());
+    assert_eq!(unsafe { vm.stack.pop().unwrap().i }, 3);
+}
+
+#[test]
+fn test_cache() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static TICK: values::NativeFunction = values::NativeFunction {
+        name: "tick",
+        arity: 1,
+        heap_arity: 0,
+        return_is_heap: false,
+        function: |vm, args, _heap_args| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            vm.stack.push(args[0]);
+            Ok(())
+        },
+    };
+
+    let mut vm = VM::new();
+    vm.typecontext.borrow_mut().insert("tick[Int]".to_string(), Type::Func(vec![Type::Int], Box::new(Type::Int)));
+    vm.heap_globals.insert("tick[Int]".to_string(), values::HeapValue::NativeFunction(&TICK));
+
+    let source = "
+    x := cache(|i: Int| { tick(i) } -> 1 to 10)
+    sum(x) + sum(x)
+    ";
+    match vm.interpret(source).unwrap() {
+        values::TaggedValue::Int(x) => assert_eq!(x, 110),
+        _ => panic!("Should be an Int"),
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 10);
+
+    let source = "
+    x := cache(|s: Str| { s + \"!\" } -> [\"a\", \"b\"])
+    @x + @x = [\"a!\", \"b!\", \"a!\", \"b!\"]
+    ";
+    assert!(run_expect_value!(source, Bool));
+}
+
+#[test]
+fn test_import() {
+    let dir = std::env::temp_dir().join(format!("henrylang_test_import_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("utils.hl"), "
+    square := |x: Int| { x * x }
+    Point := type { x: Int, y: Int }
+    ").unwrap();
+    std::fs::write(dir.join("main.hl"), "
+    import \"utils.hl\"
+    import \"utils.hl\"
+    p := Point(3, 4)
+    square(p.x) + square(p.y)
+    ").unwrap();
+    std::fs::write(dir.join("cycle_a.hl"), "import \"cycle_b.hl\" 1").unwrap();
+    std::fs::write(dir.join("cycle_b.hl"), "import \"cycle_a.hl\" 2").unwrap();
+    std::fs::write(dir.join("collision.hl"), "
+    import \"utils.hl\"
+    Point := 1
+    Point
+    ").unwrap();
+
+    let result = VM::new().interpret_file(dir.join("main.hl").to_str().unwrap());
+    let cycle = VM::new().interpret_file(dir.join("cycle_a.hl").to_str().unwrap());
+    let collision = VM::new().interpret_file(dir.join("collision.hl").to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    match result.unwrap() {
+        values::TaggedValue::Int(x) => assert_eq!(x, 25),
+        _ => panic!("Should be an Int"),
+    }
+    assert!(cycle.is_err());
+    assert!(collision.is_err());
+}
+
+#[test]
+fn test_match_maybe() {
+    let source = "
+    f := |m: Maybe(Int)| { match m { some(x) => x + 1, null => 0 } }
+    f(some(41)) + f({}: Int)
+    ";
+    assert_eq!(run_expect_value!(source, Int), 42);
+    let source = "
+    greet := |m: Maybe(Str)| { match m { null => \"nobody\", some(s) => \"hi \" + s } }
+    greet(some(\"henry\")) + \", \" + greet({}: Str)
+    ";
+    assert_eq!(run_expect_value!(source, Str), "hi henry, nobody");
+    // bound variable shadows outer variables only within the some arm
+    let source = "
+    x := 10
+    match some([1, 2, 3]) { some(x) => len(x), null => x } + x
+    ";
+    assert_eq!(run_expect_value!(source, Int), 13);
+    let source = "
+    match some(some(2)) { some(a) => match a { some(b) => b * 10, null => -1 }, null => 0 }
+    ";
+    assert_eq!(run_expect_value!(source, Int), 20);
+
+    assert!(VM::new().interpret("match some(1) { some(x) => x, null => 1.0 }").is_err());
+    assert!(VM::new().interpret("match 1 { some(x) => x, null => 0 }").is_err());
+}
+
+#[test]
+fn test_reverse() {
+    assert!(run_expect_value!("reverse([1, 2, 3]) = [3, 2, 1]", Bool));
+    assert_eq!(run_expect_value!("x := reverse([\"a\", \"b\", \"c\"]) x(0) + x(2)", Str), "ca");
+    assert_eq!(run_expect_value!("len(reverse(([]): Arr(Float)))", Int), 0);
+}
+
+#[test]
+fn test_int_literal_range() {
+    assert_eq!(run_expect_value!("2147483648", Int), 2147483648);
+    assert_eq!(run_expect_value!("9223372036854775807", Int), i64::MAX);
+    let err = VM::new().interpret("9223372036854775808").unwrap_err();
+    assert!(format!("{:?}", err).contains("Integer literal exceeds 64-bit range"));
+}
+
+#[test]
+fn test_max_by() {
+    let source = "
+    Person := type { name: Str, age: Int }
+    people := [Person(\"Ann\", 30), Person(\"Bo\", 40), Person(\"Cy\", 40), Person(\"Di\", 20)]
+    default := Person(\"\", 0)
+    oldest := unwrap(max_by(people, |p: Person| { p.age }), default)
+    youngest := unwrap(min_by(people, |p: Person| { p.age }), default)
+    oldest.name + youngest.name
+    ";
+    // ties are broken by first occurrence
+    assert_eq!(run_expect_value!(source, Str), "BoDi");
+    assert_eq!(run_expect_value!("unwrap(max_by(0 to 5, |x: Int| { mod(x, 3) }), -1)", Int), 2);
+    assert_eq!(run_expect_value!("unwrap(min_by([2.5, -1.0, 3.0], |x: Float| { x * x }), 0.0)", Float), -1.0);
+    assert!(!run_expect_value!("issome(max_by(([]): Arr(Int), |x: Int| { x }))", Bool));
+    assert!(VM::new().interpret("max_by([1, 2], |x: Int| { x > 1 })").is_err());
+    // NaN keys are skipped, wherever they appear
+    assert_eq!(run_expect_value!("unwrap(max_by([-1.0, 4.0, 9.0], |x: Float| { sqrt(x) }), 0.0)", Float), 9.0);
+    assert_eq!(run_expect_value!("unwrap(min_by([-1.0, 4.0, 9.0], |x: Float| { sqrt(x) }), 0.0)", Float), 4.0);
+    assert!(!run_expect_value!("issome(max_by([-1.0, -4.0], |x: Float| { sqrt(x) }))", Bool));
+}
+
+#[test]
+fn test_intersperse() {
+    assert!(run_expect_value!("@intersperse([1, 2, 3], 0) = [1, 0, 2, 0, 3]", Bool));
+    assert!(run_expect_value!("@intersperse(5 to 5, 0) = [5]", Bool));
+    assert_eq!(run_expect_value!("len(intersperse(([]): Arr(Float), 1.0))", Int), 0);
+    let source = "reduce(|acc: Str, x: Str| { acc + x }, intersperse([\"a\", \"b\", \"c\"], \", \"), \"\")";
+    assert_eq!(run_expect_value!(source, Str), "a, b, c");
+}
+
+#[test]
+fn test_check_file() {
+    let file = TempFile::new("test_check_file.hl", "good := |x: Int| { x + 1 }\nbad := good(\"one\")\n");
+    let bindings = VM::new().check_file(file.0.to_str().unwrap()).unwrap();
+    assert_eq!(bindings.len(), 2);
+    assert_eq!(bindings[0].0, "good");
+    assert_eq!(bindings[0].1, Ok(Type::Func(vec![Type::Int], Box::new(Type::Int))));
+    assert_eq!(bindings[1].0, "bad");
+    assert!(bindings[1].1.is_err());
+}
+
+#[test]
+fn test_zip_longest() {
+    let source = "@zip_longest([1, 2, 3, 4], [10, 20], 0, 100, |x: Int, y: Int| { x + y }) = [11, 22, 103, 104]";
+    assert!(run_expect_value!(source, Bool));
+    let source = "@zip_longest([1], [\"a\", \"b\"], 0, \"\", |n: Int, s: Str| { n }) = [1, 0]";
+    assert!(run_expect_value!(source, Bool));
+    let source = "reduce(|acc: Str, x: Str| { acc + x }, zip_longest(0 to 2, [\"a\"], 0, \"-\", |n: Int, s: Str| { s }), \"\")";
+    assert_eq!(run_expect_value!(source, Str), "a--");
+    assert!(VM::new().interpret("zip_longest([1], [2], 0, 0.0, |x: Int, y: Int| { x })").is_err());
+}
+
+#[test]
+fn test_set_field() {
+    let source = "P := type {name: Str, age: Int}\np := P(\"Ann\", 30)\nq := set_field(p, \"age\", 31)\np.age + q.age";
+    assert_eq!(run_expect_value!(source, Int), 61);
+    let source = "P := type {name: Str, age: Int}\nset_field(P(\"Ann\", 30), \"na\" + \"me\", \"Bo\").name";
+    assert_eq!(run_expect_value!(source, Str), "Bo");
+    assert!(VM::new().interpret("P := type {name: Str, age: Int}\nset_field(P(\"Ann\", 30), \"height\", 3).age").is_err());
+    assert!(VM::new().interpret("P := type {name: Str, age: Int}\nset_field(P(\"Ann\", 30), \"name\", 3).age").is_err());
+}
+
+#[test]
+fn test_with() {
+    let source = "
+    P := type { x: Int, y: Int, name: Str }
+    p := P(0, 0, \"origin\")
+    q := p with {x: 1} with {y: 2}
+    q.x = 1 and q.y = 2 and q.name = \"origin\" and p.x = 0
+    ";
+    assert!(run_expect_value!(source, Bool));
+    let source = "
+    P := type { x: Int, y: Int, name: Str }
+    q := P(0, 0, \"a\") with {x: 3, name: \"b\"}
+    if q.name = \"b\" { q.x + q.y } else { 0 }
+    ";
+    assert_eq!(run_expect_value!(source, Int), 3);
+    assert!(VM::new().interpret("P := type { x: Int } P(0) with {z: 1}").is_err());
+    assert!(VM::new().interpret("P := type { x: Int } P(0) with {x: \"a\"}").is_err());
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(run_expect_value!("format(\"{1} then {0}\", [\"a\", \"b\"])", Str), "b then a");
+    assert_eq!(run_expect_value!("format(\"{0}{0}\", [\"ab\"])", Str), "abab");
+    assert_eq!(run_expect_value!("format(\"{{{0}}} {{}}\", [\"x\"])", Str), "{x} {}");
+    assert!(VM::new().interpret("format(\"{2}\", [\"a\", \"b\"])").is_err());
+    assert!(VM::new().interpret("format(\"a } b\", [\"a\"])").is_err());
+}
+
+#[test]
+fn test_running_stats() {
+    assert!(run_expect_value!("@running_sum(1 to 4) = [1, 3, 6, 10]", Bool));
+    assert!(run_expect_value!("@running_max([3, 1, 4, 1, 5]) = [3, 3, 4, 4, 5]", Bool));
+    assert!(run_expect_value!("@running_sum([0.5, 1.5, 2.0]) = [0.5, 2.0, 4.0]", Bool));
+    assert!(run_expect_value!("@running_max(|x: Float| { -x } -> [2.0, -1.0, 0.0]) = [-2.0, 1.0, 1.0]", Bool));
+    assert_eq!(run_expect_value!("len(running_sum(([]): Arr(Int)))", Int), 0);
+}
+
+#[test]
+fn test_collect_limit() {
+    let mut vm = VM::new();
+    vm.set_collect_limit(Some(10));
+    assert!(vm.interpret("@(1 to 2000000000)").is_err());
+    let mut vm = VM::new();
+    vm.set_collect_limit(Some(10));
+    assert!(matches!(vm.interpret("len(@(1 to 10))").unwrap(), TaggedValue::Int(10)));
+    let mut vm = VM::new();
+    vm.set_collect_limit(Some(2));
+    assert!(vm.interpret("@(|x: Int| { \"a\" } -> 0 to 2)").is_err());
+}
+
+#[test]
+fn test_bool_int_conversions() {
+    assert_eq!(run_expect_value!("int(true)", Int), 1);
+    assert_eq!(run_expect_value!("int(false)", Int), 0);
+    assert!(run_expect_value!("bool(2) = true", Bool));
+    assert!(!run_expect_value!("bool(0)", Bool));
+    assert!(run_expect_value!("bool(-1)", Bool));
+}
+
+#[test]
+fn test_flat_map() {
+    assert!(run_expect_value!("@flat_map(|x: Int| { [x, x * 10] }, [1, 2, 3]) = [1, 10, 2, 20, 3, 30]", Bool));
+    assert!(run_expect_value!("@flat_map(|n: Int| { @(1 to n) }, 0 to 2) = [1, 0, 1, 1, 2]", Bool));
+    assert!(run_expect_value!("@flat_map(|s: Str| { [s, s + \"!\"] }, [\"a\", \"b\"]) = [\"a\", \"a!\", \"b\", \"b!\"]", Bool));
+    assert_eq!(run_expect_value!("len(flat_map(|x: Int| { [x] }, ([]): Arr(Int)))", Int), 0);
+    assert!(VM::new().interpret("flat_map(|x: Int| { x }, [1, 2])").is_err());
+}
+
+#[test]
+fn test_interpret_typed() {
+    let (value, typ) = VM::new().interpret_typed("@(|x: Int| { x * 2 } -> [1, 2, 3])").unwrap();
+    assert_eq!(typ, Type::Arr(Box::new(Type::Int)));
+    assert_eq!(value.to_string(), "[2, 4, 6]");
+    let (_, typ) = VM::new().interpret_typed("1.5").unwrap();
+    assert_eq!(typ, Type::Float);
+}
+
+#[test]
+fn test_dedup() {
+    assert!(run_expect_value!("@dedup([1, 1, 2, 2, 1]) = [1, 2, 1]", Bool));
+    assert!(run_expect_value!("@dedup([\"a\", \"a\", \"b\", \"a\", \"a\"]) = [\"a\", \"b\", \"a\"]", Bool));
+    assert!(run_expect_value!("@dedup(|x: Int| { x / 3 } -> 0 to 10) = [0, 1, 2, 3]", Bool));
+    assert_eq!(run_expect_value!("len(dedup([true, true, false, false, true]))", Int), 3);
+    assert_eq!(run_expect_value!("len(dedup(([]): Arr(Float)))", Int), 0);
+}
+
+#[test]
+fn test_count_where() {
+    assert_eq!(run_expect_value!("count_where(|x: Int| { mod(x, 2) = 0 }, 0 to 10)", Int), 6);
+    assert_eq!(run_expect_value!("count_where(|x: Int| { x > 0 }, ([]): Arr(Int))", Int), 0);
+    assert_eq!(run_expect_value!("count_where(|s: Str| { s = \"a\" }, [\"a\", \"b\", \"a\"])", Int), 2);
+    assert!(VM::new().interpret("count_where(|x: Int| { x }, [1, 2])").is_err());
+}
+
+#[test]
+fn test_destructured_params() {
+    let source = "
+    Point := type { x: Int, y: Int }
+    f := |{x, y}: Point| { x + y }
+    f(Point(3, 4))
+    ";
+    assert_eq!(run_expect_value!(source, Int), 7);
+    let source = "
+    Point := type { x: Float, y: Float }
+    scale := |s: Float, {y}: Point| { s * y }
+    scale(2.0, Point(1.0, 4.0))
+    ";
+    assert_eq!(run_expect_value!(source, Float), 8.0);
+    assert!(VM::new().interpret("Point := type { x: Int } f := |{z}: Point| { z } f(Point(1))").is_err());
+    assert!(VM::new().interpret("f := |{x}: Int| { x } f(1)").is_err());
+}
+
+#[test]
+fn test_find() {
+    assert_eq!(run_expect_value!("unwrap(find(\"hello\", \"llo\"), -1)", Int), 2);
+    // character indices differ from byte indices in multibyte strings
+    assert_eq!(run_expect_value!("unwrap(find(\"héllo wörld\", \"wö\"), -1)", Int), 6);
+    assert_eq!(run_expect_value!("unwrap(find(\"日本語テキスト\", \"テ\"), -1)", Int), 3);
+    assert_eq!(
+        run_expect_value!("s := \"日本語テキスト\" sub(s, unwrap(find(s, \"テ\"), 0), 7)", Str),
+        "テキスト"
+    );
+    assert!(!run_expect_value!("issome(find(\"abc\", \"ü\"))", Bool));
+}
+
+#[test]
+fn test_float_array_equality() {
+    assert!(run_expect_value!("[0.0, 1.0] = [-0.0, 1.0]", Bool));
+    assert!(!run_expect_value!("nan := 0.0 / 0.0 [nan] = [nan]", Bool));
+    assert!(run_expect_value!("nan := 0.0 / 0.0 [nan] != [nan]", Bool));
+    assert!(run_expect_value!("[1.5, 2.5] != [1.5]", Bool));
+}
+
+#[test]
+fn test_reduce_while() {
+    // stops partway through a very long range
+    let source = "
+    add_while_small := |acc: Int, x: Int| { if acc + x > 10 { {}: Int } else { some(acc + x) } }
+    reduce_while(add_while_small, 0, 1 to 1000000000)
+    ";
+    assert_eq!(run_expect_value!(source, Int), 10);
+    assert_eq!(run_expect_value!("reduce_while(|acc: Int, x: Int| { some(acc + x) }, 0, [1, 2, 3])", Int), 6);
+    assert_eq!(run_expect_value!("reduce_while(|acc: Int, x: Int| { some(acc + x) }, 5, ([]): Arr(Int))", Int), 5);
+    let source = "
+    reduce_while(|acc: Str, x: Str| { if x = \"stop\" { {}: Str } else { some(acc + x) } }, \"\", [\"a\", \"b\", \"stop\", \"c\"])
+    ";
+    assert_eq!(run_expect_value!(source, Str), "ab");
+    assert!(VM::new().interpret("reduce_while(|acc: Int, x: Int| { acc + x }, 0, [1])").is_err());
+}
+
+#[test]
+fn test_transpose() {
+    assert!(run_expect_value!("transpose([[1, 2, 3], [4, 5, 6]]) = [[1, 4], [2, 5], [3, 6]]", Bool));
+    assert_eq!(run_expect_value!("m := transpose([[1, 2, 3], [4, 5, 6]]) len(m) * 10 + len(m(0))", Int), 32);
+    assert!(run_expect_value!("transpose([[\"a\", \"b\"], [\"c\", \"d\"]]) = [[\"a\", \"c\"], [\"b\", \"d\"]]", Bool));
+    assert_eq!(run_expect_value!("len(transpose(([]): Arr(Arr(Int))))", Int), 0);
+    assert!(VM::new().interpret("transpose([[1, 2], [3]])").is_err());
+}
+
+#[test]
+fn test_error_kinds() {
+    let kind = |source: &str| VM::new().interpret(source).unwrap_err().kind();
+    assert_eq!(kind("(1 + "), ErrorKind::Parse);
+    assert_eq!(kind("y + 1"), ErrorKind::UndefinedVariable);
+    assert_eq!(kind("f := |x: Int| { x + zz } f(1)"), ErrorKind::UndefinedVariable);
+    assert_eq!(kind("1 + \"a\""), ErrorKind::TypeMismatch);
+    assert_eq!(kind("99999999999999999999"), ErrorKind::Other);
+    assert_eq!(kind("[1, 2](3)"), ErrorKind::IndexOutOfBounds);
+    assert_eq!(kind("x := 0 1 / x"), ErrorKind::DivisionByZero);
+    assert_eq!(kind("mod(3, 0)"), ErrorKind::DivisionByZero);
+    let err = VM::new().interpret("[1, 2](3)").unwrap_err();
+    assert!(matches!(err, InterpreterError::RuntimeError(..)));
+    assert_eq!(err.message(), "Index 3 out of bounds for array of length 2");
+}
+
+#[test]
+fn test_repeat_str() {
+    assert_eq!(run_expect_value!("repeat_str(\"ab\", 3)", Str), "ababab");
+    assert_eq!(run_expect_value!("repeat_str(\"ab\", 0)", Str), "");
+    assert_eq!(run_expect_value!("repeat_str(\"é日\", 2)", Str), "é日é日");
+    assert_eq!(run_expect_value!("len(repeat_str(\"-\", 10))", Int), 10);
+    assert!(VM::new().interpret("repeat_str(\"ab\", -1)").is_err());
+}
+
+#[test]
+fn test_scan() {
+    assert!(run_expect_value!("add := |a: Int, b: Int| { a + b } @scan(add, 0, 1 to 4) = [1, 3, 6, 10]", Bool));
+    assert!(run_expect_value!("@scan(|acc: Str, x: Str| { acc + x }, \"\", [\"a\", \"b\", \"c\"]) = [\"a\", \"ab\", \"abc\"]", Bool));
+    assert_eq!(run_expect_value!("len(scan(|acc: Int, x: Int| { acc + x }, 5, ([]): Arr(Int)))", Int), 0);
+    assert_eq!(run_expect_value!("sum(scan(|acc: Int, x: Str| { acc + len(x) }, 0, [\"a\", \"bb\"]))", Int), 4);
+    assert!(run_expect_value!("@take(scan(|acc: Str, x: Int| { acc + \"x\" }, \"\", 1 to 3), 2) = [\"x\", \"xx\"]", Bool));
+
+    // run through the binary to see that only the consumed prefix of the iterator is evaluated
+    let source = "@take(scan(|acc: Int, x: Int| { print(x) acc + x }, 0, 1 to 1000000000), 3)";
+    let file = TempFile::new("test_scan.hl", source);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_henrylang")).arg(&file.0).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), ["1", "2", "3", "[1, 3, 6]"]);
+}
+
+#[test]
+fn test_numeric_suffixes() {
+    let (value, typ) = VM::new().interpret_typed("[1f, 2f]").unwrap();
+    assert_eq!(typ, Type::Arr(Box::new(Type::Float)));
+    assert_eq!(value.to_string(), "[1.0, 2.0]");
+    assert_relative_eq!(run_expect_value!("5f + 1.5", Float), 6.5);
+    assert_relative_eq!(run_expect_value!("1.5f * 2f", Float), 3.0);
+    assert_eq!(run_expect_value!("5i + 2", Int), 7);
+    assert!(VM::new().interpret("5i.0").is_err());
+    assert!(VM::new().interpret("1.5i").is_err());
+    assert!(VM::new().interpret("5f + 1").is_err());
+}
+
+#[test]
+fn test_chunk_by() {
+    assert!(run_expect_value!("@chunk_by(|x: Int| { x }, [1, 1, 2, 3, 3, 3]) = [[1, 1], [2], [3, 3, 3]]", Bool));
+    assert!(run_expect_value!("@chunk_by(|x: Int| { x / 3 }, 0 to 7) = [[0, 1, 2], [3, 4, 5], [6, 7]]", Bool));
+    assert!(run_expect_value!("@chunk_by(|s: Str| { s }, [\"a\", \"a\", \"b\"]) = [[\"a\", \"a\"], [\"b\"]]", Bool));
+    assert_eq!(run_expect_value!("len(chunk_by(|x: Int| { x > 2 }, [1, 2, 3, 1]))", Int), 3);
+    assert_eq!(run_expect_value!("len(chunk_by(|x: Int| { x }, ([]): Arr(Int)))", Int), 0);
+    // key function must return an Int, Str, or Bool
+    assert!(VM::new().interpret("chunk_by(|x: Int| { 1.0 }, [1, 2])").is_err());
+
+    // run through the binary to see that each upstream element is evaluated once, and keyed by the same value it yields
+    let source = "@chunk_by(|x: Int| { x / 2 }, |x: Int| { print(x) x } -> 1 to 3)";
+    let file = TempFile::new("test_chunk_by.hl", source);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_henrylang")).arg(&file.0).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), ["1", "2", "3", "[[1], [2, 3]]"]);
+}
+
+#[test]
+fn test_closures_in_arrays() {
+    let source = "
+    n := 3
+    fns := [|x: Int| { x + n }, |x: Int| { x * n }]
+    fns(0)(5) * 100 + fns(1)(5)
+    ";
+    assert_eq!(run_expect_value!(source, Int), 815);
+    // named functions can be stored without template types
+    let source = "
+    inc := |x: Int| { x + 1 }
+    double := |x: Int| { x * 2 }
+    table := [inc, double]
+    i := 1
+    table(i)(table(0)(4))
+    ";
+    assert_eq!(run_expect_value!(source, Int), 10);
+}
+
+#[test]
+fn test_first_last() {
+    assert_eq!(run_expect_value!("unwrap(first([4, 5, 6]), 0)", Int), 4);
+    assert_eq!(run_expect_value!("unwrap(last([4, 5, 6]), 0)", Int), 6);
+    assert_eq!(run_expect_value!("unwrap(last([\"a\", \"b\"]), \"\")", Str), "b");
+    assert_eq!(run_expect_value!("unwrap(first(\"héllo\"), \"\") + unwrap(last(\"héllo\"), \"\")", Str), "ho");
+    assert!(!run_expect_value!("issome(first(([]): Arr(Float)))", Bool));
+    assert!(!run_expect_value!("issome(last(\"\"))", Bool));
+}
+
+#[test]
+fn test_hypot_atan2() {
+    assert_eq!(run_expect_value!("hypot(3.0, 4.0)", Float), 5.0);
+    assert!(run_expect_value!("hypot(3.0, 4.0) = 5.0", Bool));
+    assert_eq!(run_expect_value!("atan2(1.0, -1.0)", Float), 0.75 * std::f64::consts::PI);
+    assert_eq!(run_expect_value!("atan2(-1.0, 0.0)", Float), -std::f64::consts::FRAC_PI_2);
+}
+
+#[test]
+fn test_sum_by() {
+    let source = "
+    Order := type { id: Int, amount: Float, qty: Int }
+    orders := [Order(1, 2.5, 3), Order(2, 4.0, 1)]
+    sum_by(|o: Order| { o.amount }, orders) + float(sum_by(|o: Order| { o.qty }, orders))
+    ";
+    assert_eq!(run_expect_value!(source, Float), 10.5);
+    assert_eq!(run_expect_value!("sum_by(|x: Int| { x * x }, 1 to 4)", Int), 30);
+    assert_eq!(run_expect_value!("sum_by(|x: Int| { x }, ([]): Arr(Int))", Int), 0);
+    assert!(VM::new().interpret("sum_by(|x: Int| { \"a\" }, [1])").is_err());
+}
+
+#[test]
+fn test_lazy() {
+    // mapping directly over an array calls the function on every element right away...
+    assert!(VM::new().interpret("y := |x: Int| { 10 / x } -> [1, 0] 5").is_err());
+    // ...but mapping over lazy(arr) defers the calls until the iterator is consumed
+    assert_eq!(run_expect_value!("y := |x: Int| { 10 / x } -> lazy([1, 0]) 5", Int), 5);
+    assert_eq!(run_expect_value!("y := filter(|x: Int| { 10 / x > 1 }, lazy([1, 0])) 5", Int), 5);
+    assert!(run_expect_value!("@(|x: Int| { 10 / x } -> lazy([1, 2, 5])) = [10, 5, 2]", Bool));
+    assert!(run_expect_value!("@lazy([\"a\", \"b\"]) = [\"a\", \"b\"]", Bool));
+    // only the elements that are consumed are mapped
+    assert!(run_expect_value!("@take(|x: Int| { 10 / x } -> lazy([1, 2, 0]), 2) = [10, 5]", Bool));
+    assert!(run_expect_value!("@take(|x: Int| { 10 / x } -> 2 to 0, 2) = [5, 10]", Bool));
+    assert!(VM::new().interpret("@take(|x: Int| { 10 / x } -> [1, 2, 0], 2)").is_err());
+}
+
+#[test]
+fn test_ignore_case() {
+    assert!(run_expect_value!("eq_ignore_case(\"Hello\", \"hELLO\")", Bool));
+    assert!(run_expect_value!("eq_ignore_case(\"ÉCOLE\", \"école\")", Bool));
+    assert!(!run_expect_value!("eq_ignore_case(\"école\", \"ecole\")", Bool));
+    assert!(run_expect_value!("contains_ignore_case(\"Grüße aus MÜNCHEN\", \"münchen\")", Bool));
+    assert!(!run_expect_value!("contains_ignore_case(\"Grüße\", \"GRUSSE\")", Bool));
+}
+
+#[test]
+fn test_cumulative_product() {
+    assert!(run_expect_value!("@cumulative_product(1 to 4) = [1, 2, 6, 24]", Bool));
+    assert!(run_expect_value!("@cumulative_product([1.5, 2.0, 0.5]) = [1.5, 3.0, 1.5]", Bool));
+    assert_eq!(run_expect_value!("len(cumulative_product(([]): Arr(Float)))", Int), 0);
+}
+
+#[test]
+fn test_first_or() {
+    assert_eq!(run_expect_value!("first_or(5 to 10, 0)", Int), 5);
+    assert_eq!(run_expect_value!("first_or(filter(|x: Int| { x > 100 }, 0 to 10), -1)", Int), -1);
+    assert_eq!(run_expect_value!("first_or([\"a\", \"b\"], \"z\")", Str), "a");
+    assert_eq!(run_expect_value!("first_or(([]): Arr(Str), \"z\")", Str), "z");
+    assert_eq!(run_expect_value!("first_or(|x: Int| { float(x) / 2.0 } -> 3 to 4, 0.0)", Float), 1.5);
+}
+
+#[test]
+fn test_to_json() {
+    let source = "
+    Point := type { x: Int, y: Float }
+    Shape := type { name: Str, points: Arr(Point), tags: Arr(Str), parent: Maybe(Str), closed: Bool }
+    to_json(Shape(\"tri\\angle\", [Point(0, 0.5), Point(1, -2.0)], [\"a\", \"b\"], {}: Str, true))
+    ";
+    assert_eq!(
+        run_expect_value!(source, Str),
+        r#"{"closed":true,"name":"tri\\angle","parent":null,"points":[{"x":0,"y":0.5},{"x":1,"y":-2.0}],"tags":["a","b"]}"#
+    );
+    assert_eq!(run_expect_value!("to_json(1 to 3)", Str), "[1,2,3]");
+    assert_eq!(run_expect_value!("to_json(some(\"hi\"))", Str), "\"hi\"");
+    assert_eq!(run_expect_value!("to_json(\"line\nbreak\")", Str), "\"line\\nbreak\"");
+    assert!(VM::new().interpret("to_json(|x: Int| { x })").is_err());
+}
+
+#[test]
+fn test_from_json() {
+    let source = "
+    Point := type { x: Int, y: Float }
+    Shape := type { name: Str, points: Arr(Point), parent: Maybe(Str) }
+    s := Shape(\"tri\", [Point(0, 0.5), Point(1, -2.0)], {}: Str)
+    back := from_json(to_json(s)): Maybe(Shape)
+    to_json(unwrap(back, Shape(\"\", ([]): Arr(Point), {}: Str))) = to_json(s)
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert_eq!(run_expect_value!("unwrap(from_json(\" [1, 2,3] \"): Maybe(Arr(Int)), ([]): Arr(Int))", Arr).len(), 3);
+    // mismatched types and invalid JSON both give null
+    assert!(!run_expect_value!("issome(from_json(\"[1, 2.5]\"): Maybe(Arr(Int)))", Bool));
+    assert!(!run_expect_value!("issome(from_json(\"[1,\"): Maybe(Arr(Int)))", Bool));
+    // deeply nested input is rejected instead of overflowing the stack
+    assert!(!run_expect_value!("issome(from_json(repeat_str(\"[\", 200000)): Maybe(Arr(Int)))", Bool));
+    assert!(run_expect_value!("issome(from_json(\"[[[1]]]\"): Maybe(Arr(Arr(Arr(Int)))))", Bool));
+    assert!(VM::new().interpret("from_json(\"1\")").is_err());
+    assert!(VM::new().interpret("from_json(\"1\"): Int").is_err());
+}
+
+#[test]
+fn test_seeded_rand() {
+    let source = "to_json(@(|i: Int| { [float(rand_int(1, 6)), rand_float()] } -> 1 to 20))";
+    let run_seeded = |seed: u64| {
+        let mut vm = VM::new();
+        vm.seed(seed);
+        let result = vm.interpret(source).unwrap();
+        let result: String = result.try_into().unwrap();
+        result
+    };
+    assert_eq!(run_seeded(7), run_seeded(7));
+    assert_ne!(run_seeded(7), run_seeded(8));
+
+    let source = "
+    rolls := @(|i: Int| { rand_int(-2, 2) } -> 1 to 200)
+    count_where(|x: Int| { x < -2 or x > 2 }, rolls) = 0
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert!(run_expect_value!("x := rand_float() x >= 0.0 and x < 1.0", Bool));
+    assert!(VM::new().interpret("rand_int(3, 2)").is_err());
+}
+
+#[test]
+fn test_lines_words() {
+    // \r\n line endings are accepted, and a trailing newline doesn't add an empty line
+    let source = "lines(\"one\r\ntwo\n\nthree\n\") = [\"one\", \"two\", \"\", \"three\"]";
+    assert!(run_expect_value!(source, Bool));
+    assert!(run_expect_value!("len(lines(\"\")) = 0", Bool));
+    let source = "words(\"  the   quick\tbrown\n fox \") = [\"the\", \"quick\", \"brown\", \"fox\"]";
+    assert!(run_expect_value!(source, Bool));
+    assert!(run_expect_value!("len(words(\"   \")) = 0", Bool));
+}
+
+#[test]
+fn test_error_display() {
+    let err = VM::new().interpret("f := |x: Int| { [1](x) }\nf(3)").unwrap_err();
+    assert_eq!(err.trace().len(), 2);
+    assert!(err.trace()[1].contains("f[Int]"));
+    let display = err.to_string();
+    assert!(display.starts_with("Function call trace:\n"));
+    assert!(display.contains(&err.trace()[1]));
+    assert!(display.ends_with("Runtime error: Index 3 out of bounds for array of length 1"));
+
+    let err = VM::new().interpret("y + 1").unwrap_err();
+    assert!(err.trace().is_empty());
+    assert!(err.to_string().starts_with("Compile error: "));
+}
+
+#[test]
+fn test_sort_by_key() {
+    let source = "
+    Person := type { name: Str, age: Int }
+    people := [Person(\"Cy\", 1), Person(\"Ann\", 2), Person(\"Bo\", 3), Person(\"Ann\", 4)]
+    sorted := sort_by_key(people, |p: Person| { p.name })
+    @(|p: Person| { p.age } -> sorted)
+    ";
+    // ties keep their original order
+    assert!(run_expect_value!(&format!("{} = [2, 4, 3, 1]", source), Bool));
+    assert!(run_expect_value!("sort_by_key(1 to 4, |x: Int| { -x }) = [4, 3, 2, 1]", Bool));
+    // NaN keys go last
+    let sorted = run_expect_value!("sort_by_key([-1.0, 4.0, 1.0], |x: Float| { sqrt(x) })", Arr);
+    assert_eq!(sorted.len(), 3);
+    assert_relative_eq!(sorted[2].as_float().unwrap(), -1.0);
+    assert!(VM::new().interpret("sort_by_key([1, 2], |x: Int| { x > 1 })").is_err());
+}
+
+#[test]
+fn test_count_str() {
+    assert_eq!(run_expect_value!("count_str(\"banana\", \"an\")", Int), 2);
+    // occurrences don't overlap
+    assert_eq!(run_expect_value!("count_str(\"aaaa\", \"aa\")", Int), 2);
+    assert_eq!(run_expect_value!("count_str(\"banana\", \"x\")", Int), 0);
+    assert!(VM::new().interpret("count_str(\"banana\", \"\")").is_err());
+}
+
+#[test]
+fn test_max_call_depth() {
+    let run_limited = |source: &str| {
+        let mut vm = VM::new();
+        vm.set_max_call_depth(Some(50));
+        vm.interpret(source)
+    };
+    assert_eq!(run_limited("f := |n: Int|: Int { if n = 0 { 0 } else { 1 + f(n - 1) } } f(40)").unwrap().as_int(), Some(40));
+
+    let err = run_limited("f := |n: Int|: Int { if n = 0 { 0 } else { 1 + f(n - 1) } } f(100)").unwrap_err();
+    assert!(err.message().starts_with("Maximum call depth of 50 exceeded when calling f[Int]"));
+    assert!(err.message().contains("tail-call form"));
+
+    let err = run_limited("g := |n: Int, acc: Int|: Int { if n = 0 { acc } else { g(n - 1, acc + 1) } } g(100, 0)").unwrap_err();
+    assert!(err.message().contains("in tail position"));
+}
+
+#[test]
+fn test_windows() {
+    assert!(run_expect_value!("@windows(1 to 5, 3) = [[1, 2, 3], [2, 3, 4], [3, 4, 5]]", Bool));
+    assert!(run_expect_value!("@windows([\"a\", \"b\", \"c\"], 2) = [[\"a\", \"b\"], [\"b\", \"c\"]]", Bool));
+    // too few elements for a single window
+    assert_eq!(run_expect_value!("@windows(1 to 2, 3)", Arr).len(), 0);
+    assert!(VM::new().interpret("windows(1 to 5, 0)").is_err());
+}
+
+#[test]
+fn test_xor() {
+    assert!(!run_expect_value!("true xor true", Bool));
+    assert!(run_expect_value!("true xor false", Bool));
+    assert!(run_expect_value!("false xor true", Bool));
+    assert!(!run_expect_value!("false xor false", Bool));
+    assert!(VM::new().interpret("1 xor 2").is_err());
+}
+
+#[test]
+fn test_replace_n() {
+    assert_eq!(run_expect_value!("replace_first(\"a-b-c\", \"-\", \"+\")", Str), "a+b-c");
+    assert_eq!(run_expect_value!("replace_n(\"a-b-c-d\", \"-\", \"\", 2)", Str), "abc-d");
+    // n larger than the number of occurrences replaces all of them, and n = 0 changes nothing
+    assert_eq!(run_expect_value!("replace_n(\"a-b-c\", \"-\", \"+\", 10)", Str), "a+b+c");
+    assert_eq!(run_expect_value!("replace_n(\"a-b-c\", \"-\", \"+\", 0)", Str), "a-b-c");
+    assert!(VM::new().interpret("replace_first(\"abc\", \"\", \"+\")").is_err());
+    assert!(VM::new().interpret("replace_n(\"abc\", \"b\", \"+\", -1)").is_err());
+}
+
+#[test]
+fn test_iter_equality() {
+    assert!(run_expect_value!("(1 to 3) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("[1, 2, 3] = (1 to 3)", Bool));
+    assert!(run_expect_value!("(1 to 3) = (1 to 3)", Bool));
+    assert!(run_expect_value!("(1 to 3) != [1, 2]", Bool));
+    assert!(run_expect_value!("lazy([\"a\", \"b\"]) = [\"a\", \"b\"]", Bool));
+    assert!(run_expect_value!("lazy([0.5, 1.5]) = [0.5, 1.5]", Bool));
+    // an iterator can still be used after being compared
+    assert!(run_expect_value!("x := 1 to 3 x = [1, 2, 3] and sum(x) = 6", Bool));
+    assert!(VM::new().interpret("(1 to 3) = [1.0, 2.0, 3.0]").is_err());
+}
+
+#[test]
+fn test_find_index() {
+    assert_eq!(run_expect_value!("unwrap(find_index(|x: Int| { x > 2 }, [1, 5, 3, 7]), -1)", Int), 1);
+    assert_eq!(run_expect_value!("unwrap(find_index(|s: Str| { s = \"b\" }, [\"a\", \"b\", \"b\"]), -1)", Int), 1);
+    assert!(!run_expect_value!("issome(find_index(|x: Int| { x > 10 }, [1, 2, 3]))", Bool));
+    assert!(!run_expect_value!("issome(find_index(|x: Int| { x > 0 }, ([]): Arr(Int)))", Bool));
+    // stops at the first match, so iterators don't have to be finite
+    assert_eq!(run_expect_value!("unwrap(find_index(|x: Int| { x * x > 50 }, |x: Int| { x } -> 0 to 1000000000), -1)", Int), 8);
+    assert!(VM::new().interpret("find_index(|x: Int| { x }, [1, 2])").is_err());
+}
+
+#[test]
+fn test_clamp01_lerp() {
+    assert_eq!(run_expect_value!("clamp01(1.5)", Float), 1.0);
+    assert_eq!(run_expect_value!("clamp01(-0.5)", Float), 0.0);
+    assert_eq!(run_expect_value!("clamp01(0.25)", Float), 0.25);
+    assert_eq!(run_expect_value!("lerp(0.0, 10.0, 0.5)", Float), 5.0);
+    assert_eq!(run_expect_value!("lerp(2.0, 4.0, 0.0)", Float), 2.0);
+    assert_eq!(run_expect_value!("lerp(2.0, 4.0, 1.5)", Float), 5.0);
+    assert!(VM::new().interpret("lerp(0, 10, 1)").is_err());
+}
+
+#[test]
+fn test_range_endpoints() {
+    assert!(run_expect_value!("@(5 to 5) = [5]", Bool));
+    assert!(run_expect_value!("@(1 to 1) = [1]", Bool));
+    assert!(run_expect_value!("@(3 to 1) = [3, 2, 1]", Bool));
+    assert!(run_expect_value!("@(-1 to -3) = [-1, -2, -3]", Bool));
+    assert_eq!(run_expect_value!("len(5 to 5)", Int), 1);
+    assert_eq!(run_expect_value!("len(3 to 1)", Int), 3);
+}
+
+#[test]
+fn test_run_length() {
+    let source = "
+    Run := type { value: Str, count: Int }
+    runs := run_length([\"a\", \"a\", \"b\", \"c\", \"c\", \"c\"])
+    @(|r: Run| { r.value } -> runs) = [\"a\", \"b\", \"c\"] and @(|r: Run| { r.count } -> runs) = [2, 1, 3]
+    ";
+    assert!(run_expect_value!(source, Bool));
+    let source = "
+    Run := type { value: Int, count: Int }
+    @(|r: Run| { r.count } -> run_length(|x: Int| { x / 3 } -> 0 to 7)) = [3, 3, 2]
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert_eq!(run_expect_value!("len(run_length([true, false, false, true]))", Int), 3);
+    assert_eq!(run_expect_value!("len(run_length(([]): Arr(Float)))", Int), 0);
+}
+
+#[test]
+fn test_char_code() {
+    assert_eq!(run_expect_value!("char_code(\"A\")", Int), 65);
+    assert_eq!(run_expect_value!("char_code(\"héllo\")", Int), 104);
+    assert_eq!(run_expect_value!("char_code(\"é\")", Int), 233);
+    assert_eq!(run_expect_value!("char_code(\"€\")", Int), 0x20AC);
+    assert_eq!(&*run_expect_value!("unwrap(from_char_code(97), \"\")", Str), "a");
+    assert_eq!(&*run_expect_value!("unwrap(from_char_code(char_code(\"€\")), \"\")", Str), "€");
+    // a surrogate, a negative number, and one past the last code point
+    assert!(!run_expect_value!("issome(from_char_code(55296))", Bool));
+    assert!(!run_expect_value!("issome(from_char_code(-1))", Bool));
+    assert!(!run_expect_value!("issome(from_char_code(1114112))", Bool));
+    assert!(VM::new().interpret("char_code(\"\")").is_err());
+}
+
+#[test]
+fn test_print_trace() {
+    let source = "f := |x: Int| { [1](x) }\nf(3)";
+    let mut vm = VM::new();
+    assert!(!vm.print_trace);
+    let quiet = vm.interpret(source).unwrap_err();
+    vm.set_print_trace(true);
+    let printed = vm.interpret(source).unwrap_err();
+    // the flag only controls printing; the error carries the trace either way
+    assert_eq!(quiet.to_string(), printed.to_string());
+
+    // the binary prints the error it gets back, so by default the trace should appear in its output exactly once
+    let file = TempFile::new("test_print_trace.hl", source);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_henrylang")).arg(&file.0).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("Function call trace:").count(), 1);
+    assert_eq!(stdout.trim_end(), quiet.to_string());
+}
+
+#[test]
+fn test_chunk() {
+    assert!(run_expect_value!("chunk([1, 2, 3, 4, 5], 2) = [[1, 2], [3, 4], [5]]", Bool));
+    assert!(run_expect_value!("chunk([1, 2, 3, 4], 2) = [[1, 2], [3, 4]]", Bool));
+    assert!(run_expect_value!("chunk([\"a\", \"b\", \"c\"], 5) = [[\"a\", \"b\", \"c\"]]", Bool));
+    assert_eq!(run_expect_value!("len(chunk(([]): Arr(Float), 3))", Int), 0);
+    assert!(VM::new().interpret("chunk([1, 2], 0)").is_err());
+}
+
+#[test]
+fn test_field_names() {
+    assert!(run_expect_value!("P := type { name: Str, age: Int, tags: Arr(Str) } field_names(P(\"Ann\", 30, [\"x\"])) = [\"name\", \"age\", \"tags\"]", Bool));
+    assert!(run_expect_value!("Q := type { z: Int, a: Float } q := Q(1, 2.0) field_names(q with {z: 3}) = [\"z\", \"a\"]", Bool));
+    assert!(VM::new().interpret("field_names([1, 2])").is_err());
+}
+
+#[test]
+fn test_take_drop() {
+    assert!(run_expect_value!("@take([1, 2, 3, 4], 2) = [1, 2]", Bool));
+    assert!(run_expect_value!("@drop(1 to 5, 2) = [3, 4, 5]", Bool));
+    assert!(run_expect_value!("@take(1 to 3, 10) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("len(@drop([\"a\", \"b\"], 10)) = 0", Bool));
+    assert!(run_expect_value!("len(@take(1 to 3, 0)) = 0", Bool));
+    // take doesn't consume more of the inner iterator than it needs
+    assert!(run_expect_value!("@take(|x: Int| {x * 2} -> 0 to 1000000000, 3) = [0, 2, 4]", Bool));
+    assert!(run_expect_value!("sum(take(drop(1 to 10, 2), 3)) = 12", Bool));
+    assert!(VM::new().interpret("take([1, 2], -1)").is_err());
+    assert!(VM::new().interpret("drop(1 to 3, -1)").is_err());
+}
+
+#[test]
+fn test_split_once() {
+    let source = "
+    Split := type { before: Str, after: Str }
+    part := |s: Str, sep: Str| { unwrap(split_once(s, sep), Split(\"?\", \"?\")) }
+    kv := part(\"key=value=x\", \"=\")
+    start := part(\"=abc\", \"=\")
+    end := part(\"abc: \", \": \")
+    kv.before = \"key\" and kv.after = \"value=x\"
+        and start.before = \"\" and start.after = \"abc\"
+        and end.before = \"abc\" and end.after = \"\"
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert!(!run_expect_value!("issome(split_once(\"abc\", \"=\"))", Bool));
+}
+
+#[test]
+fn test_zip() {
+    let source = "
+    Pair := type { first: Int, second: Str }
+    pairs := zip(0 to 10, chars(\"abc\"))
+    @(|p: Pair| { p.first } -> pairs) = [0, 1, 2] and @(|p: Pair| { p.second } -> pairs) = [\"a\", \"b\", \"c\"]
+    ";
+    assert!(run_expect_value!(source, Bool));
+    let source = "
+    Pair := type { first: Str, second: Float }
+    @(|p: Pair| { p.second } -> zip([\"x\", \"y\"], [1.5, 2.5, 3.5])) = [1.5, 2.5]
+    ";
+    assert!(run_expect_value!(source, Bool));
+    assert_eq!(run_expect_value!("len(zip([1, 2, 3], ([]): Arr(Bool)))", Int), 0);
+    assert!(VM::new().interpret("zip(1, [1])").is_err());
+}
+
+#[test]
+fn test_interpret_tokens() {
+    let source = "f := |x: Int| { x * 2 } @(f -> 1 to 3)";
+    let tokens = scan(source);
+    assert_eq!(
+        VM::new().interpret_tokens(tokens).unwrap().to_string(),
+        VM::new().interpret(source).unwrap().to_string()
+    );
+    // tokens can also be built by hand
+    let tokens = vec![
+        Token { ttype: TokenType::Int, line: 1, text: "1".to_string() },
+        Token { ttype: TokenType::Plus, line: 1, text: "+".to_string() },
+        Token { ttype: TokenType::Int, line: 1, text: "2".to_string() },
+        Token { ttype: TokenType::EoF, line: 1, text: "".to_string() },
+    ];
+    match VM::new().interpret_tokens(tokens).unwrap() {
+        values::TaggedValue::Int(x) => assert_eq!(x, 3),
+        _ => panic!("Should be an Int"),
+    }
+}
+
+#[test]
+fn test_abs_diff() {
+    assert_eq!(run_expect_value!("abs_diff(3, 10)", Int), 7);
+    assert_eq!(run_expect_value!("abs_diff(10, 3)", Int), 7);
+    assert_eq!(run_expect_value!("abs_diff(-5, 5)", Int), 10);
+    assert_eq!(run_expect_value!("abs_diff(1.5, 4.0)", Float), 2.5);
+    assert_eq!(run_expect_value!("abs_diff(4.0, 1.5)", Float), 2.5);
+}
+
+#[test]
+fn test_inspect() {
+    assert!(run_expect_value!("@inspect(|x: Int| { x * 2 }, 1 to 3) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("@inspect(|s: Str| { len(s) }, [\"a\", \"bc\"]) = [\"a\", \"bc\"]", Bool));
+    assert!(VM::new().interpret("inspect(|x: Float| { x }, [1])").is_err());
+
+    // run through the binary to see that the function is called once per element, as the elements are consumed
+    let source = "@inspect(|x: Int| { print(x) }, |x: Int| { x * 10 } -> 1 to 3)";
+    let file = TempFile::new("test_inspect.hl", source);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_henrylang")).arg(&file.0).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), ["10", "20", "30", "[10, 20, 30]"]);
+}
+
+#[test]
+fn test_len_literal_array() {
+    assert_eq!(run_expect_value!("len([1, 2, 3])", Int), 3);
+    assert_eq!(run_expect_value!("len(([]): Arr(Int))", Int), 0);
+    assert_eq!(run_expect_value!("len([[1], [2, 3]])", Int), 2);
+    // arrays with non-literal elements are still evaluated
+    assert_eq!(run_expect_value!("f := |x: Int| { x } len([f(1), f(2)])", Int), 2);
+    assert!(VM::new().interpret("len([1, 2.0])").is_err());
+
+    // the length is compiled as a constant, without building the array
+    let instructions = VM::new().instructions("len([1, 2, 3])").unwrap();
+    assert!(instructions.iter().any(|i| i.contains(" Constant ")));
+    assert!(!instructions.iter().any(|i| i.contains(" Array")));
+    let instructions = VM::new().instructions("len([[1], [2, 3]])").unwrap();
+    assert!(!instructions.iter().any(|i| i.contains(" Array")));
+}
+
+#[test]
+fn test_center() {
+    assert!(run_expect_value!("center(\"ab\", 6, \"*\") = \"**ab**\"", Bool));
+    // odd padding puts the extra fill on the right
+    assert!(run_expect_value!("center(\"ab\", 5, \"*\") = \"*ab**\"", Bool));
+    assert!(run_expect_value!("center(\"héllo\", 9, \"·\") = \"··héllo··\"", Bool));
+    assert!(run_expect_value!("center(\"toolong\", 3, \" \") = \"toolong\"", Bool));
+    assert!(run_expect_value!("center_trunc(\"toolong\", 3, \" \") = \"too\"", Bool));
+    assert!(run_expect_value!("center_trunc(\"ab\", 4, \"-\") = \"-ab-\"", Bool));
+    assert!(VM::new().interpret("center(\"a\", 3, \"ab\")").is_err());
+    assert!(VM::new().interpret("center(\"a\", -1, \" \")").is_err());
+}
+
+#[test]
+fn test_maybe_func() {
+    let source = "
+    handle := |on_value: Maybe(Func(Int, Int)), x: Int| { unwrap(on_value, |y: Int| { y })(x) }
+    double := some(|x: Int| { x * 2 })
+    handle(double, 5) + handle({}:Func(Int, Int), 5)
+    ";
+    assert_eq!(run_expect_value!(source, Int), 15);
+    let source = "
+    T := type { on_value: Maybe(Func(Str, Str)) }
+    t := T(some(|s: Str| { s + \"!\" }))
+    match t.on_value { some(f) => f(\"hi\"), null => \"\" }
+    ";
+    assert_eq!(run_expect_value!(source, Str), "hi!".to_string());
+    // closures keep their captured values through the Maybe
+    assert_eq!(run_expect_value!("adder := |k: Int| { some(|x: Int| { x + k }) } unwrap(adder(5), |x: Int| { 0 })(1)", Int), 6);
+    assert_eq!(run_expect_value!("f := {}:Func(Int) unwrap(f, || { 3 })()", Int), 3);
+    assert!(VM::new().interpret("f := some(|x: Int| { x }) unwrap(f, |x: Int| { 1.0 })(1)").is_err());
+}
+
+#[test]
+fn test_reduce_count() {
+    let source = "
+    c := reduce_count(|acc: Int, x: Int| { acc + x }, 0, 1 to 100)
+    c.result = 5050 and c.count = 100
+    ";
+    assert!(run_expect_value!(source, Bool));
+    let source = "
+    Counted := type { result: Float, count: Int }
+    mean := |c: Counted| { c.result / float(c.count) }
+    mean(reduce_count(|acc: Float, x: Float| { acc + x }, 0.0, |x: Int| { float(x) } -> 1 to 4))
+    ";
+    assert_eq!(run_expect_value!(source, Float), 2.5);
+    assert!(run_expect_value!("c := reduce_count(|acc: Str, x: Str| { acc + x }, \"\", [\"a\", \"b\"]) c.result = \"ab\" and c.count = 2", Bool));
+    assert_eq!(run_expect_value!("reduce_count(|acc: Int, x: Int| { acc + x }, 7, ([]): Arr(Int)).count", Int), 0);
+    assert!(VM::new().interpret("reduce_count(|acc: Int, x: Int| { acc + x }, 0.0, [1])").is_err());
+}
+
+#[test]
+fn test_bit_ops() {
+    assert_eq!(run_expect_value!("popcount(255)", Int), 8);
+    assert_eq!(run_expect_value!("popcount(0)", Int), 0);
+    assert_eq!(run_expect_value!("shl(3, 4)", Int), 48);
+    assert_eq!(run_expect_value!("shr(100, 3)", Int), 12);
+    // shr keeps the sign
+    assert_eq!(run_expect_value!("shr(-16, 2)", Int), -4);
+    assert_eq!(run_expect_value!("band(12, 10)", Int), 8);
+    assert_eq!(run_expect_value!("bor(12, 10)", Int), 14);
+    assert_eq!(run_expect_value!("bxor(12, 10)", Int), 6);
+    // the interpreter works on 64 bits
+    assert_eq!(run_expect_value!("popcount(-1)", Int), 64);
+    assert_eq!(run_expect_value!("shl(1, 40)", Int), 1 << 40);
+}
+
+#[test]
+fn test_parse_ints() {
+    assert!(run_expect_value!("unwrap(parse_ints(\"1,2,3\", \",\"), [0]) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("unwrap(parse_ints(\"1, -2 , 30\", \",\"), [0]) = [1, -2, 30]", Bool));
+    assert_eq!(run_expect_value!("len(unwrap(parse_ints(\"\", \",\"), [0]))", Int), 0);
+    assert!(!run_expect_value!("issome(parse_ints(\"1,x,3\", \",\"))", Bool));
+    assert!(!run_expect_value!("issome(parse_ints(\"1,,3\", \",\"))", Bool));
+    assert!(VM::new().interpret("parse_ints(\"1 2\", \"\")").is_err());
+}
+
+#[test]
+fn test_wildcard_params() {
+    assert_eq!(run_expect_value!("f := |_: Int, x: Int, _: Str| { x } f(1, 2, \"c\")", Int), 2);
+    assert_eq!(run_expect_value!("f := |_: Str, _: Int| { 3 } f(\"a\", 2)", Int), 3);
+    assert!(run_expect_value!("@zipmap_indexed(|_: Int, x: Str| { x + \"!\" }, [\"a\", \"b\"]) = [\"a!\", \"b!\"]", Bool));
+    // `_` doesn't bind its argument
+    assert!(VM::new().interpret("f := |_: Int| { _ } f(1)").is_err());
+    assert!(VM::new().interpret("f := |x: Int, x: Int| { x } f(1, 2)").is_err());
+}
+
+#[test]
+fn test_concat_arr_iter() {
+    assert!(run_expect_value!("[1, 2] + (3 to 4) = [1, 2, 3, 4]", Bool));
+    assert!(run_expect_value!("(1 to 2) + [5] = [1, 2, 5]", Bool));
+    assert_eq!(run_expect_value!("len([1.5] + (|x: Float| { x * 0.5 } -> [1.0, 2.0]))", Int), 3);
+    assert!(run_expect_value!("[\"a\"] + (|x: Int| { \"b\" } -> [1, 2]) = [\"a\", \"b\", \"b\"]", Bool));
+    // the result is an array, so it can be indexed
+    assert_eq!(run_expect_value!("x := [1, 2] + (3 to 4) x(2)", Int), 3);
+    assert!(VM::new().interpret("[1, 2] + (1.0 to 3.0)").is_err());
+    assert!(VM::new().interpret("[1, 2] + [true]").is_err());
+}
+
+#[test]
+fn test_parse_errors_in_subexpressions() {
+    // a subexpression that fails to parse has no type, so type checks on its parent fail with an error
+    let sources = [
+        "|x: Int| { x } -> (1 + ",
+        "len(1 +)",
+        "f := |x: Int| { x + } f(1)",
+        "[1, +]",
+        "zip(1 to 3, )",
+    ];
+    for source in sources.iter() {
+        assert_eq!(VM::new().interpret(source).unwrap_err().kind(), ErrorKind::Parse);
+    }
+}
+
+#[test]
+fn test_is_finite_is_inf() {
+    assert!(run_expect_value!("is_finite(1.5)", Bool));
+    assert!(!run_expect_value!("is_finite(1.0 / 0.0)", Bool));
+    assert!(!run_expect_value!("is_finite(0.0 / 0.0)", Bool));
+    assert!(run_expect_value!("is_inf(1.0 / 0.0)", Bool));
+    assert!(run_expect_value!("is_inf(-1.0 / 0.0)", Bool));
+    assert!(!run_expect_value!("is_inf(0.0 / 0.0)", Bool));
+    assert!(!run_expect_value!("is_inf(-2.5)", Bool));
+}
+
+#[test]
+fn test_byte_len() {
+    assert_eq!(run_expect_value!("byte_len(\"hello\")", Int), 5);
+    assert_eq!(run_expect_value!("byte_len(\"héllo\")", Int), 6);
+    assert_eq!(run_expect_value!("len(\"héllo\")", Int), 5);
+    assert_eq!(run_expect_value!("byte_len(\"😀\")", Int), 4);
+    assert_eq!(run_expect_value!("byte_len(\"\")", Int), 0);
+}
+
+#[test]
+fn test_sort() {
+    assert!(run_expect_value!("sort([3, 1, 2]) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("sort_desc([3, 1, 2]) = [3, 2, 1]", Bool));
+    assert!(run_expect_value!("sort(3 to 1) = [1, 2, 3]", Bool));
+    assert!(run_expect_value!("sort_desc([\"b\", \"a\", \"c\"]) = [\"c\", \"b\", \"a\"]", Bool));
+    assert!(run_expect_value!("x := sort([true, false, true]) !x(0) and x(1) and x(2)", Bool));
+    // NaN goes last in either direction
+    assert!(run_expect_value!("x := sort_desc([1.5, 0.0 / 0.0, -2.0, 3.0]) x(0) = 3.0 and x(2) = -2.0 and x(3) != x(3)", Bool));
+    assert!(run_expect_value!("x := sort([1.5, 0.0 / 0.0, -2.0]) x(0) = -2.0 and x(2) != x(2)", Bool));
+}
+
+#[test]
+fn test_sort_by() {
+    assert!(run_expect_value!("sort_by([5, 3, 8, 1], |a: Int, b: Int| { b - a }) = [8, 5, 3, 1]", Bool));
+    assert!(run_expect_value!("f := |a: Int, b: Int| { a - b } sort_by(3 to 1, f) = [1, 2, 3]", Bool));
+    assert_eq!(run_expect_value!("len(sort_by(([]): Arr(Int), |a: Int, b: Int| { a - b }))", Int), 0);
+    // elements that compare equal keep their order
+    assert!(run_expect_value!("sort_by([\"bb\", \"a\", \"ccc\", \"dd\"], |a: Str, b: Str| { len(a) - len(b) }) = [\"a\", \"bb\", \"dd\", \"ccc\"]", Bool));
+    assert!(run_expect_value!("
+        P := type { k: Int, n: Str }
+        ps := sort_by([P(2, \"a\"), P(1, \"b\"), P(2, \"c\"), P(1, \"d\")], |a: P, b: P| { a.k - b.k })
+        @(|p: P| { p.n } -> ps) = [\"b\", \"d\", \"a\", \"c\"]
+    ", Bool));
+    assert!(run_expect_value!("sort_by([3, 1, 2], |a: Int, b: Int| { 0 }) = [3, 1, 2]", Bool));
+    assert!(VM::new().interpret("sort_by([0, 1], |a: Int, b: Int| { 1 / a })").is_err());
+    assert!(VM::new().interpret("sort_by([1], |a: Int| { 1 })").is_err());
+    assert!(VM::new().interpret("sort_by([1], |a: Int, b: Int| { 1.0 })").is_err());
+}
+
+#[test]
+fn test_resolved_names() {
+    let vm = VM::new();
+    let resolved = |source: &str| vm.resolved_names(source).unwrap();
+    assert_eq!(resolved("sum(1 to 3)"), vec![("sum".to_string(), "sum[Iter(Int)]".to_string())]);
+    assert_eq!(
+        resolved("sum(lazy([1.5, 2.5]))"),
+        vec![("lazy".to_string(), "lazy[Arr(Float)]".to_string()), ("sum".to_string(), "sum[Iter(Float)]".to_string())]
+    );
+    assert_eq!(
+        resolved("f := |x: Int| { x } f(1) + 1 sqrt(2.0)"),
+        vec![("f".to_string(), "f[Int]".to_string()), ("sqrt".to_string(), "sqrt[Float]".to_string())]
+    );
+    assert!(resolved("x := 1 x + 1").is_empty());
+    assert!(vm.resolved_names("sum(\"a\")").is_err());
+    // resolving names doesn't define any globals
+    assert!(vm.resolved_names("z := 2 z").unwrap().is_empty());
+    assert!(!vm.global_names().contains(&"z".to_string()));
+}
+
+#[test]
+fn test_modulo_operator() {
+    assert_eq!(run_expect_value!("7 % 3", Int), 1);
+    // result is always positive, like mod
+    assert_eq!(run_expect_value!("-7 % 3", Int), 2);
+    assert_eq!(run_expect_value!("7 % -3", Int), 1);
+    assert_eq!(run_expect_value!("-7 % -3", Int), 2);
+    // same precedence as * and /
+    assert_eq!(run_expect_value!("2 + 7 % 4 * 2", Int), 8);
+    assert_eq!(run_expect_value!("10 / 3 % 2", Int), 1);
+    assert!(run_expect_value!("all(|x: Int| { x % 4 = mod(x, 4) } -> -10 to 10)", Bool));
+    assert!(VM::new().interpret("1 % 0").is_err());
+    assert!(VM::new().interpret("7.0 % 2.0").is_err());
+    assert!(VM::new().interpret("\"a\" % \"b\"").is_err());
+}
+
+#[test]
+fn test_completion() {
+    let names = ["sqrt", "sub", "sum", "zip_longest"].map(String::from).to_vec();
+    assert_eq!(completion::complete_name("x := su", 7, &names), (5, vec!["sub".to_string(), "sum".to_string()]));
+    assert_eq!(completion::complete_name("zip_l(", 5, &names), (0, vec!["zip_longest".to_string()]));
+    assert_eq!(completion::complete_name("1 + ", 4, &names), (4, vec![]));
+    let globals = VM::new().global_names();
+    assert!(globals.contains(&"sum".to_string()));
+    assert!(globals.contains(&"filter".to_string()));
+    assert_eq!(completion::complete_name("@filt", 5, &globals), (1, vec!["filter".to_string()]));
+    assert!(!globals.iter().any(|name| name.contains('[')));
+}
+
+#[test]
+fn test_globals_list() {
+    let mut vm = VM::new();
+    vm.interpret("answer := 42").unwrap();
+    let globals = vm.globals_list();
+    assert!(globals.contains(&("answer".to_string(), Type::Int)));
+    assert!(globals.contains(&("sqrt[Float]".to_string(), Type::Func(vec![Type::Float], Box::new(Type::Float)))));
+    assert!(globals.windows(2).all(|w| w[0].0 < w[1].0));
+}
@@ -11,6 +11,16 @@ mod wasm_tests {
             Err(e) => panic!("Compile Error: {}", e),
         }
     }
+
+    fn run_err(source: &str) -> String {
+        match wasmize(source, Env::default()) {
+            Ok((bytes, typ)) => match run_wasm(&bytes, typ) {
+                Ok(x) => panic!("Expected a runtime error, but got {}", x),
+                Err(e) => e,
+            },
+            Err(e) => panic!("Compile Error: {}", e),
+        }
+    }
     
     #[test]
     fn test_arithmetic() {
@@ -32,6 +42,30 @@ mod wasm_tests {
         assert_eq!(run("a := [[1,2], [3]] a(0) + a(1)"), "[1, 2, 3]");
     }
     
+    #[test]
+    fn test_bool_arrays() {
+        assert_eq!(run("[true, false, true]"), "[true, false, true]");
+        assert_eq!(run("a := [true, false, true] a(1)"), "false");
+        assert_eq!(run("a := [true, false, true] len(a)"), "3");
+        assert_eq!(run("a := [true, false, true] a = [true, false, true]"), "true");
+        assert_eq!(run("a := [true, false, true] a = [true, true, true]"), "false");
+        assert_eq!(run("[true] + [false, true]"), "[true, false, true]");
+    }
+
+    #[test]
+    fn test_float_array_equality() {
+        for source in [
+            "[0.0, 1.0] = [-0.0, 1.0]",
+            "nan := 0.0 / 0.0 [nan] = [nan]",
+            "nan := 0.0 / 0.0 [nan] != [nan]",
+            "[1.5, 2.5] = [1.5, 2.5]",
+            "[1.5, 2.5] != [1.5]",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
     #[test]
     fn test_strings() {
         assert_eq!(run("\"Hello, World!\""), "Hello, World!");
@@ -122,6 +156,11 @@ mod wasm_tests {
     fn test_ranges() {
         assert_eq!(run("@(0 to 3)"), "[0, 1, 2, 3]");
         assert_eq!(run("@(0 to -3)"), "[0, -1, -2, -3]");
+        // ranges with equal endpoints yield that one value, in either direction
+        let sources = ["@(5 to 5)", "@(1 to 1)", "@(3 to 1)", "@(-1 to -3)", "@(-2 to 0)", "len(5 to 5)", "sum(3 to 1)"];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
     }
     
     #[test]
@@ -215,6 +254,12 @@ mod wasm_tests {
         assert_eq!(run("sqrt(4.0)"), "2.0");
         assert_eq!(run("mod(5, 3)"), "2");
         assert_eq!(run("mod(-5, 3)"), "1");
+        assert_eq!(run("pow(2.0, 0)"), "1.0");
+        assert_eq!(run("pow(2.0, 1)"), "2.0");
+        assert_eq!(run("pow(1.5, 5)"), "7.59375");
+        assert_eq!(run("pow(2.0, -2)"), "0.25");
+        assert_eq!(run("hypot(3.0, 4.0)"), "5.0");
+        assert_eq!(run("atan2(1.0, 0.0) * 2.0 > 3.14 and atan2(0.0, 1.0) = 0.0"), "true");
         assert_eq!(run("sum(0 to 100)"), "5050");
         assert_eq!(run("sum(|x:Int|{float(x)} -> 0 to 100)"), "5050.0");
         assert_eq!(run("prod(1 to 3)"), "6");
@@ -268,4 +313,445 @@ mod wasm_tests {
     
         assert_eq!(result, "2.718282");
     }
+    
+    #[test]
+    fn test_type_ascription() {
+        assert_eq!(run("[1, 2] : Arr(Int)"), "[1, 2]");
+        assert_eq!(run("x := ([]): Arr(Int) len(x)"), "0");
+    }
+
+    #[test]
+    fn test_with_index() {
+        assert_eq!(run("@with_index(|i: Int, x: Int| { i * x }, [5, 6, 7])"), "[0, 6, 14]");
+        assert_eq!(run("sum(with_index(|i: Int, x: Int| { i + x }, 10 to 12))"), "36");
+        assert_eq!(run("@zipmap_indexed(|i: Int, a: Int, b: Int| { 100 * i + a + b }, [1, 2, 3], 10 to 11)"), "[11, 113]");
+    }
+
+    #[test]
+    fn test_array_repeat() {
+        assert_eq!(run("[0; 5]"), "[0, 0, 0, 0, 0]");
+        assert_eq!(run("[1.5; 2] = [1.5, 1.5]"), "true");
+        assert_eq!(run("x := [\"hi\"; 3] x(2) + x(0)"), "hihi");
+        assert_eq!(run("len([7; 100])"), "100");
+    }
+
+    #[test]
+    fn test_int_literal_range() {
+        assert_eq!(run("2147483647"), "2147483647");
+        let err = wasmize("2147483648", Env::default()).unwrap_err();
+        assert!(err.contains("Integer literal exceeds 32-bit range for wasm backend"));
+    }
+
+    #[test]
+    fn test_reverse() {
+        for source in [
+            "reverse([1, 2, 3, 4])",
+            "reverse([1.5, -2.5])",
+            "x := [5, 6, 7] y := reverse(x) y(0) * 100 + x(0)",
+            "len(reverse(([]): Arr(Int)))",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    #[test]
+    fn test_bool_int_conversions() {
+        for source in [
+            "int(true) + int(false) * 10",
+            "bool(2) = true",
+            "bool(0)",
+            "bool(-3) and bool(int(true))",
+            "if bool(5) { 1 } else { 0 }",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    #[test]
+    fn test_flat_map() {
+        for source in [
+            "@flat_map(|x: Int| { [x, x * 10] }, [1, 2, 3])",
+            "@flat_map(|n: Int| { @(0 to n) }, 0 to 3)",
+            "@flat_map(|x: Int| { @filter(|y: Int| { y > 5 }, [x]) }, [1, 7, 3, 9])",
+            "@flat_map(|x: Float| { [x, -x] }, [1.5, 2.5])",
+            "f := |x: Int| { [x, 1] } sum(flat_map(f, 1 to 4))",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    fn count_functions(source: &str) -> u32 {
+        let (bytes, _) = wasmize(source, Env::default()).unwrap();
+        let read_leb128 = |i: &mut usize| {
+            let (mut result, mut shift) = (0u32, 0);
+            loop {
+                let byte = bytes[*i];
+                *i += 1;
+                result |= ((byte & 0x7f) as u32) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    return result;
+                }
+            }
+        };
+        // skip magic number and version, then walk sections until the function section
+        let mut i = 8;
+        while i < bytes.len() {
+            let section_id = bytes[i];
+            i += 1;
+            let size = read_leb128(&mut i) as usize;
+            if section_id == 3 {
+                return read_leb128(&mut i);
+            }
+            i += size;
+        }
+        0
+    }
+
+    // reads the function names from the module's custom name section
+    fn function_names(source: &str) -> Vec<String> {
+        let (bytes, _) = wasmize(source, Env::default()).unwrap();
+        let read_leb128 = |i: &mut usize| {
+            let (mut result, mut shift) = (0u32, 0);
+            loop {
+                let byte = bytes[*i];
+                *i += 1;
+                result |= ((byte & 0x7f) as u32) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    return result;
+                }
+            }
+        };
+        let read_string = |i: &mut usize| {
+            let len = read_leb128(i) as usize;
+            let s = String::from_utf8(bytes[*i..*i + len].to_vec()).unwrap();
+            *i += len;
+            s
+        };
+        let mut i = 8;
+        while i < bytes.len() {
+            let section_id = bytes[i];
+            i += 1;
+            let size = read_leb128(&mut i) as usize;
+            let section_end = i + size;
+            if section_id == 0 && read_string(&mut i) == "name" {
+                // function names are in subsection 1
+                assert_eq!(bytes[i], 1);
+                i += 1;
+                read_leb128(&mut i);
+                let n_names = read_leb128(&mut i);
+                return (0..n_names).map(|_| {
+                    read_leb128(&mut i);
+                    read_string(&mut i)
+                }).collect();
+            }
+            i = section_end;
+        }
+        vec![]
+    }
+
+    #[test]
+    fn test_name_section() {
+        let names = function_names("square := |x: Int| { x * x } square(3)");
+        for name in ["main", "square[Int]", "print[Int]", "alloc"] {
+            assert!(names.iter().any(|n| n == name), "{} not in {:?}", name, names);
+        }
+        // the name section shouldn't affect execution
+        assert_eq!(run("square := |x: Int| { x * x } square(3)"), "9");
+    }
+
+    #[test]
+    fn test_count_where() {
+        assert_eq!(run("count_where(|x: Int| { x / 2 * 2 = x }, 0 to 10)"), "6");
+        assert_eq!(run("count_where(|x: Int| { x > 0 }, ([]): Arr(Int))"), "0");
+        assert_eq!(run("f := |x: Float| { x > 1.0 } count_where(f, [0.5, 1.5, 2.5])"), "2");
+    }
+
+    #[test]
+    fn test_shared_builtins() {
+        // Int and Bool have the same wasm representation, so their iterators should share functions
+        assert_eq!(
+            count_functions("a := @(|x: Int| { x } -> [1, 2]) b := @(|x: Bool| { x } -> [true, false]) len(a) + len(b)"),
+            count_functions("a := @(|x: Int| { x } -> [1, 2]) b := @(|x: Int| { x } -> [3, 4]) len(a) + len(b)"),
+        );
+        // types with the same field layout should share a constructor
+        assert_eq!(
+            count_functions("A := type {x: Int, y: Float} B := type {u: Int, v: Float} a := A(1, 2.0) b := B(3, 4.0) a.x + b.u"),
+            count_functions("A := type {x: Int, y: Float} a := A(1, 2.0) a.x"),
+        );
+        assert_eq!(
+            run("A := type {x: Int, y: Float} B := type {u: Int, v: Float} a := A(1, 2.0) b := B(3, 4.0) a.x + b.u"),
+            "4"
+        );
+    }
+
+    #[test]
+    fn test_inline_functions() {
+        // function literals are inlined into the iterator, while named functions are called indirectly
+        let pairs = [
+            ("@(|x: Int| { x * 2 + 1 } -> 0 to 5)", "f := |x: Int| { x * 2 + 1 } @(f -> 0 to 5)"),
+            ("@(|x: Float| { -x / 2.0 } -> [1.0, 3.0])", "f := |x: Float| { -x / 2.0 } @(f -> [1.0, 3.0])"),
+            ("len(filter(|x: Int| { !(x > 2) }, [1, 3, 5]))", "f := |x: Int| { !(x > 2) } len(filter(f, [1, 3, 5]))"),
+            ("@filter(|x: Int| { x / 2 * 2 = x }, 0 to 10)", "f := |x: Int| { x / 2 * 2 = x } @filter(f, 0 to 10)"),
+            (
+                "sum(|x: Int| { x * x } -> filter(|x: Int| { x > 3 }, 1 to 6))",
+                "f := |x: Int| { x * x } g := |x: Int| { x > 3 } sum(f -> filter(g, 1 to 6))",
+            ),
+        ];
+        for (inlined, called) in pairs {
+            assert_eq!(run(inlined), run(called));
+            assert!(count_functions(inlined) < count_functions(called));
+        }
+        // functions that capture variables are not inlined
+        assert_eq!(run("k := 3 @(|x: Int| { x * k } -> 0 to 3)"), "[0, 3, 6, 9]");
+    }
+
+    #[test]
+    fn test_int_sum_width() {
+        // intermediate totals beyond the range of an Int are fine if the final total fits
+        let source = "sum(|x: Int| { if x < 3 { 2000000000 } else { -2000000000 } } -> 0 to 4)";
+        let expected = VM::new().interpret(source).unwrap().to_string();
+        assert_eq!(run(source), expected);
+        // the VM's Int is 64 bits, but wasm reports an overflow rather than wrapping
+        assert_eq!(VM::new().interpret("sum(1 to 100000)").unwrap().to_string(), "5000050000");
+        assert_eq!(run_err("sum(1 to 100000)"), "Integer overflow");
+    }
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(run("add := |a: Int, b: Int| { a + b } @scan(add, 0, 1 to 4)"), "[1, 3, 6, 10]");
+        for source in [
+            "@scan(|acc: Float, x: Int| { acc * 2.0 }, 1.0, [1, 2, 3])",
+            "@scan(|acc: Int, x: Int| { acc + x }, 5, ([]): Arr(Int))",
+            "x := @scan(|acc: Arr(Int), x: Int| { acc + [x] }, ([]): Arr(Int), 1 to 3) x(2)",
+            "sum(scan(|acc: Int, x: Int| { acc * x }, 1, 1 to 5))",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    #[test]
+    fn test_first_or() {
+        for source in [
+            "first_or(5 to 10, 0)",
+            "first_or(filter(|x: Int| { x > 100 }, 0 to 10), -1)",
+            "first_or(filter(|x: Float| { x > 100.0 }, [1.0]), 2.5)",
+            "first_or(|x: Int| { \"s\" } -> 1 to 3, \"none\")",
+            "first_or(|x: Int| { \"s\" } -> filter(|x: Int| { x > 100 }, 0 to 10), \"none\")",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    #[test]
+    fn test_xor() {
+        assert_eq!(run("[true xor true, true xor false, false xor true, false xor false]"), "[false, true, true, false]");
+    }
+
+    #[test]
+    fn test_with_index_typedef() {
+        // there is no separate `enumerate`; passing a type definition to `with_index` builds index/value objects
+        for source in [
+            "E := type { index: Int, value: Int } sum(|e: E| { e.index * e.value } -> with_index(E, [5, 6, 7]))",
+            "E := type { index: Int, value: Float } x := @with_index(E, [1.5, 2.5]) x(1).value",
+            "E := type { index: Int, value: Bool } count_where(|e: E| { e.value and e.index > 0 }, with_index(E, [true, true, false]))",
+            "E := type { index: Int, value: Str } x := @with_index(E, [\"a\", \"b\"]) x(1).value = \"b\" and x(1).index = 1",
+        ] {
+            let expected = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), expected);
+        }
+    }
+
+    #[test]
+    fn test_clamp01_lerp() {
+        assert_eq!(run("lerp(0.0, 10.0, 0.5) = 5.0"), "true");
+        let sources = [
+            "clamp01(1.5)",
+            "clamp01(-0.5)",
+            "clamp01(0.25)",
+            "lerp(0.0, 10.0, 0.5)",
+            "lerp(2.0, 4.0, 1.5)",
+            "f := |x: Float| { lerp(-1.0, 1.0, clamp01(x)) }\nf(2.0) + f(0.75)",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_chunk() {
+        let sources = [
+            "chunk([1, 2, 3, 4, 5], 2)",
+            "chunk([1, 2, 3, 4], 2)",
+            "chunk(@(0 to 6), 3)",
+            "chunk([1, 2, 3], 10)",
+            "chunk([1.5, 2.5, 3.5], 2)",
+            "chunk([true, false, true], 1)",
+            "chunk([\"a\", \"b\", \"c\"], 2)",
+            "len(chunk([1, 2, 3, 4, 5, 6, 7], 3))",
+            "sum(|c: Arr(Int)| { len(c) } -> chunk(@(1 to 10), 4))",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+        assert_eq!(run_err("chunk([1, 2], 0)"), "Reached unreachable code");
+    }
+
+    #[test]
+    fn test_take_drop() {
+        assert_eq!(run("@take(1 to 1000, 3)"), "[1, 2, 3]");
+        assert_eq!(run("@drop(1 to 5, 2)"), "[3, 4, 5]");
+        let sources = [
+            "@take(1 to 3, 10)",
+            "@drop(1 to 3, 10)",
+            "@take(1 to 3, 0)",
+            "@drop(1 to 3, 0)",
+            "@drop(|x: Int| {x > 2} -> 1 to 4, 1)",
+            "sum(take(drop(1 to 10, 2), 3))",
+            "@take([1, 2, 3], 2)",
+            "@drop([1.5, 2.0, 3.0], 1)",
+            "@take([true, false, true], 5)",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+        assert_eq!(run_err("@take(1 to 3, -1)"), "Reached unreachable code");
+    }
+
+    #[test]
+    fn test_abs_diff() {
+        let sources = [
+            "abs_diff(3, 10)",
+            "abs_diff(10, 3)",
+            "abs_diff(-5, 5)",
+            "abs_diff(2, 2)",
+            "abs_diff(1.5, 4.0)",
+            "abs_diff(4.0, 1.5)",
+            "sum(|x: Int| { abs_diff(x, 2) } -> 0 to 4)",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_len_literal_array() {
+        // the length of a literal array is folded into a constant, so the array is never built
+        let (folded, _) = wasmize("len([1, 2, 3])", Env::default()).unwrap();
+        let (constant, _) = wasmize("3", Env::default()).unwrap();
+        assert_eq!(folded, constant);
+        assert_eq!(run("len(([]): Arr(Float))"), "0");
+        assert_eq!(run("f := |x: Int| { x } len([f(1), f(2)])"), "2");
+    }
+
+    #[test]
+    fn test_bit_ops() {
+        let sources = [
+            "popcount(255)",
+            "popcount(0)",
+            "shl(3, 4)",
+            "shr(100, 3)",
+            "shr(-16, 2)",
+            "band(12, 10)",
+            "bor(12, 10)",
+            "bxor(12, 10)",
+            "sum(|x: Int| { popcount(band(x, 6)) } -> 0 to 7)",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+        // wasm Ints are 32 bits
+        assert_eq!(run("popcount(-1)"), "32");
+    }
+
+    #[test]
+    fn test_wildcard_params() {
+        assert_eq!(run("f := |_: Int, x: Int, _: Float| { x } f(1, 2, 3.0)"), "2");
+        assert_eq!(run("@zipmap_indexed(|_: Int, x: Int| { x * 2 }, [3, 4])"), "[6, 8]");
+    }
+
+    #[test]
+    fn test_zip() {
+        let sources = [
+            "Pair := type { first: Int, second: Int } f := |p: Pair| { p.first + p.second } @(f -> zip(1 to 3, 10 to 14))",
+            "p := @(zip(1 to 3, [true, false])) p(1).second",
+            "Pair := type { first: Int, second: Float } f := |p: Pair| { p.second } @(f -> zip([1, 2], [0.5, 1.5, 2.5]))",
+            // zips of different types, and a user-defined `Pair`, each keep their own layout
+            "a := @(zip([1, 2], [3, 4])) b := @(zip([0.5], [1.5])) a(1).second",
+            "a := @(zip([1, 2], [3, 4])) b := @(zip([0.5], [1.5])) b(0).second",
+            "Pair := type { x: Float, y: Float } p := Pair(0.5, 1.5) q := @(zip([1], [2])) p.y",
+        ];
+        for source in sources.iter() {
+            let vm_result = VM::new().interpret(source).unwrap().to_string();
+            assert_eq!(run(source), vm_result);
+        }
+    }
+
+    #[test]
+    fn test_is_finite_is_inf() {
+        let sources = [
+            "is_finite(1.5)",
+            "is_finite(1.0 / 0.0)",
+            "is_finite(0.0 / 0.0)",
+            "is_inf(1.0 / 0.0)",
+            "is_inf(-1.0 / 0.0)",
+            "is_inf(0.0 / 0.0)",
+            "is_inf(-2.5)",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_zipmap_arrays_and_ranges() {
+        let sources = [
+            "@zipmap(|a: Int, b: Int, c: Float| { float(a * b) + c }, [1, 2, 3], 10 to 20, [0.5, 0.25, 0.125])",
+            "@zipmap(|a: Int, b: Int| { a - b }, 1 to 4, [10, 20])",
+            "sum(zipmap(|a: Int, b: Int| { a * b }, [1, 2, 3], 1 to 3))",
+            "@zipmap_indexed(|i: Int, x: Int, y: Int| { i + x * y }, 5 to 7, [1, 2, 3])",
+        ];
+        for source in sources {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_byte_len() {
+        assert_eq!(run("byte_len(\"héllo\")"), "6");
+        assert_eq!(run("len(\"héllo\")"), "5");
+        assert_eq!(run("byte_len(\"😀\")"), "4");
+        assert_eq!(run("x := \"日本\" byte_len(x) - len(x)"), "4");
+    }
+
+    #[test]
+    fn test_modulo_operator() {
+        for source in [
+            "7 % 3",
+            "-7 % 3",
+            "7 % -3",
+            "-7 % -3",
+            "mod(7, -3)",
+            "2 + 7 % 4 * 2",
+            "sum(|x: Int| { x % 4 } -> -10 to 10)",
+        ] {
+            assert_eq!(run(source), VM::new().interpret(source).unwrap().to_string(), "{}", source);
+        }
+        assert_eq!(run_err("1 % 0"), "Integer division by zero");
+    }
+
+    #[test]
+    fn test_traps() {
+        assert_eq!(run_err("1 / 0"), "Integer division by zero");
+        assert_eq!(
+            run_err("f := |x: Int|: Int { f(x + 1) } f(0)"),
+            "Stack overflow; this may be caused by infinite recursion"
+        );
+    }
 }